@@ -0,0 +1,24 @@
+use std::{env, fs, path::Path};
+
+/// Expose the locked helium-proto revision as `HELIUM_PROTO_REV` so the
+/// `version` command can report which proto definitions were compiled in.
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("cargo manifest dir");
+    let lock_path = Path::new(&manifest_dir).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    let revision = fs::read_to_string(&lock_path)
+        .ok()
+        .and_then(|lock| proto_revision(&lock))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=HELIUM_PROTO_REV={revision}");
+}
+
+fn proto_revision(lock: &str) -> Option<String> {
+    lock.split("[[package]]")
+        .find(|package| package.contains("name = \"helium-proto\""))?
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("source = "))
+        .and_then(|source| source.trim_matches('"').split_once('#'))
+        .map(|(_, revision)| revision.to_string())
+}
@@ -0,0 +1,259 @@
+//! Top level short forms, e.g. `skf add` for `route skfs add`, and user
+//! defined aliases, expanded before arguments are parsed.
+//!
+//! An aliases file has a `name = expansion` per line and `#` comments:
+//!
+//! ```text
+//! # list the routes of the OUI in HELIUM_OUI as a table
+//! rt = route list --output table
+//! ```
+//!
+//! Like git, only the command name, the first argument after any global
+//! flags, is expanded, and aliases never shadow a command. A malformed aliases
+//! file is ignored, and warned about once `--warnings-as-errors` is known.
+//!
+//! Aliases are kept out of the profiles file because they are expanded before
+//! arguments are parsed, while the profile is only known from the parsed
+//! `--profile`, which may itself come from an alias.
+
+use crate::{warning, Result};
+use anyhow::{anyhow, Context};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Used when `HELIUM_ALIASES` is not set, relative to `$HOME`.
+const DEFAULT_ALIASES_FILE: &str = ".config/helium-config-cli/aliases";
+
+const BUILTIN: [(&str, &str); 3] = [
+    ("skf", "route skfs"),
+    ("dev", "route devaddrs"),
+    ("eui", "route euis"),
+];
+
+pub struct Aliases {
+    aliases: Vec<(String, Vec<String>)>,
+    /// Why the aliases file was ignored
+    ignored: Option<String>,
+}
+
+impl Aliases {
+    /// The built in short forms, then the aliases in `path`, or in the default
+    /// aliases file when it exists.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut aliases = Self {
+            aliases: BUILTIN
+                .iter()
+                .map(|(name, expansion)| {
+                    let expansion = expansion.split(' ').map(String::from).collect();
+                    (name.to_string(), expansion)
+                })
+                .collect(),
+            ignored: None,
+        };
+        let path = match path {
+            Some(path) => Some(path),
+            None => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(DEFAULT_ALIASES_FILE))
+                .filter(|path| path.exists()),
+        };
+        if let Some(path) = path {
+            match Self::read(&path) {
+                Ok(user) => aliases.aliases.extend(user.aliases),
+                Err(err) => aliases.ignored = Some(format!("{err:#}, its aliases are ignored")),
+            }
+        }
+        aliases
+    }
+
+    /// Warn when the aliases file was ignored, once `--warnings-as-errors` is
+    /// set.
+    pub fn warn_ignored(&self) -> Result {
+        match &self.ignored {
+            Some(ignored) => warning::warn(ignored),
+            None => Ok(()),
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let data =
+            std::fs::read_to_string(path).context(format!("reading aliases {}", path.display()))?;
+        Self::parse(&data).context(format!("in aliases {}", path.display()))
+    }
+
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut aliases = vec![];
+        for (idx, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, expansion) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected `name = expansion`", idx + 1))?;
+            let name = name.trim();
+            let expansion: Vec<String> = expansion.split_whitespace().map(String::from).collect();
+            if name.is_empty() || name.contains(char::is_whitespace) || expansion.is_empty() {
+                return Err(anyhow!("line {}: expected `name = expansion`", idx + 1));
+            }
+            aliases.push((name.to_string(), expansion));
+        }
+        Ok(Self {
+            aliases,
+            ignored: None,
+        })
+    }
+
+    /// `args` with the command name replaced by its expansion. The last
+    /// definition of an alias wins, and names of `command`'s subcommands are
+    /// left alone.
+    pub fn expand(&self, mut args: Vec<OsString>, command: &clap::Command) -> Vec<OsString> {
+        let Some(idx) = command_name_index(&args, command) else {
+            return args;
+        };
+        let Some(name) = args[idx].to_str() else {
+            return args;
+        };
+        if command.find_subcommand(name).is_some() {
+            return args;
+        }
+        if let Some((_, expansion)) = self.aliases.iter().rev().find(|(alias, _)| alias == name) {
+            args.splice(idx..idx + 1, expansion.iter().map(OsString::from));
+        }
+        args
+    }
+}
+
+/// The index of the first argument after the binary name that is neither a
+/// flag of `command` nor a flag's value.
+fn command_name_index(args: &[OsString], command: &clap::Command) -> Option<usize> {
+    let mut idx = 1;
+    while let Some(arg) = args.get(idx) {
+        let arg = arg.to_str()?;
+        if arg == "--" {
+            return None;
+        }
+        let flag = if let Some(long) = arg.strip_prefix("--") {
+            (!long.contains('=')).then(|| {
+                command
+                    .get_arguments()
+                    .find(|flag| flag.get_long() == Some(long))
+            })
+        } else if let Some(short) = arg.strip_prefix('-').filter(|short| !short.is_empty()) {
+            let mut chars = short.chars();
+            match (chars.next(), chars.next()) {
+                (Some(short), None) => Some(
+                    command
+                        .get_arguments()
+                        .find(|flag| flag.get_short() == Some(short)),
+                ),
+                // `-kvalue` or combined short flags
+                _ => Some(None),
+            }
+        } else {
+            return Some(idx);
+        };
+        let takes_value = flag
+            .flatten()
+            .is_some_and(|flag| flag.get_action().takes_values());
+        idx += if takes_value { 2 } else { 1 };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Aliases;
+    use crate::{cmds::Cli, testing::fixtures};
+    use clap::CommandFactory;
+    use std::ffi::OsString;
+    use temp_dir::TempDir;
+
+    fn expand(aliases: &Aliases, args: &str) -> String {
+        let args = args.split(' ').map(OsString::from).collect();
+        let expanded = aliases.expand(args, &Cli::command());
+        expanded
+            .iter()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn aliases_with_builtins() -> Aliases {
+        Aliases::load(Some("/dev/null".into()))
+    }
+
+    #[test]
+    fn aliases_expand_first_argument() {
+        let aliases = aliases_with_builtins();
+        assert_eq!(
+            "cli route skfs add --route-id id",
+            expand(&aliases, "cli skf add --route-id id")
+        );
+        assert_eq!("cli route list skf", expand(&aliases, "cli route list skf"));
+
+        let mut aliases = Aliases::parse("# comment\nrt = route list --output table\n").unwrap();
+        aliases.aliases.extend(
+            Aliases::parse("route = org list\nrt = org ls")
+                .unwrap()
+                .aliases,
+        );
+        assert_eq!("cli org ls --oui 1", expand(&aliases, "cli rt --oui 1"));
+        // aliases never shadow a command
+        assert_eq!("cli route get", expand(&aliases, "cli route get"));
+
+        // global flags before the command name are skipped
+        assert_eq!(
+            "cli --keypair k.bin --quiet route skfs ls",
+            expand(
+                &aliases_with_builtins(),
+                "cli --keypair k.bin --quiet skf ls"
+            )
+        );
+        assert_eq!(
+            "cli --config-host=http://localhost org ls",
+            expand(&aliases, "cli --config-host=http://localhost rt")
+        );
+        assert_eq!("cli --keypair rt", expand(&aliases, "cli --keypair rt"));
+        assert_eq!("cli -- rt", expand(&aliases, "cli -- rt"));
+
+        assert!(Aliases::parse("rt route list").is_err());
+        assert!(Aliases::parse("rt =").is_err());
+    }
+
+    #[test]
+    fn malformed_aliases_file_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("aliases");
+        std::fs::write(&path, "rt route list\n").unwrap();
+        let aliases = Aliases::load(Some(path));
+        assert_eq!("cli route skfs ls", expand(&aliases, "cli skf ls"));
+        assert_eq!("cli rt", expand(&aliases, "cli rt"));
+        assert!(aliases.ignored.unwrap().contains("its aliases are ignored"));
+    }
+
+    #[test]
+    fn clap_aliases() {
+        use crate::cmds::{Commands, RouteCommands, SkfCommands};
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from(["cli", "r", "ls", "--oui", "1"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Route {
+                command: RouteCommands::List(_)
+            }
+        ));
+        let args = ["cli", "r", "skf", "ls", "--route-id", fixtures::ROUTE_ID];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Route {
+                command: RouteCommands::Skfs {
+                    command: SkfCommands::List(_)
+                }
+            }
+        ));
+    }
+}
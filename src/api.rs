@@ -0,0 +1,69 @@
+use crate::{
+    client::{OrgClient, RouteClient},
+    route::Route,
+    OrgList, OrgResponse, Oui, Result, RouteList,
+};
+use helium_crypto::Keypair;
+use std::future::Future;
+
+/// Route operations used by commands, implemented by [`RouteClient`] and by
+/// the in-memory fake in `testing`.
+pub trait RouteApi {
+    fn list(
+        &mut self,
+        oui: Oui,
+        keypair: &Keypair,
+    ) -> impl Future<Output = Result<RouteList>> + Send;
+    fn get(&mut self, id: &str, keypair: &Keypair) -> impl Future<Output = Result<Route>> + Send;
+    fn create_route(
+        &mut self,
+        route: Route,
+        keypair: &Keypair,
+    ) -> impl Future<Output = Result<Route>> + Send;
+    fn push(
+        &mut self,
+        route: Route,
+        keypair: &Keypair,
+    ) -> impl Future<Output = Result<Route>> + Send;
+    fn delete(&mut self, id: &str, keypair: &Keypair)
+        -> impl Future<Output = Result<Route>> + Send;
+}
+
+/// Org lookups used by commands, implemented by [`OrgClient`] and by the
+/// in-memory fake in `testing`.
+pub trait OrgApi {
+    fn list(&mut self) -> impl Future<Output = Result<OrgList>> + Send;
+    fn get(&mut self, oui: Oui) -> impl Future<Output = Result<OrgResponse>> + Send;
+}
+
+impl RouteApi for RouteClient {
+    async fn list(&mut self, oui: Oui, keypair: &Keypair) -> Result<RouteList> {
+        RouteClient::list(self, oui, keypair).await
+    }
+
+    async fn get(&mut self, id: &str, keypair: &Keypair) -> Result<Route> {
+        RouteClient::get(self, id, keypair).await
+    }
+
+    async fn create_route(&mut self, route: Route, keypair: &Keypair) -> Result<Route> {
+        RouteClient::create_route(self, route, keypair).await
+    }
+
+    async fn push(&mut self, route: Route, keypair: &Keypair) -> Result<Route> {
+        RouteClient::push(self, route, keypair).await
+    }
+
+    async fn delete(&mut self, id: &str, keypair: &Keypair) -> Result<Route> {
+        RouteClient::delete(self, id, keypair).await
+    }
+}
+
+impl OrgApi for OrgClient {
+    async fn list(&mut self) -> Result<OrgList> {
+        OrgClient::list(self).await
+    }
+
+    async fn get(&mut self, oui: Oui) -> Result<OrgResponse> {
+        OrgClient::get(self, oui).await
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    route::Route,
+    schema::{self, Versioned},
+    shared_file::{self, FileLock},
+    PrettyJson, Result,
+};
+use anyhow::{anyhow, Context};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Used when no backup directory is configured, relative to `$HOME`.
+const DEFAULT_BACKUP_DIR: &str = ".local/share/helium-config-cli/backups";
+
+/// Copies of Routes taken before committed changes.
+///
+/// Each Route gets a directory of `<unix millis>.json` files, oldest first.
+/// Routes are saved with their `auth_header` redacted.
+pub struct Backups {
+    dir: PathBuf,
+    retention: usize,
+}
+
+impl Backups {
+    /// `retention` is the number of backups kept per Route, 0 keeps all of them.
+    pub fn new(dir: Option<PathBuf>, retention: usize) -> Result<Self> {
+        let dir = match dir {
+            Some(dir) => dir,
+            None => {
+                let home = std::env::var_os("HOME")
+                    .ok_or_else(|| anyhow!("HOME is not set, configure a backup directory"))?;
+                PathBuf::from(home).join(DEFAULT_BACKUP_DIR)
+            }
+        };
+        Ok(Self { dir, retention })
+    }
+
+    fn route_dir(&self, route_id: &str) -> PathBuf {
+        self.dir.join(route_id)
+    }
+
+    /// Write `route` to a new backup file and prune old backups past retention.
+    ///
+    /// Saves of the same Route by concurrent invocations are serialized, so
+    /// neither overwrites the other's backup nor prunes one being written.
+    pub fn save(&self, route: &Route) -> Result<PathBuf> {
+        let route_dir = self.route_dir(&route.id);
+        fs::create_dir_all(&route_dir)
+            .context(format!("creating backup directory {}", route_dir.display()))?;
+        let _lock = FileLock::acquire(&route_dir)?;
+
+        let mut millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let mut path = route_dir.join(format!("{millis}.json"));
+        while path.exists() {
+            millis += 1;
+            path = route_dir.join(format!("{millis}.json"));
+        }
+        shared_file::write_atomic(&path, Versioned::new(route.redacted()).pretty_json()?)
+            .context(format!("writing backup {}", path.display()))?;
+
+        self.prune(&route.id)?;
+        Ok(path)
+    }
+
+    /// Route IDs with at least one backup.
+    pub fn routes(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut route_ids = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                route_ids.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        route_ids.sort();
+        Ok(route_ids)
+    }
+
+    /// Backup names for a Route, oldest first.
+    pub fn list(&self, route_id: &str) -> Result<Vec<String>> {
+        let route_dir = self.route_dir(route_id);
+        if !route_dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut backups: Vec<(u128, String)> = vec![];
+        for entry in fs::read_dir(&route_dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(millis) = backup_millis(&name) {
+                backups.push((millis, name));
+            }
+        }
+        backups.sort();
+        Ok(backups.into_iter().map(|(_, name)| name).collect())
+    }
+
+    /// Read a backup by name, or the most recent one when `name` is `None`.
+    pub fn read(&self, route_id: &str, name: Option<&str>) -> Result<Route> {
+        let name = match name {
+            Some(name) if backup_millis(name).is_none() => {
+                return Err(anyhow!(
+                    "{name} is not a backup name, expected <unix millis>.json"
+                ))
+            }
+            Some(name) => name.to_string(),
+            None => self
+                .list(route_id)?
+                .pop()
+                .ok_or_else(|| anyhow!("no backups for route {route_id}"))?,
+        };
+        let path = self.route_dir(route_id).join(&name);
+        let data =
+            fs::read_to_string(&path).context(format!("reading backup {}", path.display()))?;
+        schema::from_str(&data, "backup", false)
+            .context(format!("parsing backup {}", path.display()))
+    }
+
+    fn prune(&self, route_id: &str) -> Result {
+        if self.retention == 0 {
+            return Ok(());
+        }
+        let backups = self.list(route_id)?;
+        let excess = backups.len().saturating_sub(self.retention);
+        for name in &backups[..excess] {
+            fs::remove_file(self.route_dir(route_id).join(name))?;
+        }
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// The unix millis a backup named `<unix millis>.json` was taken at. Other
+/// names, e.g. paths outside the Route's directory, are not backups.
+fn backup_millis(name: &str) -> Option<u128> {
+    let stem = name.strip_suffix(".json")?;
+    if stem.is_empty() || !stem.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    stem.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backup_millis, Backups};
+    use crate::{hex_field, route::Route};
+    use temp_dir::TempDir;
+
+    #[test]
+    fn save_prunes_past_retention() {
+        let dir = TempDir::new().unwrap();
+        let backups = Backups::new(Some(dir.path().to_path_buf()), 2).unwrap();
+        let route = Route {
+            id: "route-id".into(),
+            ..Route::new(hex_field::net_id(1), 1, 1)
+        };
+
+        for max_copies in 1..=3 {
+            backups
+                .save(&Route {
+                    max_copies,
+                    ..route.clone()
+                })
+                .unwrap();
+            // backups are named by millisecond
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        assert_eq!(2, backups.list("route-id").unwrap().len());
+        assert_eq!(vec!["route-id"], backups.routes().unwrap());
+        assert_eq!(3, backups.read("route-id", None).unwrap().max_copies);
+        assert!(backups.list("other-route").unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_backup_names_are_read() {
+        assert_eq!(Some(1700000000000), backup_millis("1700000000000.json"));
+        for name in ["../../secret.json", "+1.json", ".json", "1.json.tmp"] {
+            assert_eq!(None, backup_millis(name));
+        }
+
+        let dir = TempDir::new().unwrap();
+        let backups = Backups::new(Some(dir.path().join("backups")), 0).unwrap();
+        std::fs::write(dir.child("secret.json"), "{}").unwrap();
+        let err = backups
+            .read("route-id", Some("../../secret.json"))
+            .unwrap_err();
+        assert!(err.to_string().contains("is not a backup name"));
+    }
+}
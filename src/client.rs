@@ -1,10 +1,20 @@
 use crate::{
-    cmds::gateway::GatewayInfo, hex_field, region::Region, region_params::RegionParams,
-    route::Route, DevaddrConstraint, DevaddrRange, Eui, HeliumNetId, KeyType, NetId, OrgList,
-    OrgResponse, Oui, Result, RouteList, Skf, SkfUpdate,
+    cmds::{gateway::GatewayInfo, BULK_CHUNK_SIZE},
+    hex_field,
+    keyring::ServerKeys,
+    metadata::RequestMetadata,
+    progress::{ProgressEvent, ProgressSink, StderrProgress},
+    recording::Transport,
+    region::Region,
+    region_params::RegionParams,
+    retry::{Chunker, RateLimiter},
+    route::Route,
+    DevaddrConstraint, DevaddrRange, DevaddrUpdate, Eui, EuiUpdate, HeliumNetId, KeyType, NetId,
+    OrgList, OrgResponse, Oui, Result, RouteList, Skf, SkfUpdate,
 };
 use anyhow::anyhow;
-use helium_crypto::{Keypair, PublicKey, Sign, Verify};
+use futures::{Stream, StreamExt, TryStreamExt};
+use helium_crypto::{Keypair, PublicKey, Sign};
 use helium_proto::{
     services::iot_config::{
         admin_client, gateway_client, org_client,
@@ -20,32 +30,44 @@ use helium_proto::{
         RouteCreateReqV1, RouteDeleteReqV1, RouteDevaddrRangesResV1, RouteEuisResV1,
         RouteGetDevaddrRangesReqV1, RouteGetEuisReqV1, RouteGetReqV1, RouteListReqV1,
         RouteListResV1, RouteResV1, RouteSkfGetReqV1, RouteSkfListReqV1, RouteSkfUpdateReqV1,
-        RouteSkfUpdateResV1, RouteUpdateDevaddrRangesReqV1, RouteUpdateEuisReqV1, RouteUpdateReqV1,
+        RouteSkfUpdateResV1, RouteStreamReqV1, RouteStreamResV1, RouteUpdateDevaddrRangesReqV1,
+        RouteUpdateEuisReqV1, RouteUpdateReqV1,
     },
     Message,
 };
 use std::{
     str::FromStr,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::{Arc, OnceLock},
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
+use tonic::service::interceptor::InterceptedService;
+use tracing::Instrument;
 
+/// What every client sends requests through.
+type Channel = InterceptedService<Transport, RequestMetadata>;
+
+#[derive(Clone)]
 pub struct OrgClient {
-    client: org_client::OrgClient<helium_proto::services::Channel>,
-    server_pubkey: PublicKey,
+    client: org_client::OrgClient<Channel>,
+    server_pubkey: ServerKeys,
+    progress: Arc<dyn ProgressSink>,
 }
+
+#[derive(Clone)]
 pub struct RouteClient {
-    client: route_client::RouteClient<helium_proto::services::Channel>,
-    server_pubkey: PublicKey,
+    client: route_client::RouteClient<Channel>,
+    server_pubkey: ServerKeys,
+    progress: Arc<dyn ProgressSink>,
 }
 
 pub struct AdminClient {
-    client: admin_client::AdminClient<helium_proto::services::Channel>,
-    server_pubkey: PublicKey,
+    client: admin_client::AdminClient<Channel>,
+    server_pubkey: ServerKeys,
 }
 
 pub struct GatewayClient {
-    client: gateway_client::GatewayClient<helium_proto::services::Channel>,
-    server_pubkey: PublicKey,
+    client: gateway_client::GatewayClient<Channel>,
+    server_pubkey: ServerKeys,
 }
 
 pub type EuiClient = RouteClient;
@@ -55,8 +77,11 @@ pub type SkfClient = RouteClient;
 impl GatewayClient {
     pub async fn new(host: &str, server_pubkey: &str) -> Result<Self> {
         Ok(Self {
-            client: gateway_client::GatewayClient::connect(host.to_owned()).await?,
-            server_pubkey: helium_crypto::PublicKey::from_str(server_pubkey)?,
+            client: gateway_client::GatewayClient::with_interceptor(
+                Transport::connect(host).await?,
+                RequestMetadata::current(),
+            ),
+            server_pubkey: ServerKeys::from_str(server_pubkey)?,
         })
     }
 
@@ -93,11 +118,20 @@ impl GatewayClient {
 impl OrgClient {
     pub async fn new(host: &str, server_pubkey: &str) -> Result<Self> {
         Ok(Self {
-            client: org_client::OrgClient::connect(host.to_owned()).await?,
-            server_pubkey: helium_crypto::PublicKey::from_str(server_pubkey)?,
+            client: org_client::OrgClient::with_interceptor(
+                Transport::connect(host).await?,
+                RequestMetadata::current(),
+            ),
+            server_pubkey: ServerKeys::from_str(server_pubkey)?,
+            progress: Arc::new(StderrProgress),
         })
     }
 
+    /// Report progress of bulk operations to `progress` instead of stderr.
+    pub fn with_progress(self, progress: Arc<dyn ProgressSink>) -> Self {
+        Self { progress, ..self }
+    }
+
     pub async fn list(&mut self) -> Result<OrgList> {
         let request = OrgListReqV1 {};
         let response = self.client.list(request).await?.into_inner();
@@ -112,6 +146,29 @@ impl OrgClient {
         Ok(response.into())
     }
 
+    /// List all Orgs, fetching each Org's net_id and devaddr constraints,
+    /// `concurrency` Orgs at a time.
+    pub async fn list_with_constraints(&mut self, concurrency: usize) -> Result<Vec<OrgResponse>> {
+        let org_list = self.list().await?;
+        let limiter = RateLimiter::default().with_progress(self.progress.clone());
+        futures::stream::iter(org_list.orgs)
+            .map(|org| {
+                let client = self.clone();
+                let limiter = limiter.clone();
+                async move {
+                    limiter
+                        .retry(|| {
+                            let mut client = client.clone();
+                            async move { client.get(org.oui).await }
+                        })
+                        .await
+                }
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await
+    }
+
     pub async fn create_helium(
         &mut self,
         owner: &PublicKey,
@@ -143,7 +200,7 @@ impl OrgClient {
         payer: &PublicKey,
         delegates: Vec<PublicKey>,
         net_id: NetId,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let mut request = OrgCreateRoamerReqV1 {
             owner: owner.into(),
@@ -154,20 +211,20 @@ impl OrgClient {
             signer: keypair.public_key().into(),
             signature: vec![],
         };
-        request.signature = request.sign(&keypair)?;
+        request.signature = request.sign(keypair)?;
         let response = self.client.create_roamer(request).await?.into_inner();
         response.verify(&self.server_pubkey)?;
         Ok(response.into())
     }
 
-    pub async fn enable(&mut self, oui: u64, keypair: Keypair) -> Result<()> {
+    pub async fn enable(&mut self, oui: u64, keypair: &Keypair) -> Result<()> {
         let mut request = OrgEnableReqV1 {
             oui,
             timestamp: current_timestamp()?,
             signer: keypair.public_key().into(),
             signature: vec![],
         };
-        request.signature = request.sign(&keypair)?;
+        request.signature = request.sign(keypair)?;
         let response = self.client.enable(request).await?.into_inner();
         response.verify(&self.server_pubkey)?;
         Ok(())
@@ -176,51 +233,80 @@ impl OrgClient {
     async fn request_update(
         &mut self,
         oui: u64,
-        update: UpdateV1,
-        keypair: Keypair,
+        updates: Vec<UpdateV1>,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let mut request = OrgUpdateReqV1 {
             oui,
-            updates: vec![update],
+            updates,
             timestamp: current_timestamp()?,
             signer: keypair.public_key().into(),
             signature: vec![],
         };
-        request.signature = request.sign(&keypair)?;
+        request.signature = request.sign(keypair)?;
         let response = self.client.update(request).await?.into_inner();
         response.verify(&self.server_pubkey)?;
         Ok(response.into())
     }
 
+    /// Transfer the Org to `owner`, optionally replacing the payer in the same request.
     pub async fn update_owner(
         &mut self,
         oui: u64,
         owner: &PublicKey,
-        keypair: Keypair,
+        payer: Option<&PublicKey>,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
-        let update = UpdateV1 {
+        self.transfer(oui, owner, payer, &[], keypair).await
+    }
+
+    /// Like [`OrgClient::update_owner`], removing `delegate_keys` in the same
+    /// request so the Org is never left half transferred.
+    pub async fn transfer(
+        &mut self,
+        oui: u64,
+        owner: &PublicKey,
+        payer: Option<&PublicKey>,
+        delegate_keys: &[PublicKey],
+        keypair: &Keypair,
+    ) -> Result<OrgResponse> {
+        let mut updates: Vec<UpdateV1> = delegate_keys
+            .iter()
+            .map(|delegate_key| UpdateV1 {
+                update: Some(Update::DelegateKey(DelegateKeyUpdateV1 {
+                    delegate_key: delegate_key.into(),
+                    action: ActionV1::Remove as i32,
+                })),
+            })
+            .collect();
+        updates.push(UpdateV1 {
             update: Some(Update::Owner(owner.into())),
-        };
-        self.request_update(oui, update, keypair).await
+        });
+        if let Some(payer) = payer {
+            updates.push(UpdateV1 {
+                update: Some(Update::Payer(payer.into())),
+            });
+        }
+        self.request_update(oui, updates, keypair).await
     }
 
     pub async fn update_payer(
         &mut self,
         oui: u64,
         payer: &PublicKey,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let update = UpdateV1 {
             update: Some(Update::Payer(payer.into())),
         };
-        self.request_update(oui, update, keypair).await
+        self.request_update(oui, vec![update], keypair).await
     }
 
     pub async fn add_delegate_key(
         &mut self,
         oui: u64,
         delegate_key: &PublicKey,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let update = UpdateV1 {
             update: Some(Update::DelegateKey(DelegateKeyUpdateV1 {
@@ -228,14 +314,14 @@ impl OrgClient {
                 action: ActionV1::Add as i32,
             })),
         };
-        self.request_update(oui, update, keypair).await
+        self.request_update(oui, vec![update], keypair).await
     }
 
     pub async fn remove_delegate_key(
         &mut self,
         oui: u64,
         delegate_key: &PublicKey,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let update = UpdateV1 {
             update: Some(Update::DelegateKey(DelegateKeyUpdateV1 {
@@ -243,14 +329,14 @@ impl OrgClient {
                 action: ActionV1::Remove as i32,
             })),
         };
-        self.request_update(oui, update, keypair).await
+        self.request_update(oui, vec![update], keypair).await
     }
 
     pub async fn add_devaddr_constraint(
         &mut self,
         oui: u64,
         constraint: DevaddrConstraint,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let update = UpdateV1 {
             update: Some(Update::Constraint(DevaddrConstraintUpdateV1 {
@@ -258,14 +344,33 @@ impl OrgClient {
                 action: ActionV1::Add as i32,
             })),
         };
-        self.request_update(oui, update, keypair).await
+        self.request_update(oui, vec![update], keypair).await
+    }
+
+    /// Add all `constraints` in a single update.
+    pub async fn add_devaddr_constraints(
+        &mut self,
+        oui: u64,
+        constraints: Vec<DevaddrConstraint>,
+        keypair: &Keypair,
+    ) -> Result<OrgResponse> {
+        let updates = constraints
+            .into_iter()
+            .map(|constraint| UpdateV1 {
+                update: Some(Update::Constraint(DevaddrConstraintUpdateV1 {
+                    constraint: Some(constraint.into()),
+                    action: ActionV1::Add as i32,
+                })),
+            })
+            .collect();
+        self.request_update(oui, updates, keypair).await
     }
 
     pub async fn remove_devaddr_constraint(
         &mut self,
         oui: u64,
         constraint: DevaddrConstraint,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let update = UpdateV1 {
             update: Some(Update::Constraint(DevaddrConstraintUpdateV1 {
@@ -273,19 +378,19 @@ impl OrgClient {
                 action: ActionV1::Remove as i32,
             })),
         };
-        self.request_update(oui, update, keypair).await
+        self.request_update(oui, vec![update], keypair).await
     }
 
     pub async fn add_devaddr_slab(
         &mut self,
         oui: u64,
         slab_count: u64,
-        keypair: Keypair,
+        keypair: &Keypair,
     ) -> Result<OrgResponse> {
         let update = UpdateV1 {
             update: Some(Update::Devaddrs(slab_count)),
         };
-        self.request_update(oui, update, keypair).await
+        self.request_update(oui, vec![update], keypair).await
     }
 }
 
@@ -295,6 +400,18 @@ impl DevaddrClient {
         route_id: &str,
         keypair: &Keypair,
     ) -> Result<Vec<DevaddrRange>> {
+        self.stream_devaddrs(route_id, keypair)
+            .await?
+            .try_collect()
+            .await
+    }
+
+    /// Devaddr Ranges for a Route as they arrive from the server.
+    pub async fn stream_devaddrs(
+        &mut self,
+        route_id: &str,
+        keypair: &Keypair,
+    ) -> Result<impl Stream<Item = Result<DevaddrRange>>> {
         let mut request = RouteGetDevaddrRangesReqV1 {
             route_id: route_id.to_string(),
             timestamp: current_timestamp()?,
@@ -302,14 +419,11 @@ impl DevaddrClient {
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
-        let mut stream = self.client.get_devaddr_ranges(request).await?.into_inner();
+        let stream = self.client.get_devaddr_ranges(request).await?.into_inner();
 
-        let mut ranges = vec![];
-        while let Some(range) = stream.message().await? {
-            ranges.push(range.into());
-        }
-
-        Ok(ranges)
+        Ok(stream
+            .map_ok(DevaddrRange::from)
+            .map_err(anyhow::Error::from))
     }
 
     pub async fn add_devaddrs(
@@ -374,6 +488,41 @@ impl DevaddrClient {
         Ok(response)
     }
 
+    /// Add and remove Devaddr Ranges for a Route in a single stream.
+    pub async fn update_devaddrs(
+        &mut self,
+        route_id: &str,
+        updates: &[DevaddrUpdate],
+        keypair: &Keypair,
+    ) -> Result<RouteDevaddrRangesResV1> {
+        let timestamp = current_timestamp()?;
+        let signer: Vec<u8> = keypair.public_key().into();
+        let route_devaddrs = updates
+            .iter()
+            .map(|update| -> Result<RouteUpdateDevaddrRangesReqV1> {
+                let range =
+                    DevaddrRange::new(route_id.to_string(), update.start_addr, update.end_addr)?;
+                let mut request = RouteUpdateDevaddrRangesReqV1 {
+                    action: ActionV1::from(update.action).into(),
+                    timestamp,
+                    signer: signer.clone(),
+                    signature: vec![],
+                    devaddr_range: Some(range.into()),
+                };
+                request.signature = request.sign(keypair)?;
+                Ok(request)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let request = futures::stream::iter(route_devaddrs);
+        let response = self
+            .client
+            .update_devaddr_ranges(request)
+            .await?
+            .into_inner();
+        response.verify(&self.server_pubkey)?;
+        Ok(response)
+    }
+
     pub async fn delete_devaddrs(&mut self, route_id: String, keypair: &Keypair) -> Result {
         let devaddrs = self.get_devaddrs(&route_id, keypair).await?;
         self.remove_devaddrs(devaddrs, keypair).await?;
@@ -383,6 +532,18 @@ impl DevaddrClient {
 
 impl EuiClient {
     pub async fn get_euis(&mut self, route_id: &str, keypair: &Keypair) -> Result<Vec<Eui>> {
+        self.stream_euis(route_id, keypair)
+            .await?
+            .try_collect()
+            .await
+    }
+
+    /// EUI pairs for a Route as they arrive from the server.
+    pub async fn stream_euis(
+        &mut self,
+        route_id: &str,
+        keypair: &Keypair,
+    ) -> Result<impl Stream<Item = Result<Eui>>> {
         let mut request = RouteGetEuisReqV1 {
             route_id: route_id.to_string(),
             timestamp: current_timestamp()?,
@@ -390,14 +551,9 @@ impl EuiClient {
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
-        let mut stream = self.client.get_euis(request).await?.into_inner();
+        let stream = self.client.get_euis(request).await?.into_inner();
 
-        let mut pairs = vec![];
-        while let Some(pair) = stream.message().await? {
-            pairs.push(pair.into());
-        }
-
-        Ok(pairs)
+        Ok(stream.map_ok(Eui::from).map_err(anyhow::Error::from))
     }
 
     pub async fn add_euis(&mut self, euis: Vec<Eui>, keypair: &Keypair) -> Result<RouteEuisResV1> {
@@ -450,6 +606,36 @@ impl EuiClient {
         Ok(response)
     }
 
+    /// Add and remove EUI pairs for a Route in a single stream.
+    pub async fn update_euis(
+        &mut self,
+        route_id: &str,
+        updates: &[EuiUpdate],
+        keypair: &Keypair,
+    ) -> Result<RouteEuisResV1> {
+        let timestamp = current_timestamp()?;
+        let signer: Vec<u8> = keypair.public_key().into();
+        let route_euis = updates
+            .iter()
+            .map(|update| -> Result<RouteUpdateEuisReqV1> {
+                let eui = Eui::new(route_id.to_string(), update.app_eui, update.dev_eui)?;
+                let mut request = RouteUpdateEuisReqV1 {
+                    action: ActionV1::from(update.action).into(),
+                    timestamp,
+                    signature: vec![],
+                    signer: signer.clone(),
+                    eui_pair: Some(eui.into()),
+                };
+                request.signature = request.sign(keypair)?;
+                Ok(request)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let request = futures::stream::iter(route_euis);
+        let response = self.client.update_euis(request).await?.into_inner();
+        response.verify(&self.server_pubkey)?;
+        Ok(response)
+    }
+
     pub async fn delete_euis(&mut self, route_id: String, keypair: &Keypair) -> Result {
         let euis = self.get_euis(&route_id, keypair).await?;
         self.remove_euis(euis, keypair).await?;
@@ -460,11 +646,24 @@ impl EuiClient {
 impl RouteClient {
     pub async fn new(host: &str, server_pubkey: &str) -> Result<Self> {
         Ok(Self {
-            client: route_client::RouteClient::connect(host.to_owned()).await?,
-            server_pubkey: helium_crypto::PublicKey::from_str(server_pubkey)?,
+            client: route_client::RouteClient::with_interceptor(
+                Transport::connect(host).await?,
+                RequestMetadata::current(),
+            ),
+            server_pubkey: ServerKeys::from_str(server_pubkey)?,
+            progress: Arc::new(StderrProgress),
         })
     }
 
+    /// Report progress of bulk operations to `progress` instead of stderr.
+    pub fn with_progress(self, progress: Arc<dyn ProgressSink>) -> Self {
+        Self { progress, ..self }
+    }
+
+    pub fn progress(&self) -> Arc<dyn ProgressSink> {
+        self.progress.clone()
+    }
+
     pub async fn list(&mut self, oui: Oui, keypair: &Keypair) -> Result<RouteList> {
         let mut request = RouteListReqV1 {
             oui,
@@ -494,7 +693,34 @@ impl RouteClient {
             .ok_or(anyhow!("Route get failed"))
     }
 
+    /// Route, EUI, Devaddr Range, and Session Key Filter changes as the config
+    /// service sends them to packet routers. Only keys registered with the
+    /// config service as packet router keys may subscribe.
+    /// Changes from `since`, in unix milliseconds, then as they happen. The
+    /// config service sends every current Route and member when `since` is 0
+    /// or it does not support replaying.
+    pub async fn stream(
+        &mut self,
+        since: u64,
+        keypair: &Keypair,
+    ) -> Result<impl Stream<Item = Result<RouteStreamResV1>>> {
+        let mut request = RouteStreamReqV1 {
+            timestamp: current_timestamp()?,
+            signer: keypair.public_key().into(),
+            signature: vec![],
+            since,
+        };
+        request.signature = request.sign(keypair)?;
+        let server_pubkey = self.server_pubkey.clone();
+        let stream = self.client.stream(request).await?.into_inner();
+
+        Ok(stream
+            .map_err(anyhow::Error::from)
+            .and_then(move |res| futures::future::ready(res.verify(&server_pubkey).map(|_| res))))
+    }
+
     pub async fn create_route(&mut self, route: Route, keypair: &Keypair) -> Result<Route> {
+        route.ensure_unredacted()?;
         let mut request = RouteCreateReqV1 {
             oui: route.oui,
             route: Some(route.into()),
@@ -528,6 +754,7 @@ impl RouteClient {
     }
 
     pub async fn push(&mut self, route: Route, keypair: &Keypair) -> Result<Route> {
+        route.ensure_unredacted()?;
         let mut request = RouteUpdateReqV1 {
             route: Some(route.into()),
             timestamp: current_timestamp()?,
@@ -546,6 +773,18 @@ impl RouteClient {
 
 impl SkfClient {
     pub async fn list_filters(&mut self, route_id: &str, keypair: &Keypair) -> Result<Vec<Skf>> {
+        self.stream_filters(route_id, keypair)
+            .await?
+            .try_collect()
+            .await
+    }
+
+    /// Session Key Filters for a Route as they arrive from the server.
+    pub async fn stream_filters(
+        &mut self,
+        route_id: &str,
+        keypair: &Keypair,
+    ) -> Result<impl Stream<Item = Result<Skf>>> {
         let mut request = RouteSkfListReqV1 {
             route_id: route_id.to_string(),
             timestamp: current_timestamp()?,
@@ -553,14 +792,9 @@ impl SkfClient {
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
-        let mut stream = self.client.list_skfs(request).await?.into_inner();
-
-        let mut filters = vec![];
-        while let Some(filter) = stream.message().await? {
-            filters.push(filter.into());
-        }
+        let stream = self.client.list_skfs(request).await?.into_inner();
 
-        Ok(filters)
+        Ok(stream.map_ok(Skf::from).map_err(anyhow::Error::from))
     }
 
     pub async fn get_filters(
@@ -595,9 +829,9 @@ impl SkfClient {
         let signer: Vec<u8> = keypair.public_key().into();
         let add_filter = RouteSkfUpdateV1 {
             devaddr: filter.devaddr.into(),
+            max_copies: filter.effective_max_copies(),
             session_key: filter.session_key,
             action: ActionV1::Add.into(),
-            max_copies: filter.max_copies.unwrap_or(1),
         };
         let mut request = RouteSkfUpdateReqV1 {
             route_id: filter.route_id,
@@ -638,34 +872,90 @@ impl SkfClient {
         Ok(response)
     }
 
+    /// Replace `filter` with a copy using its new `max_copies`.
+    ///
+    /// The remove and add are sent in one request, so the filter is never
+    /// missing from the Route.
+    pub async fn replace_filter(
+        &mut self,
+        filter: Skf,
+        keypair: &Keypair,
+    ) -> Result<RouteSkfUpdateResV1> {
+        let timestamp = current_timestamp()?;
+        let signer: Vec<u8> = keypair.public_key().into();
+        let remove_filter = RouteSkfUpdateV1 {
+            devaddr: filter.devaddr.into(),
+            session_key: filter.session_key.clone(),
+            action: ActionV1::Remove.into(),
+            max_copies: 0,
+        };
+        let add_filter = RouteSkfUpdateV1 {
+            devaddr: filter.devaddr.into(),
+            max_copies: filter.effective_max_copies(),
+            session_key: filter.session_key,
+            action: ActionV1::Add.into(),
+        };
+        let mut request = RouteSkfUpdateReqV1 {
+            route_id: filter.route_id,
+            updates: vec![remove_filter, add_filter],
+            timestamp,
+            signer,
+            signature: vec![],
+        };
+        request.signature = request.sign(keypair)?;
+        let response = self.client.update_skfs(request).await?.into_inner();
+        response.verify(&self.server_pubkey)?;
+        Ok(response)
+    }
+
     pub async fn delete_filters(&mut self, route_id: String, keypair: &Keypair) -> Result {
         let skfs = self.list_filters(&route_id, keypair).await?;
-        let total = skfs.len() / 100;
-        for (idx, chunk) in skfs.chunks(100).enumerate() {
-            let mut request = RouteSkfUpdateReqV1 {
-                route_id: route_id.clone(),
-                updates: chunk
-                    .iter()
-                    .map(|skf| RouteSkfUpdateV1 {
-                        devaddr: skf.devaddr.into(),
-                        session_key: skf.session_key.to_owned(),
-                        action: ActionV1::Remove.into(),
-                        max_copies: 0
-                    })
-                    .collect(),
-                timestamp: current_timestamp()?,
-                signer: keypair.public_key().into(),
-                signature: vec![],
-            };
-            request.signature = request.sign(keypair)?;
-            let response = self.client.update_skfs(request).await?.into_inner();
-            response.verify(&self.server_pubkey)?;
-            println!("Removed page: {idx}/{total}");
+        let total = skfs.len();
+        let limiter = RateLimiter::default().with_progress(self.progress.clone());
+        let mut chunks = Chunker::new(&skfs, BULK_CHUNK_SIZE).with_progress(self.progress.clone());
+        while let Some(chunk) = chunks.next_chunk() {
+            let started = Instant::now();
+            let result = limiter
+                .retry(|| {
+                    let mut client = self.clone();
+                    let route_id = &route_id;
+                    async move { client.remove_filters(route_id, chunk, keypair).await }
+                })
+                .instrument(chunks.span())
+                .await;
+            chunks.record(result, started.elapsed())?;
+            self.progress.event(ProgressEvent::Chunk {
+                operation: "remove session key filters",
+                done: chunks.done(),
+                total,
+            });
         }
 
         Ok(())
     }
 
+    async fn remove_filters(&mut self, route_id: &str, skfs: &[Skf], keypair: &Keypair) -> Result {
+        let mut request = RouteSkfUpdateReqV1 {
+            route_id: route_id.to_string(),
+            updates: skfs
+                .iter()
+                .map(|skf| RouteSkfUpdateV1 {
+                    devaddr: skf.devaddr.into(),
+                    session_key: skf.session_key.to_owned(),
+                    action: ActionV1::Remove.into(),
+                    max_copies: 0,
+                })
+                .collect(),
+            timestamp: current_timestamp()?,
+            signer: keypair.public_key().into(),
+            signature: vec![],
+        };
+        request.signature = request.sign(keypair)?;
+        let response = self.client.update_skfs(request).await?.into_inner();
+        response.verify(&self.server_pubkey)?;
+        Ok(())
+    }
+
     pub async fn update_filters(
         &mut self,
         route_id: &str,
@@ -691,8 +981,11 @@ impl SkfClient {
 impl AdminClient {
     pub async fn new(host: &str, server_pubkey: &str) -> Result<Self> {
         Ok(Self {
-            client: admin_client::AdminClient::connect(host.to_owned()).await?,
-            server_pubkey: helium_crypto::PublicKey::from_str(server_pubkey)?,
+            client: admin_client::AdminClient::with_interceptor(
+                Transport::connect(host).await?,
+                RequestMetadata::current(),
+            ),
+            server_pubkey: ServerKeys::from_str(server_pubkey)?,
         })
     }
 
@@ -753,8 +1046,25 @@ impl AdminClient {
     }
 }
 
+static TIMESTAMP_SKEW_MS: OnceLock<i64> = OnceLock::new();
+
+/// Shift the timestamp of signed requests by `skew_secs`, set once from
+/// `--timestamp-skew` before any client is created.
+pub fn set_timestamp_skew(skew_secs: i64) -> Result {
+    let skew_ms = skew_secs
+        .checked_mul(1000)
+        .ok_or_else(|| anyhow!("timestamp skew of {skew_secs}s is too large"))?;
+    TIMESTAMP_SKEW_MS
+        .set(skew_ms)
+        .map_err(|_| anyhow!("timestamp skew is already set"))
+}
+
 fn current_timestamp() -> Result<u64> {
-    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64)
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+    let skew = TIMESTAMP_SKEW_MS.get().copied().unwrap_or_default();
+    now.checked_add(skew)
+        .and_then(|timestamp| u64::try_from(timestamp).ok())
+        .ok_or_else(|| anyhow!("timestamp skew of {}s is out of range", skew / 1000))
 }
 
 pub trait MsgSign: Message + std::clone::Clone {
@@ -787,6 +1097,7 @@ impl_sign!(RouteGetDevaddrRangesReqV1, signature);
 impl_sign!(RouteSkfListReqV1, signature);
 impl_sign!(RouteSkfGetReqV1, signature);
 impl_sign!(RouteSkfUpdateReqV1, signature);
+impl_sign!(RouteStreamReqV1, signature);
 impl_sign!(OrgCreateHeliumReqV1, signature);
 impl_sign!(OrgCreateRoamerReqV1, signature);
 impl_sign!(OrgEnableReqV1, signature);
@@ -798,7 +1109,7 @@ impl_sign!(GatewayLocationReqV1, signature);
 impl_sign!(GatewayInfoReqV1, signature);
 
 pub trait MsgVerify: Message + std::clone::Clone {
-    fn verify(&self, verifier: &PublicKey) -> Result
+    fn verify(&self, verifier: &ServerKeys) -> Result
     where
         Self: std::marker::Sized;
 }
@@ -806,14 +1117,12 @@ pub trait MsgVerify: Message + std::clone::Clone {
 macro_rules! impl_verify {
     ($msg_type:ty, $sig: ident) => {
         impl MsgVerify for $msg_type {
-            fn verify(&self, verifier: &PublicKey) -> Result {
+            fn verify(&self, verifier: &ServerKeys) -> Result {
                 let mut buf = vec![];
                 let mut msg = self.clone();
                 msg.$sig = vec![];
                 msg.encode(&mut buf)?;
-                verifier
-                    .verify(&buf, &self.$sig)
-                    .map_err(anyhow::Error::from)
+                verifier.verify(&buf, &self.$sig)
             }
         }
     };
@@ -827,6 +1136,7 @@ impl_verify!(RouteEuisResV1, signature);
 impl_verify!(RouteListResV1, signature);
 impl_verify!(RouteResV1, signature);
 impl_verify!(RouteSkfUpdateResV1, signature);
+impl_verify!(RouteStreamResV1, signature);
 impl_verify!(AdminKeyResV1, signature);
 impl_verify!(AdminLoadRegionResV1, signature);
 impl_verify!(GatewayLocationResV1, signature);
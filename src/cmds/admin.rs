@@ -1,14 +1,32 @@
-use crate::{client, cmds::PathBufKeypair, region_params::RegionParams, Msg, Result};
+use crate::{
+    client,
+    cmds::{format_csv, PathBufKeypair},
+    hex_field::{self, HexDevAddr, HexNetID},
+    keyring::ServerKeys,
+    netid,
+    region_params::RegionParams,
+    Msg, OrgResponse, Oui, PrettyJson, Result,
+};
 use anyhow::Context;
+use helium_crypto::PublicKey;
 use helium_proto::Region as ProtoRegion;
+use serde::Serialize;
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
     io::Read,
+    str::FromStr,
+    time::SystemTime,
 };
 
-use super::{AdminAddKey, AdminLoadRegionParams, AdminRemoveKey};
+use super::{AdminAddKey, AdminConstraintReport, AdminLoadRegionParams, AdminRemoveKey};
 
 pub async fn add_key(args: AdminAddKey) -> Result<Msg> {
+    let config_keys = ServerKeys::from_str(&args.config_pubkey)?;
+    if let Some(mismatch) = network_mismatch(&args.pubkey, config_keys.primary()) {
+        return Msg::err(mismatch);
+    }
+
     if args.commit {
         let mut client = client::AdminClient::new(&args.config_host, &args.config_pubkey).await?;
         client
@@ -20,6 +38,17 @@ pub async fn add_key(args: AdminAddKey) -> Result<Msg> {
     Msg::dry_run(format!("Added {} as {} key", args.pubkey, args.key_type))
 }
 
+/// Keys must belong to the same network as the config service they are added to.
+fn network_mismatch(pubkey: &PublicKey, config_pubkey: &PublicKey) -> Option<String> {
+    if pubkey.network == config_pubkey.network {
+        return None;
+    }
+    Some(format!(
+        "{pubkey} is a {:?} key but the config service at this host is {:?}",
+        pubkey.network, config_pubkey.network
+    ))
+}
+
 pub async fn remove_key(args: AdminRemoveKey) -> Result<Msg> {
     if args.commit {
         let mut client = client::AdminClient::new(&args.config_host, &args.config_pubkey).await?;
@@ -71,3 +100,254 @@ pub async fn load_region(args: AdminLoadRegionParams) -> Result<Msg> {
         Err(err) => Msg::err(format!("region params not created: {err}")),
     }
 }
+
+#[derive(Debug, Serialize)]
+struct ConstraintReport {
+    generated_at: String,
+    net_ids: Vec<NetIdAllocations>,
+}
+
+/// The devaddr constraints of every Org sharing a NetID.
+#[derive(Debug, PartialEq, Serialize)]
+struct NetIdAllocations {
+    net_id: HexNetID,
+    /// Every devaddr the NetID may assign, `None` for an invalid NetID
+    range: Option<Block>,
+    allocations: Vec<Allocation>,
+    /// Devaddrs in the constraints of more than one Org
+    overlaps: Vec<Overlap>,
+    /// Devaddrs of the NetID in no Org's constraints
+    gaps: Vec<Block>,
+}
+
+/// An inclusive run of devaddrs.
+#[derive(Debug, PartialEq, Serialize)]
+struct Block {
+    start_addr: HexDevAddr,
+    end_addr: HexDevAddr,
+    devaddrs: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Allocation {
+    oui: Oui,
+    #[serde(flatten)]
+    block: Block,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Overlap {
+    ouis: [Oui; 2],
+    #[serde(flatten)]
+    block: Block,
+}
+
+impl Block {
+    fn new(start: u64, end: u64) -> Self {
+        Self {
+            start_addr: hex_field::devaddr(start),
+            end_addr: hex_field::devaddr(end),
+            devaddrs: end - start + 1,
+        }
+    }
+}
+
+pub async fn constraint_report(args: AdminConstraintReport) -> Result<Msg> {
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let orgs = client.list_with_constraints(args.concurrency.get()).await?;
+    let report = ConstraintReport {
+        generated_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        net_ids: allocation_map(&orgs),
+    };
+
+    if let Some(path) = &args.csv {
+        let csv = format_csv(&allocation_rows(&report.net_ids));
+        fs::write(path, format!("{csv}\n")).context(format!("writing {}", path.display()))?;
+    }
+    let json = report.pretty_json()?;
+    match args.out_file {
+        Some(path) => {
+            fs::write(&path, json).context(format!("writing {}", path.display()))?;
+            let count = |count: fn(&NetIdAllocations) -> usize| -> usize {
+                report.net_ids.iter().map(count).sum()
+            };
+            Msg::ok(format!(
+                "wrote allocations of {} orgs in {} net ids with {} overlaps and {} gaps to {}",
+                orgs.len(),
+                report.net_ids.len(),
+                count(|net_id| net_id.overlaps.len()),
+                count(|net_id| net_id.gaps.len()),
+                path.display()
+            ))
+        }
+        None => Msg::ok(json),
+    }
+}
+
+/// Every devaddr constraint grouped by NetID and sorted by address, with the
+/// overlaps between different Orgs and the gaps left in each NetID's range.
+fn allocation_map(orgs: &[OrgResponse]) -> Vec<NetIdAllocations> {
+    let mut by_net_id: BTreeMap<u64, Vec<Allocation>> = BTreeMap::new();
+    for org in orgs {
+        let allocations = by_net_id.entry(org.net_id.0).or_default();
+        allocations.extend(org.devaddr_constraints.iter().map(|constraint| Allocation {
+            oui: org.org.oui,
+            block: Block::new(constraint.start_addr.0, constraint.end_addr.0),
+        }));
+    }
+
+    by_net_id
+        .into_iter()
+        .map(|(net_id, mut allocations)| {
+            allocations.sort_by_key(|allocation| {
+                (allocation.block.start_addr.0, allocation.block.end_addr.0)
+            });
+            let range = u32::try_from(net_id)
+                .ok()
+                .and_then(|net_id| netid::devaddr_range(net_id).ok());
+            NetIdAllocations {
+                net_id: net_id.into(),
+                overlaps: overlaps(&allocations),
+                gaps: range
+                    .as_ref()
+                    .map(|range| gaps(range.start_addr.0, range.end_addr.0, &allocations))
+                    .unwrap_or_default(),
+                range: range.map(|range| Block::new(range.start_addr.0, range.end_addr.0)),
+                allocations,
+            }
+        })
+        .collect()
+}
+
+/// `allocations` are sorted by start address.
+fn overlaps(allocations: &[Allocation]) -> Vec<Overlap> {
+    let mut overlaps = vec![];
+    for (idx, first) in allocations.iter().enumerate() {
+        let first_end = first.block.end_addr.0;
+        for second in allocations[idx + 1..]
+            .iter()
+            .take_while(|second| second.block.start_addr.0 <= first_end)
+        {
+            if first.oui == second.oui {
+                continue;
+            }
+            overlaps.push(Overlap {
+                ouis: [first.oui, second.oui],
+                block: Block::new(
+                    second.block.start_addr.0,
+                    first_end.min(second.block.end_addr.0),
+                ),
+            });
+        }
+    }
+    overlaps
+}
+
+/// The devaddrs from `start` through `end` outside every allocation.
+/// `allocations` are sorted by start address.
+fn gaps(start: u64, end: u64, allocations: &[Allocation]) -> Vec<Block> {
+    let mut gaps = vec![];
+    let mut next = start;
+    for allocation in allocations {
+        if next > end {
+            return gaps;
+        }
+        let (alloc_start, alloc_end) = (allocation.block.start_addr.0, allocation.block.end_addr.0);
+        if alloc_start > next {
+            gaps.push(Block::new(next, (alloc_start - 1).min(end)));
+        }
+        next = next.max(alloc_end + 1);
+    }
+    if next <= end {
+        gaps.push(Block::new(next, end));
+    }
+    gaps
+}
+
+fn allocation_rows(net_ids: &[NetIdAllocations]) -> Vec<Vec<String>> {
+    let header = [
+        "net_id",
+        "kind",
+        "ouis",
+        "start_addr",
+        "end_addr",
+        "devaddrs",
+    ];
+    let mut rows = vec![header.map(String::from).to_vec()];
+    let row = |net_id: &HexNetID, kind: &str, ouis: String, block: &Block| {
+        vec![
+            net_id.to_string(),
+            kind.to_string(),
+            ouis,
+            block.start_addr.to_string(),
+            block.end_addr.to_string(),
+            block.devaddrs.to_string(),
+        ]
+    };
+    for net_id in net_ids {
+        for allocation in &net_id.allocations {
+            let ouis = allocation.oui.to_string();
+            rows.push(row(&net_id.net_id, "allocation", ouis, &allocation.block));
+        }
+        for overlap in &net_id.overlaps {
+            let ouis = format!("{};{}", overlap.ouis[0], overlap.ouis[1]);
+            rows.push(row(&net_id.net_id, "overlap", ouis, &overlap.block));
+        }
+        for gap in &net_id.gaps {
+            rows.push(row(&net_id.net_id, "gap", String::new(), gap));
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allocation_map, allocation_rows, Block, Overlap};
+    use crate::{cmds::format_csv, hex_field, subnet::DevaddrConstraint, testing::fixtures};
+
+    #[test]
+    fn constraint_overlaps_and_gaps() {
+        let owner = fixtures::keypair().public_key().to_owned();
+        let org = |oui, constraints: &[(u64, u64)]| {
+            let mut org = fixtures::org(oui, &owner);
+            org.devaddr_constraints = constraints
+                .iter()
+                .map(|(start, end)| {
+                    DevaddrConstraint::new(hex_field::devaddr(*start), hex_field::devaddr(*end))
+                        .unwrap()
+                })
+                .collect();
+            org
+        };
+        // NetID C00053 assigns FC014C00 through FC014FFF
+        let orgs = [
+            org(2, &[(0xFC01_4C10, 0xFC01_4C1F), (0xFC01_4E00, 0xFC01_4FFF)]),
+            org(1, &[(0xFC01_4C00, 0xFC01_4C17)]),
+        ];
+
+        let map = allocation_map(&orgs);
+        assert_eq!(1, map.len());
+        let net_id = &map[0];
+        assert_eq!(
+            vec![1, 2, 2],
+            net_id
+                .allocations
+                .iter()
+                .map(|allocation| allocation.oui)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Overlap {
+                ouis: [1, 2],
+                block: Block::new(0xFC01_4C10, 0xFC01_4C17),
+            }],
+            net_id.overlaps
+        );
+        assert_eq!(vec![Block::new(0xFC01_4C20, 0xFC01_4DFF)], net_id.gaps);
+
+        let csv = format_csv(&allocation_rows(&map));
+        assert!(csv.starts_with("net_id,kind,ouis,start_addr,end_addr,devaddrs\n"));
+        assert!(csv.contains("C00053,overlap,1;2,FC014C10,FC014C17,8\n"));
+        assert!(csv.ends_with("C00053,gap,,FC014C20,FC014DFF,480"));
+    }
+}
@@ -2,9 +2,14 @@ use serde::{Deserialize, Serialize};
 use std::{env, fs, path::PathBuf};
 
 use super::{
-    EnvInfo, GenerateKeypair, ENV_CONFIG_HOST, ENV_KEYPAIR_BIN, ENV_MAX_COPIES, ENV_NET_ID, ENV_OUI,
+    EnvInfo, GenerateKeypair, WalletInfo, ENV_CONFIG_HOST, ENV_KEYPAIR_BIN, ENV_MAX_COPIES,
+    ENV_NET_ID, ENV_OUI, ENV_SOLANA_URL,
+};
+use crate::{
+    client, hex_field, profile,
+    solana::{SolanaKeypair, SolanaRpc, DC_MINT, HNT_MINT, IOT_MINT},
+    Msg, Oui, PrettyJson, Result,
 };
-use crate::{hex_field, Msg, Oui, PrettyJson, Result};
 use anyhow::Context;
 use dialoguer::Input;
 use helium_crypto::Keypair;
@@ -17,6 +22,10 @@ pub async fn env_init() -> Result<Msg> {
         .with_prompt("Config Service Host")
         .allow_empty(true)
         .interact()?;
+    let solana_url: String = Input::new()
+        .with_prompt("Solana RPC URL")
+        .allow_empty(true)
+        .interact()?;
     let keypair_path: String = Input::<String>::new()
         .with_prompt("Keypair Location")
         .with_initial_text("./keypair.bin")
@@ -47,6 +56,9 @@ pub async fn env_init() -> Result<Msg> {
     if !config_host.is_empty() {
         report.push(format!("{ENV_CONFIG_HOST}={config_host}"));
     }
+    if !solana_url.is_empty() {
+        report.push(format!("{ENV_SOLANA_URL}={solana_url}"));
+    }
     if !keypair_path.is_empty() {
         report.push(format!("{ENV_KEYPAIR_BIN}={keypair_path}"))
     }
@@ -63,7 +75,15 @@ pub async fn env_init() -> Result<Msg> {
     Msg::ok(report.join("\n"))
 }
 
+/// `arguments` is the effective configuration, from flags, then the
+/// environment, then the profile's defaults for `--oui`.
 pub fn env_info(args: EnvInfo) -> Result<Msg> {
+    let applied = profile::applied();
+    // values set from the profile are not part of the caller's environment
+    let env_var = |var: &str| match applied.filter(|applied| applied.defaults.contains_key(var)) {
+        Some(_) => "unset".to_string(),
+        None => env::var(var).unwrap_or_else(|_| "unset".into()),
+    };
     let env_keypair = env::var(ENV_KEYPAIR_BIN).ok().map(|i| i.into());
     let (env_keypair_location, env_public_key, env_key_type) =
         get_public_key_from_path(env_keypair);
@@ -72,10 +92,11 @@ pub fn env_info(args: EnvInfo) -> Result<Msg> {
 
     let output = json!({
         "environment": {
-            ENV_CONFIG_HOST: env::var(ENV_CONFIG_HOST).unwrap_or_else(|_| "unset".into()),
-            ENV_NET_ID:  env::var(ENV_NET_ID).unwrap_or_else(|_| "unset".into()),
-            ENV_OUI:  env::var(ENV_OUI).unwrap_or_else(|_| "unset".into()),
-            ENV_MAX_COPIES: env::var(ENV_MAX_COPIES).unwrap_or_else(|_| "unset".into()),
+            ENV_CONFIG_HOST: env_var(ENV_CONFIG_HOST),
+            ENV_NET_ID: env_var(ENV_NET_ID),
+            ENV_OUI: env_var(ENV_OUI),
+            ENV_MAX_COPIES: env_var(ENV_MAX_COPIES),
+            ENV_SOLANA_URL: env_var(ENV_SOLANA_URL),
             ENV_KEYPAIR_BIN:  env_keypair_location,
             "public_key_from_keypair": env_public_key,
             "key_type_from_keypair": env_key_type,
@@ -85,10 +106,72 @@ pub fn env_info(args: EnvInfo) -> Result<Msg> {
             "net_id": args.net_id,
             "oui": args.oui,
             "max_copies": args.max_copies,
+            "solana_url": args.solana_url,
             "keypair": arg_keypair_location,
             "public_key_from_keypair": arg_public_key,
             "key_type_from_keypair": arg_key_type
-        }
+        },
+        "profile": applied,
+    });
+    Msg::ok(output.pretty_json()?)
+}
+
+/// Org commands fail with opaque rpc errors when the payer is unfunded, so
+/// report balances and Org roles up front.
+pub async fn wallet_info(args: WalletInfo) -> Result<Msg> {
+    let keypair_path = SolanaKeypair::path_or_default(args.solana_keypair)?;
+    let keypair = SolanaKeypair::read(&keypair_path)?;
+    let address = keypair.address();
+
+    let rpc = SolanaRpc::new(&args.solana_url);
+    let sol = rpc.sol_balance(&address).await?;
+    let hnt = rpc.token_balance(&address, HNT_MINT).await?;
+    let iot = rpc.token_balance(&address, IOT_MINT).await?;
+    let dc = rpc.token_balance(&address, DC_MINT).await?;
+
+    let mut org_client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let orgs: Vec<_> = org_client
+        .list()
+        .await?
+        .orgs
+        .into_iter()
+        .filter_map(|org| {
+            let mut roles = vec![];
+            if keypair.matches(&org.owner) {
+                roles.push("owner");
+            }
+            if keypair.matches(&org.payer) {
+                roles.push("payer");
+            }
+            if org.delegate_keys.iter().any(|key| keypair.matches(key)) {
+                roles.push("delegate");
+            }
+            (!roles.is_empty()).then(|| json!({"oui": org.oui, "roles": roles}))
+        })
+        .collect();
+
+    let mut warnings = vec![];
+    if sol == 0.0 {
+        warnings.push("no SOL to pay transaction fees".to_string());
+    }
+    if orgs.is_empty() {
+        warnings.push(format!(
+            "{address} is not the owner, payer, or a delegate of any Org"
+        ));
+    }
+
+    let output = json!({
+        "keypair": keypair_path.display().to_string(),
+        "address": address,
+        "solana_url": args.solana_url,
+        "balances": {
+            "sol": sol,
+            "hnt": hnt,
+            "iot": iot,
+            "dc": dc,
+        },
+        "orgs": orgs,
+        "warnings": warnings,
     });
     Msg::ok(output.pretty_json()?)
 }
@@ -101,18 +184,25 @@ pub enum NetworkArg {
     Testnet,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+pub enum KeyTypeArg {
+    #[default]
+    Ed25519,
+    EccCompact,
+}
+
 pub fn generate_keypair(args: GenerateKeypair) -> Result<Msg> {
     let network: helium_crypto::Network = match args.network {
         NetworkArg::Mainnet => helium_crypto::Network::MainNet,
         NetworkArg::Testnet => helium_crypto::Network::TestNet,
     };
-    let key = helium_crypto::Keypair::generate(
-        helium_crypto::KeyTag {
-            network,
-            key_type: helium_crypto::KeyType::Ed25519,
-        },
-        &mut OsRng,
-    );
+    let key_type = match args.key_type {
+        KeyTypeArg::Ed25519 => helium_crypto::KeyType::Ed25519,
+        KeyTypeArg::EccCompact => helium_crypto::KeyType::EccCompact,
+    };
+    let key =
+        helium_crypto::Keypair::generate(helium_crypto::KeyTag { network, key_type }, &mut OsRng);
     if let Some(parent) = args.out_file.parent() {
         fs::create_dir_all(parent)?;
     }
@@ -123,10 +213,17 @@ pub fn generate_keypair(args: GenerateKeypair) -> Result<Msg> {
     enc.extend(key.public_key().to_vec());
     fs::write(&args.out_file, enc)?;
 
-    Msg::ok(format!(
-        "New Keypair created and written to {:?}",
+    let pubkey = key.public_key().to_string();
+    let mut msg = format!(
+        "New Keypair created and written to {:?}\npublic key: {pubkey}",
         args.out_file.display()
-    ))
+    );
+    if args.qr {
+        let code = qrcode::QrCode::new(&pubkey)?;
+        msg.push('\n');
+        msg.push_str(&code.render::<qrcode::render::unicode::Dense1x2>().build());
+    }
+    Msg::ok(msg)
 }
 
 pub fn get_public_key_from_path(path: Option<PathBuf>) -> (String, String, String) {
@@ -163,7 +260,7 @@ mod tests {
         cmds::{
             self,
             env::{env_info, generate_keypair, get_public_key_from_path},
-            EnvInfo, GenerateKeypair,
+            EnvInfo, GenerateKeypair, PathBufKeypair,
         },
         hex_field,
     };
@@ -176,12 +273,16 @@ mod tests {
         let arg_keypair = dir.child("arg-keypair.bin");
         generate_keypair(GenerateKeypair {
             network: cmds::env::NetworkArg::Mainnet,
+            key_type: cmds::env::KeyTypeArg::Ed25519,
+            qr: false,
             out_file: env_keypair.clone(),
             commit: true,
         })
         .unwrap();
         generate_keypair(GenerateKeypair {
             network: cmds::env::NetworkArg::Testnet,
+            key_type: cmds::env::KeyTypeArg::Ed25519,
+            qr: false,
             out_file: arg_keypair.clone(),
             commit: true,
         })
@@ -192,6 +293,7 @@ mod tests {
         env::set_var(cmds::ENV_NET_ID, "C0053");
         env::set_var(cmds::ENV_OUI, "42");
         env::set_var(cmds::ENV_MAX_COPIES, "42");
+        env::set_var(cmds::ENV_SOLANA_URL, "https://env-solana:8899");
         env::set_var(cmds::ENV_KEYPAIR_BIN, env_keypair.clone());
 
         let env_args = EnvInfo {
@@ -200,6 +302,7 @@ mod tests {
             net_id: Some(hex_field::net_id(42)),
             oui: Some(4),
             max_copies: Some(1337),
+            solana_url: Some("https://arg-solana:8899".to_string()),
         };
 
         // =======
@@ -216,6 +319,7 @@ mod tests {
         assert_eq!(env[cmds::ENV_NET_ID], "C0053");
         assert_eq!(env[cmds::ENV_OUI], "42");
         assert_eq!(env[cmds::ENV_MAX_COPIES], "42");
+        assert_eq!(env[cmds::ENV_SOLANA_URL], "https://env-solana:8899");
         assert_eq!(
             env[cmds::ENV_KEYPAIR_BIN],
             env_keypair.display().to_string()
@@ -228,6 +332,33 @@ mod tests {
         assert_eq!(arg["net_id"], "00002A");
         assert_eq!(arg["oui"], 4);
         assert_eq!(arg["max_copies"], 1337);
+        assert_eq!(arg["solana_url"], "https://arg-solana:8899");
+    }
+
+    #[test]
+    fn generate_ecc_compact_keypair() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("keypair.bin");
+        let msg = generate_keypair(GenerateKeypair {
+            network: cmds::env::NetworkArg::Testnet,
+            key_type: cmds::env::KeyTypeArg::EccCompact,
+            qr: true,
+            out_file: path.clone(),
+            commit: true,
+        })
+        .unwrap()
+        .into_inner();
+
+        let keypair = path.to_keypair().unwrap();
+        assert_eq!(
+            helium_crypto::KeyType::EccCompact,
+            keypair.key_tag().key_type
+        );
+        assert_eq!(helium_crypto::Network::TestNet, keypair.key_tag().network);
+        let pubkey_line = format!("public key: {}", keypair.public_key());
+        assert_eq!(Some(pubkey_line.as_str()), msg.lines().nth(1));
+        // the QR code follows
+        assert!(msg.lines().count() > 10);
     }
 
     #[test]
@@ -1,21 +1,31 @@
-use super::{GetHotspot, PathBufKeypair};
-use crate::{client, region::Region, Msg, PrettyJson, Result};
+use super::{GetHotspot, GetHotspotLocation, PathBufKeypair};
+use crate::{
+    client,
+    region::Region,
+    solana::{IotHotspotInfo, SolanaRpc},
+    timestamp::Timestamp,
+    Msg, PrettyJson, Result,
+};
 use angry_purple_tiger::AnimalName;
+use anyhow::{anyhow, Context};
 use helium_crypto::PublicKey;
 use helium_proto::services::iot_config::{
     GatewayInfo as GatewayInfoProto, GatewayLocationResV1, GatewayMetadata as GatewayMetadataProto,
 };
 use serde::Serialize;
-use std::str::FromStr;
+use std::{collections::HashSet, str::FromStr};
 
-pub async fn location(args: GetHotspot) -> Result<Msg> {
+pub async fn location(args: GetHotspotLocation) -> Result<Msg> {
     let mut client = client::GatewayClient::new(&args.config_host, &args.config_pubkey).await?;
     match client
         .location(&args.hotspot, &args.keypair.to_keypair()?)
         .await
     {
         Ok(location) => {
-            let location = Location::from_proto_resp(args.hotspot.to_owned(), location)?;
+            let mut location = Location::from_proto_resp(args.hotspot.to_owned(), location)?;
+            if args.with_asserted_meta {
+                location.asserted = Some(asserted_meta(&args.solana_url, &args.hotspot).await?);
+            }
             Msg::ok(location.pretty_json()?)
         }
         Err(err) => Msg::err(format!(
@@ -31,7 +41,17 @@ pub async fn info(args: GetHotspot) -> Result<Msg> {
         .info(&args.hotspot, &args.keypair.to_keypair()?)
         .await
     {
-        Ok(info) => Msg::ok(info.pretty_json()?),
+        Ok(mut info) => {
+            if let Some(source) = args.check_denylist {
+                let denylist = read_denylist(&source).await?;
+                info.denylist = Some(DenylistCheck {
+                    denied: denylist.contains(&args.hotspot.to_string()),
+                    entries: denylist.len(),
+                    source,
+                });
+            }
+            Msg::ok(info.pretty_json()?)
+        }
         Err(err) => Msg::err(format!(
             "failed to retrieve {} info: {}",
             &args.hotspot, err
@@ -39,6 +59,25 @@ pub async fn info(args: GetHotspot) -> Result<Msg> {
     }
 }
 
+/// The asserted location record lives on Solana, not in the config service.
+async fn asserted_meta(solana_url: &str, hotspot: &PublicKey) -> Result<AssertedMeta> {
+    let rpc = SolanaRpc::new(solana_url);
+    let asset = rpc.hotspot_asset(hotspot).await?;
+    let info = rpc.iot_hotspot_info(hotspot).await?;
+    let asserted_at = rpc.last_transaction_time(&info.info_address).await?;
+    let latlng = match &info.location {
+        Some(location) => Some(h3o::LatLng::from(h3o::CellIndex::from_str(location)?)),
+        None => None,
+    };
+    Ok(AssertedMeta {
+        asset,
+        lat: latlng.map(|latlng| latlng.lat()),
+        lon: latlng.map(|latlng| latlng.lng()),
+        info,
+        asserted_at: asserted_at.map(Timestamp::from_unix_secs).transpose()?,
+    })
+}
+
 #[derive(Debug, Serialize)]
 pub struct Location {
     name: String,
@@ -46,6 +85,20 @@ pub struct Location {
     hex: String,
     lat: f64,
     lon: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    asserted: Option<AssertedMeta>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssertedMeta {
+    asset: String,
+    #[serde(flatten)]
+    info: IotHotspotInfo,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    /// Time of the latest transaction on the info account, which is the
+    /// latest location assertion
+    asserted_at: Option<Timestamp>,
 }
 
 impl Location {
@@ -62,6 +115,7 @@ impl Location {
             hex,
             lat: latlng.lat(),
             lon: latlng.lng(),
+            asserted: None,
         })
     }
 }
@@ -72,6 +126,51 @@ pub struct GatewayInfo {
     pubkey: PublicKey,
     is_full_hotspot: bool,
     metadata: Option<GatewayMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    denylist: Option<DenylistCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DenylistCheck {
+    source: String,
+    entries: usize,
+    denied: bool,
+}
+
+async fn read_denylist(source: &str) -> Result<HashSet<String>> {
+    let data = if source.starts_with("http://") || source.starts_with("https://") {
+        reqwest::get(source)
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(format!("fetching denylist {source}"))?
+            .text()
+            .await?
+    } else {
+        std::fs::read_to_string(source).context(format!("reading denylist {source}"))?
+    };
+    parse_denylist(&data).context(format!("parsing denylist {source}"))
+}
+
+/// A pubkey per line, or per row in the first column of a CSV with an
+/// optional header. Blank lines and `#` comments are skipped. Pubkeys are
+/// validated and kept in their canonical b58 form.
+fn parse_denylist(data: &str) -> Result<HashSet<String>> {
+    let mut denylist = HashSet::new();
+    for (idx, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let field = line.split(',').next().unwrap_or_default().trim_matches('"');
+        match PublicKey::from_str(field.trim()) {
+            Ok(pubkey) => {
+                denylist.insert(pubkey.to_string());
+            }
+            Err(_) if idx == 0 => {}
+            Err(err) => return Err(anyhow!("line {}: invalid pubkey {field}: {err}", idx + 1)),
+        }
+    }
+    Ok(denylist)
 }
 
 #[derive(Debug, Serialize)]
@@ -100,6 +199,7 @@ impl TryFrom<GatewayInfoProto> for GatewayInfo {
             pubkey,
             is_full_hotspot: info.is_full_hotspot,
             metadata,
+            denylist: None,
         })
     }
 }
@@ -120,3 +220,25 @@ impl TryFrom<GatewayMetadataProto> for GatewayMetadata {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_denylist;
+    use crate::testing::fixtures;
+
+    #[test]
+    fn denylists_are_parsed() {
+        let denied = fixtures::keypair().public_key().to_string();
+        let allowed = fixtures::keypair().public_key().to_string();
+        let denylist =
+            parse_denylist(&format!("pubkey,reason\n\n# spoofing\n\"{denied}\",gps\n")).unwrap();
+        assert!(denylist.contains(&denied));
+        assert!(!denylist.contains(&allowed));
+        assert_eq!(1, denylist.len());
+
+        let err = parse_denylist(&format!("{denied}\nnot-a-key\n")).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("line 2: invalid pubkey not-a-key"));
+    }
+}
@@ -1,19 +1,35 @@
 use crate::{
-    cmds::env::NetworkArg,
+    cmds::env::{KeyTypeArg, NetworkArg},
     hex_field::{self, HexNetID},
+    metadata,
+    normalize::{normalize, BulkUpdate},
+    profile,
     region::Region,
-    DevaddrConstraint, HeliumNetId, KeyType, Msg, Oui, PrettyJson, Result,
+    route::validate_route_id,
+    server, strict, timestamp, DevaddrConstraint, HeliumNetId, KeyType, Msg, Oui, PrettyJson,
+    Result,
 };
 use anyhow::Context;
 use clap::{Args, Parser, Subcommand};
+use futures::{Stream, TryStreamExt};
 use helium_crypto::PublicKey;
-use std::path::PathBuf;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 pub mod admin;
 pub mod env;
 pub mod gateway;
+pub mod netid;
 pub mod org;
+pub mod report;
 pub mod route;
+pub mod version;
 
 pub const ENV_CONFIG_HOST: &str = "HELIUM_CONFIG_HOST";
 pub const ENV_CONFIG_PUBKEY: &str = "HELIUM_CONFIG_PUBKEY";
@@ -21,6 +37,17 @@ pub const ENV_KEYPAIR_BIN: &str = "HELIUM_KEYPAIR_BIN";
 pub const ENV_NET_ID: &str = "HELIUM_NET_ID";
 pub const ENV_OUI: &str = "HELIUM_OUI";
 pub const ENV_MAX_COPIES: &str = "HELIUM_MAX_COPIES";
+pub const ENV_SOLANA_URL: &str = "HELIUM_SOLANA_URL";
+pub const ENV_SOLANA_KEYPAIR: &str = "HELIUM_SOLANA_KEYPAIR";
+pub const ENV_BACKUP_DIR: &str = "HELIUM_BACKUP_DIR";
+pub const ENV_BACKUP_RETENTION: &str = "HELIUM_BACKUP_RETENTION";
+pub const ENV_TIMESTAMP_SKEW: &str = "HELIUM_TIMESTAMP_SKEW";
+pub const ENV_ALIASES: &str = "HELIUM_ALIASES";
+pub const ENV_HTTP_AUTH_HEADER: &str = "HELIUM_HTTP_AUTH_HEADER";
+pub const ENV_PROFILE: &str = "HELIUM_PROFILE";
+pub const ENV_PROFILES: &str = "HELIUM_PROFILES";
+pub const ENV_ROUTE_TEMPLATE: &str = "HELIUM_ROUTE_TEMPLATE";
+pub const ENV_OTLP_ENDPOINT: &str = "HELIUM_OTLP_ENDPOINT";
 
 #[derive(Debug, Parser)]
 #[command(name = "helium-config-cli")]
@@ -37,6 +64,10 @@ pub struct Cli {
     )]
     pub config_host: String,
 
+    /// Config service signing key, a comma separated list of keys, or the
+    /// path of a keyring file with one key per line
+    ///
+    /// Responses signed by any of the keys are accepted.
     #[arg(
         global = true,
         long,
@@ -45,6 +76,15 @@ pub struct Cli {
     )]
     pub config_pubkey: String,
 
+    /// Solana RPC endpoint used by commands that read or write on-chain records
+    #[arg(
+        global = true,
+        long,
+        env = ENV_SOLANA_URL,
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    pub solana_url: String,
+
     #[arg(
         global = true,
         long,
@@ -55,6 +95,101 @@ pub struct Cli {
 
     #[arg(global = true, long)]
     pub print_command: bool,
+
+    /// Don't print the result of the command to stdout
+    #[arg(global = true, long)]
+    pub quiet: bool,
+    /// Append the result of the command to this file
+    #[arg(global = true, long)]
+    pub output_file: Option<PathBuf>,
+    /// Send the result of the command to syslog, or journald
+    #[arg(global = true, long)]
+    pub syslog: bool,
+    /// Export traces of the command and its config service requests to this
+    /// OTLP collector, e.g. http://localhost:4317. Needs the `otel` feature.
+    #[arg(global = true, long, env = ENV_OTLP_ENDPOINT)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Reject input files containing fields the CLI does not recognize
+    #[arg(global = true, long)]
+    pub strict: bool,
+    /// Skip checking that your keypair manages a Route's OUI before changing it
+    #[arg(global = true, long)]
+    pub no_preflight: bool,
+    /// Succeed without a change when an add finds the entry already present
+    /// or a remove finds it already absent, so provisioning can be re-run
+    #[arg(global = true, long)]
+    pub idempotent: bool,
+    /// Push Route updates even when the Route changed on the config service
+    /// since it was read, overwriting the other change
+    #[arg(global = true, long)]
+    pub force: bool,
+
+    /// Where Routes are backed up before committed changes
+    ///
+    /// Defaults to ~/.local/share/helium-config-cli/backups
+    #[arg(global = true, long, env = ENV_BACKUP_DIR)]
+    pub backup_dir: Option<PathBuf>,
+
+    /// List Routes from the config service for lookups instead of using the
+    /// Route lists cached in ~/.cache/helium-config-cli
+    #[arg(global = true, long)]
+    pub no_cache: bool,
+
+    /// Backups kept per Route, 0 keeps all of them
+    #[arg(global = true, long, env = ENV_BACKUP_RETENTION, default_value = "20")]
+    pub backup_retention: usize,
+
+    /// Requests in flight at once for commands that fetch details for many
+    /// Routes or Orgs
+    #[arg(global = true, long, default_value = "8")]
+    pub concurrency: NonZeroUsize,
+
+    /// Metadata sent with every config service request, as `key=value`, to
+    /// tag automation runs in the service's logs
+    #[arg(global = true, long, value_parser = metadata::parse_header)]
+    pub header: Vec<metadata::Header>,
+
+    /// Seconds added to the timestamp of signed requests, for machines whose
+    /// clock is behind (positive) or ahead (negative) of the config service.
+    /// The config service does not report its time, measure the drift with
+    /// e.g. `ntpdate -q`.
+    #[arg(
+        global = true,
+        long,
+        env = ENV_TIMESTAMP_SKEW,
+        default_value = "0",
+        allow_negative_numbers = true
+    )]
+    pub timestamp_skew: i64,
+
+    /// Print times in UTC rather than the local time zone
+    #[arg(global = true, long)]
+    pub utc: bool,
+
+    /// Profile of per-OUI defaults in ~/.config/helium-config-cli/profiles.json,
+    /// or the file in HELIUM_PROFILES
+    #[arg(global = true, long, env = ENV_PROFILE, default_value = profile::DEFAULT_PROFILE)]
+    pub profile: String,
+
+    /// Shrink the chunks of bulk Session Key Filter, EUI, and Devaddr updates
+    /// when requests to the config service time out or slow down, and grow
+    /// them back once requests are fast again
+    #[arg(global = true, long)]
+    pub adaptive_chunking: bool,
+
+    /// Fail instead of printing a warning to stderr, e.g. for a wildcard EUI
+    /// or a protocol change
+    #[arg(global = true, long)]
+    pub warnings_as_errors: bool,
+
+    /// Save every config service request and response in this directory
+    #[arg(global = true, long, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    /// Answer config service requests from a `--record` directory instead of
+    /// connecting
+    #[arg(global = true, long)]
+    pub replay: Option<PathBuf>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -66,18 +201,25 @@ pub enum Commands {
     },
 
     /// Route
+    #[command(alias = "r")]
     Route {
         #[command(subcommand)]
         command: RouteCommands,
     },
 
     /// Org
+    #[command(alias = "o")]
     Org {
         #[command(subcommand)]
         command: OrgCommands,
     },
     /// Print a Subnet Mask for a given Devaddr Range
     SubnetMask(SubnetMask),
+    /// NetID reference
+    Netid {
+        #[command(subcommand)]
+        command: NetIdCommands,
+    },
     /// Admin
     Admin {
         #[command(subcommand)]
@@ -87,6 +229,13 @@ pub enum Commands {
         #[command(subcommand)]
         command: GatewayCommands,
     },
+    /// Reports assembled from the config service
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    /// Print version information and probe the config host for compatibility
+    Version(VersionInfo),
 }
 
 #[derive(Debug, Subcommand)]
@@ -97,22 +246,47 @@ pub enum EnvCommands {
     Info(EnvInfo),
     /// Make a new keypair
     GenerateKeypair(GenerateKeypair),
+    /// Show the balances of a Solana wallet and the Orgs it manages
+    WalletInfo(WalletInfo),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NetIdCommands {
+    /// List the Helium NetIDs accepted by `org create-helium --net-id` and
+    /// their devaddr ranges
+    ListHelium,
+    /// List the NetIDs on chain with how much of each devaddr range has been
+    /// allocated to Orgs. Only Helium's NetIDs and those of Orgs in the
+    /// config service are looked up.
+    #[command(alias = "ls")]
+    List(ListNetIds),
 }
 
 #[derive(Debug, Subcommand)]
 pub enum GatewayCommands {
     /// Retrieve H3 index location for the given hotspot
-    Location(GetHotspot),
+    Location(GetHotspotLocation),
     /// Retrieve the on-chain registered info for the hotspot
     Info(GetHotspot),
 }
 
+#[derive(Debug, Subcommand)]
+pub enum ReportCommands {
+    /// Orgs, constraints, Routes, and member counts in one document, with
+    /// anomalies flagged
+    Inventory(InventoryReport),
+}
+
 #[derive(Debug, Subcommand)]
 pub enum RouteCommands {
     /// List all Routes for an OUI
+    #[command(alias = "ls")]
     List(ListRoutes),
     /// Get a Route by ID
     Get(GetRoute),
+    /// Print a short human readable summary of a Route, for sharing during
+    /// incidents
+    Summary(RouteSummary),
     /// Create new Route
     New(NewRoute),
     /// Update Route component
@@ -121,17 +295,25 @@ pub enum RouteCommands {
         command: RouteUpdateCommand,
     },
     /// Operate on EUIs for a Route
+    #[command(alias = "eui")]
     Euis {
         #[command(subcommand)]
         command: EuiCommands,
     },
     /// Operate on Devaddrs for a Route
+    #[command(alias = "dev")]
     Devaddrs {
         #[command(subcommand)]
         command: DevaddrCommands,
     },
     /// Remove Route
     Delete(DeleteRoute),
+    /// Measure how long a change takes to reach the Route stream packet
+    /// routers subscribe to, by adding and removing a canary Session Key Filter
+    ProbePropagation(ProbePropagation),
+    /// Print the changes the config service sends packet routers, one json
+    /// object per line, or post each to a webhook
+    Stream(StreamRoutes),
     /// Turn on routing for Route.
     ///
     /// The route field `locked` supersedes this setting.
@@ -143,16 +325,61 @@ pub enum RouteCommands {
     #[command(alias = "disable")]
     Deactivate(DeactivateRoute),
     /// Operate on Session Key Filters for a Route.
+    #[command(alias = "skf")]
     Skfs {
         #[command(subcommand)]
         command: SkfCommands,
     },
+    /// Print the JSON Schema for Route files
+    Schema,
+    /// Route copies saved before committed changes
+    Backups {
+        #[command(subcommand)]
+        command: RouteBackupCommands,
+    },
+    /// List the saved versions of a Route with what changed after each, from
+    /// the backups taken before committed changes
+    History(RouteHistory),
+    /// Print a Route as it was at a past time, as far as its backups show
+    Show(ShowRouteAt),
+    /// Rewrite a route file canonically, with sorted members, so diffs of
+    /// stored route files only show real changes. Files of an older
+    /// schema_version are migrated, and unknown fields are an error rather
+    /// than being dropped.
+    Fmt(FmtRouteFile),
+    /// Prepare a Route in a local file without contacting the config service
+    File {
+        #[command(subcommand)]
+        command: RouteFileCommands,
+    },
 }
 
 #[derive(Debug, Args)]
 pub struct GetHotspot {
     #[arg(long)]
     pub hotspot: PublicKey,
+    /// Report whether the hotspot is on a denylist, read from an http(s) URL
+    /// or a file of pubkeys, one per line
+    #[arg(long)]
+    pub check_denylist: Option<String>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct GetHotspotLocation {
+    #[arg(long)]
+    pub hotspot: PublicKey,
+    /// Include the location, gain, and elevation asserted on chain, and when
+    /// they were last asserted
+    #[arg(long)]
+    pub with_asserted_meta: bool,
+    #[arg(from_global)]
+    pub solana_url: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -163,8 +390,29 @@ pub struct GetHotspot {
 
 #[derive(Debug, Args)]
 pub struct ListRoutes {
+    /// Defaults to the Org managed by your keypair
     #[arg(long, env = ENV_OUI)]
-    pub oui: Oui,
+    pub oui: Option<Oui>,
+    #[arg(long, value_enum)]
+    pub sort: Option<RouteSort>,
+    /// Columns for `table` and `csv` output
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "id,oui,net_id,server,protocol,max_copies,active,locked"
+    )]
+    pub columns: Vec<RouteColumn>,
+    #[arg(long, value_enum, default_value = "json")]
+    pub output: RouteListFormat,
+    /// Include the number of Devaddr Ranges, EUIs, and Session Key Filters
+    /// of each Route
+    #[arg(long)]
+    pub with_counts: bool,
+    #[arg(from_global)]
+    pub concurrency: NonZeroUsize,
+    #[arg(from_global)]
+    pub no_cache: bool,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -175,12 +423,59 @@ pub struct ListRoutes {
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct InventoryReport {
+    /// Defaults to the Org managed by your keypair
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Option<Oui>,
+    /// Report on every Org, requires an admin keypair. Takes precedence over
+    /// --oui.
+    #[arg(long)]
+    pub all: bool,
+    /// Write the report here instead of printing it
+    #[arg(long)]
+    pub out_file: Option<PathBuf>,
+    #[arg(from_global)]
+    pub concurrency: NonZeroUsize,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
 #[derive(Debug, Args)]
 pub struct GetRoute {
+    /// Validated when the command runs, so a malformed id still gets
+    /// suggestions with `--oui`
     #[arg(short, long)]
     pub route_id: String,
     #[arg(short, long)]
     pub stats: bool,
+    /// Include the Route's Devaddr Ranges, EUIs, and Session Key Filters
+    #[arg(long)]
+    pub with_members: bool,
+    /// Only include the number of each member type
+    #[arg(long, requires = "with_members")]
+    pub counts_only: bool,
+    /// OUI to search for similar Route IDs when the Route is not found
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Option<Oui>,
+    #[arg(from_global)]
+    pub no_cache: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteSummary {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -193,28 +488,100 @@ pub struct GetRoute {
 pub struct NewRoute {
     #[arg(long, env = ENV_NET_ID, default_value = "000024")]
     pub net_id: HexNetID,
+    /// Defaults to the Org managed by your keypair
     #[arg(long, env = ENV_OUI)]
-    pub oui: Oui,
+    pub oui: Option<Oui>,
     #[arg(long, env = ENV_MAX_COPIES, default_value = "5")]
     pub max_copies: u32,
+    /// Create the Route described by a route file, then add its EUIs and
+    /// Devaddr Ranges to the new Route
+    ///
+    /// The file's `id` is replaced by the ID the config service assigns, and
+    /// `--net-id` and `--max-copies` are ignored. `--oui`, when given or set
+    /// by a profile, must be the file's OUI.
+    #[arg(long, env = ENV_ROUTE_TEMPLATE)]
+    pub from_file: Option<PathBuf>,
+    /// Accept EUI pairs in `--from-file` with the zero app_eui
+    #[arg(long, requires = "from_file")]
+    pub wildcard_app_eui: bool,
+
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub strict: bool,
+    #[arg(long)]
+    pub commit: bool,
+}
 
+#[derive(Debug, Args)]
+pub struct ProbePropagation {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// Seconds to wait for each change to arrive on the stream
+    #[arg(long, default_value = "60")]
+    pub wait_timeout: u64,
+    /// Subscribing to the Route stream requires a packet router key
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+    /// Add and remove the canary filter
     #[arg(long)]
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct StreamRoutes {
+    /// Post each change as json to this URL instead of printing it
+    #[arg(long, value_parser = reqwest::Url::parse)]
+    pub webhook: Option<reqwest::Url>,
+    /// Only changes to this Route and its members
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: Option<String>,
+    /// Only changes to the Routes of this OUI and their members
+    #[arg(long)]
+    pub oui: Option<Oui>,
+    /// Only changes from this RFC3339 time, or this long ago, e.g. `15m`.
+    /// Older changes are replayed when the config service supports it.
+    /// Defaults to the `--state-file` timestamp, or now.
+    #[arg(long, value_parser = timestamp::parse_since)]
+    pub since: Option<u64>,
+    /// Record the timestamp of the last change received here, and resume
+    /// from it when started again
+    #[arg(long)]
+    pub state_file: Option<PathBuf>,
+    /// Subscribing to the Route stream requires a packet router key
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
 #[derive(Debug, Args)]
 pub struct DeleteRoute {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    /// Remove the Route's EUIs, Devaddr Ranges, and Session Key Filters
+    /// before deleting it, so packet routers receive explicit removes
+    #[arg(long)]
+    pub purge_members: bool,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -224,11 +591,19 @@ pub struct DeleteRoute {
 
 #[derive(Debug, Args)]
 pub struct ActivateRoute {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -238,11 +613,26 @@ pub struct ActivateRoute {
 
 #[derive(Debug, Args)]
 pub struct DeactivateRoute {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    /// Reactivate the route after this long, e.g. `2h` or `90m`
+    #[arg(long = "for", value_parser = humantime::parse_duration)]
+    pub reactivate_after: Option<Duration>,
+    /// Keep running until `--for` has passed and reactivate the route,
+    /// instead of printing how to schedule the reactivation
+    #[arg(long, requires = "reactivate_after")]
+    pub stay: bool,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -262,24 +652,54 @@ pub enum RouteUpdateCommand {
     /// This will change the protocol to Gwmp AND add
     /// a region mapping if one was provided.
     AddGwmpRegion(AddGwmpRegion),
-    /// Remove a region mapping from the Gwmp Protocol.
+    /// Remove region mappings from the Gwmp Protocol.
     /// This only works if the protocol is already gwmp.
     RemoveGwmpRegion(RemoveGwmpRegion),
+    /// Remove the Route's protocol entirely, which stops routing to it.
+    /// Committing requires `--force`.
+    ClearProtocol(ClearProtocol),
     /// Set the Route Protocol to PacketRouter (GRPC)
     PacketRouter(UpdatePacketRouter),
     /// Set route `ignore_empty_skf` boolean
     IgnoreEmptySkf(SetIgnoreEmptySkf),
 }
 
+/// The Route an update starts from, the live Route or a previously exported
+/// route file.
+#[derive(Debug, Args)]
+pub struct RouteTarget {
+    #[arg(
+        short,
+        long,
+        value_parser = validate_route_id,
+        required_unless_present = "route_file"
+    )]
+    pub route_id: Option<String>,
+    /// Update a previously exported route file instead of the live Route.
+    /// The diff is against the file and the whole result is pushed.
+    #[arg(long, conflicts_with = "route_id")]
+    pub route_file: Option<PathBuf>,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct UpdateMaxCopies {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[command(flatten)]
+    pub target: RouteTarget,
     #[arg(short, long)]
     pub max_copies: u32,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -289,15 +709,23 @@ pub struct UpdateMaxCopies {
 
 #[derive(Debug, Args)]
 pub struct UpdateServer {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[command(flatten)]
+    pub target: RouteTarget,
     #[arg(long)]
     pub host: String,
-    #[arg(long)]
+    #[arg(long, value_parser = server::parse_port)]
     pub port: u32,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -307,8 +735,8 @@ pub struct UpdateServer {
 
 #[derive(Debug, Args)]
 pub struct UpdateHttp {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[command(flatten)]
+    pub target: RouteTarget,
     /// Dedupe timeout in ms
     #[arg(short, long, default_value = "250")]
     pub dedupe_timeout: u32,
@@ -318,64 +746,140 @@ pub struct UpdateHttp {
     #[arg(short, long)]
     pub path: String,
     /// Authorization Header
-    #[arg(short, long)]
+    ///
+    /// Prefer the environment variable or `--auth-header-file`, a flag value
+    /// is kept in shell history and visible in process listings.
+    #[arg(short, long, env = ENV_HTTP_AUTH_HEADER, hide_env_values = true)]
     pub auth_header: Option<String>,
-    /// Receiver NSID
+    /// Read the Authorization Header from this file, or stdin with `-`.
+    /// Overrides `--auth-header`.
     #[arg(long)]
+    pub auth_header_file: Option<PathBuf>,
+    /// Receiver NSID, the NetID of the home network, required by roaming
+    /// partners
+    #[arg(long, value_parser = server::validate_receiver_nsid)]
     pub receiver_nsid: Option<String>,
 
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+    /// Required to commit an update that changes the protocol type
+    #[arg(long)]
+    pub allow_protocol_change: bool,
     #[arg(long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct UpdatePacketRouter {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[command(flatten)]
+    pub target: RouteTarget,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+    /// Required to commit an update that changes the protocol type
+    #[arg(long)]
+    pub allow_protocol_change: bool,
     #[arg(long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct AddGwmpRegion {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[command(flatten)]
+    pub target: RouteTarget,
     #[arg(value_enum)]
     pub region: Region,
+    #[arg(value_parser = server::parse_port)]
     pub region_port: u32,
+    /// Overwrite the port of a region that is already mapped
+    #[arg(long)]
+    pub replace: bool,
 
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+    /// Required to commit an update that changes the protocol type
+    #[arg(long)]
+    pub allow_protocol_change: bool,
     #[arg(long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct RemoveGwmpRegion {
-    #[arg(short, long)]
-    pub route_id: String,
-    #[arg(value_enum)]
-    pub region: Region,
+    #[command(flatten)]
+    pub target: RouteTarget,
+    /// Every region is removed in a single update
+    #[arg(value_enum, required = true)]
+    pub regions: Vec<Region>,
+
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(long)]
+    pub commit: bool,
+}
 
+#[derive(Debug, Args)]
+pub struct ClearProtocol {
+    #[command(flatten)]
+    pub target: RouteTarget,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -385,13 +889,21 @@ pub struct RemoveGwmpRegion {
 
 #[derive(Debug, Args)]
 pub struct SetIgnoreEmptySkf {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[command(flatten)]
+    pub target: RouteTarget,
     #[arg(short, long)]
     pub ignore: bool,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -400,60 +912,381 @@ pub struct SetIgnoreEmptySkf {
 }
 
 #[derive(Debug, Subcommand)]
-pub enum EuiCommands {
-    /// Get all EUI pairs for a Route
-    List(ListEuis),
-    /// Add EUI pair to Route
-    Add(AddEui),
-    /// Remove EUI pair from Route
-    Remove(RemoveEui),
-    /// Remove ALL EUI Pairs from Route
-    Clear(ClearEuis),
-}
-
-#[derive(Debug, Subcommand)]
-pub enum DevaddrCommands {
-    /// Get all Devaddr Ranges for a Route
-    List(ListDevaddrs),
-    /// Add Devaddr Range to Route
-    Add(AddDevaddr),
-    /// Remove Devaddr Range from Route
-    Remove(RemoveDevaddr),
-    /// Print subnet mask for all devaddr ranges in a Route.
-    SubnetMask(RouteSubnetMask),
-    /// Remove ALL Devaddr Ranges from Route
-    Clear(ClearDevaddrs),
+pub enum RouteBackupCommands {
+    /// List backups for a Route, or the Routes with backups
+    List(ListBackups),
+    /// Print a backup, defaults to the most recent
+    Show(ShowBackup),
+    /// Push a backup to the config service, defaults to the most recent
+    Restore(RestoreBackup),
 }
 
-#[derive(Debug, Subcommand)]
-pub enum SkfCommands {
-    /// Get all Session Key Filters for a Route
-    List(ListFilters),
-    /// Get all Session Key Filters for a Route and Devaddr
-    Get(GetFilters),
-    /// Update a Route to add a Session Key Filter to a Devaddr
-    Add(AddFilter),
-    /// Update a Route to remove a Session Key Filter from a Devaddr
-    Remove(RemoveFilter),
-    /// Remove ALL Session Key Filters from a Route
-    Clear(ClearFilters),
-    /// Update a Route by reading a list of Session Key Filters from
-    /// a file and adding or removing them
-    Update(UpdateFilters),
+#[derive(Debug, Args)]
+pub struct ListBackups {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: Option<String>,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
 }
 
-#[derive(Debug, Subcommand)]
+#[derive(Debug, Args)]
+pub struct RouteHistory {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ShowRouteAt {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// An RFC3339 time, or how long ago, e.g. `2024-03-05T09:00:00Z` or `3d`
+    #[arg(long, value_parser = timestamp::parse_since)]
+    pub at: u64,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ShowBackup {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// Backup name from `route backups list`
+    #[arg(short, long)]
+    pub backup: Option<String>,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct RestoreBackup {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// Backup name from `route backups list`
+    #[arg(short, long)]
+    pub backup: Option<String>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub force: bool,
+    #[arg(from_global)]
+    pub backup_dir: Option<PathBuf>,
+    #[arg(from_global)]
+    pub backup_retention: usize,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    /// Required to commit a restore that changes the protocol type
+    #[arg(long)]
+    pub allow_protocol_change: bool,
+    #[arg(long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RouteFileCommands {
+    /// Write a new Route to a local file
+    New(RouteFileNew),
+    /// Print the Route in a local file
+    Show(RouteFileShow),
+    /// Set max number of packets to buy
+    SetMaxCopies(RouteFileMaxCopies),
+    /// Set server destination details
+    SetServer(RouteFileServer),
+    /// Set the Route Protocol to Http
+    SetHttp(RouteFileHttp),
+    /// Set the Route Protocol to PacketRouter (GRPC)
+    SetPacketRouter(RouteFileShow),
+    /// Set the Route Protocol to Gwmp (UDP) and add a region mapping
+    AddGwmpRegion(RouteFileAddGwmpRegion),
+    /// Remove a region mapping from the Gwmp Protocol
+    RemoveGwmpRegion(RouteFileRemoveGwmpRegion),
+    /// Add an EUI pair to the Route file
+    AddEui(RouteFileEui),
+    /// Remove an EUI pair from the Route file
+    RemoveEui(RouteFileEui),
+    /// Add a Devaddr Range to the Route file
+    AddDevaddr(RouteFileDevaddr),
+    /// Remove a Devaddr Range from the Route file
+    RemoveDevaddr(RouteFileDevaddr),
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileNew {
+    /// Path of the Route file to write
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(long, env = ENV_NET_ID, default_value = "000024")]
+    pub net_id: HexNetID,
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    #[arg(long, env = ENV_MAX_COPIES, default_value = "5")]
+    pub max_copies: u32,
+    /// Overwrite <route_file> if it already exists
+    #[arg(long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct FmtRouteFile {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    /// Fail if the file is not already canonical, without rewriting it
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileShow {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileMaxCopies {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(short, long)]
+    pub max_copies: u32,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileServer {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(long)]
+    pub host: String,
+    #[arg(long, value_parser = server::parse_port)]
+    pub port: u32,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileHttp {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    /// Dedupe timeout in ms
+    #[arg(short, long, default_value = "250")]
+    pub dedupe_timeout: u32,
+    /// Just the path part of the Server URL
+    ///
+    /// The rest will be taken from the Server {host}:{port}
+    #[arg(short, long)]
+    pub path: String,
+    /// Authorization Header
+    ///
+    /// Prefer the environment variable or `--auth-header-file`, a flag value
+    /// is kept in shell history and visible in process listings.
+    #[arg(short, long, env = ENV_HTTP_AUTH_HEADER, hide_env_values = true)]
+    pub auth_header: Option<String>,
+    /// Read the Authorization Header from this file, or stdin with `-`.
+    /// Overrides `--auth-header`.
+    #[arg(long)]
+    pub auth_header_file: Option<PathBuf>,
+    /// Receiver NSID, the NetID of the home network, required by roaming
+    /// partners
+    #[arg(long, value_parser = server::validate_receiver_nsid)]
+    pub receiver_nsid: Option<String>,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileAddGwmpRegion {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(value_enum)]
+    pub region: Region,
+    #[arg(value_parser = server::parse_port)]
+    pub region_port: u32,
+    /// Overwrite the port of a region that is already mapped
+    #[arg(long)]
+    pub replace: bool,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileRemoveGwmpRegion {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(value_enum, required = true)]
+    pub regions: Vec<Region>,
+    #[arg(from_global)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileEui {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(short, long, value_parser = hex_field::validate_eui)]
+    pub dev_eui: hex_field::HexEui,
+    #[arg(short, long, value_parser = hex_field::validate_eui)]
+    pub app_eui: hex_field::HexEui,
+    /// Accept adding the zero app_eui, matching joins from `dev_eui`
+    /// regardless of app_eui
+    #[arg(long)]
+    pub wildcard_app_eui: bool,
+    #[arg(from_global)]
+    pub strict: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RouteFileDevaddr {
+    #[arg(short = 'f', long)]
+    pub route_file: PathBuf,
+    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
+    pub start_addr: hex_field::HexDevAddr,
+    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
+    pub end_addr: hex_field::HexDevAddr,
+    #[arg(from_global)]
+    pub strict: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EuiCommands {
+    /// Get all EUI pairs for a Route
+    #[command(alias = "ls")]
+    List(ListEuis),
+    /// Add EUI pair to Route
+    Add(AddEui),
+    /// Remove EUI pair from Route
+    #[command(alias = "rm")]
+    Remove(RemoveEui),
+    /// Remove ALL EUI Pairs from Route
+    Clear(ClearEuis),
+    /// Update a Route by reading a list of EUI pairs from a file
+    /// and adding or removing them
+    ///
+    /// With `--idempotent`, pairs already added or removed are skipped.
+    Update(UpdateEuis),
+    /// Add every EUI pair from one Route to another
+    Copy(CopyRouteMembers),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DevaddrCommands {
+    /// Get all Devaddr Ranges for a Route
+    #[command(alias = "ls")]
+    List(ListDevaddrs),
+    /// Add Devaddr Range to Route
+    Add(AddDevaddr),
+    /// Remove Devaddr Range from Route
+    #[command(alias = "rm")]
+    Remove(RemoveDevaddr),
+    /// Swap one Devaddr Range of a Route for another in a single request, so
+    /// the Route is never without either
+    Replace(ReplaceDevaddr),
+    /// Print subnet mask for all devaddr ranges in a Route.
+    SubnetMask(RouteSubnetMask),
+    /// Write a Route's Devaddr Ranges as CIDRs for firewall rules, or as the
+    /// legacy packet-purchaser config
+    Export(ExportDevaddrs),
+    /// Remove ALL Devaddr Ranges from Route
+    Clear(ClearDevaddrs),
+    /// Update a Route by reading a list of Devaddr Ranges from a file
+    /// and adding or removing them
+    Update(UpdateDevaddrs),
+    /// Add every Devaddr Range from one Route to another
+    Copy(CopyRouteMembers),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SkfCommands {
+    /// Get all Session Key Filters for a Route
+    #[command(alias = "ls")]
+    List(ListFilters),
+    /// Get all Session Key Filters for a Route and Devaddr
+    Get(GetFilters),
+    /// Update a Route to add a Session Key Filter to a Devaddr
+    Add(AddFilter),
+    /// Update a Route to remove a Session Key Filter from a Devaddr
+    #[command(alias = "rm")]
+    Remove(RemoveFilter),
+    /// Change the max_copies of an existing Session Key Filter in a single update
+    SetMaxCopies(SetFilterMaxCopies),
+    /// Remove ALL Session Key Filters from a Route
+    Clear(ClearFilters),
+    /// Update a Route by reading a list of Session Key Filters from
+    /// a file and adding or removing them
+    Update(UpdateFilters),
+    /// Write every Session Key Filter of a Route to a file, sorted by devaddr
+    /// and session key
+    Export(ExportFilters),
+    /// Compare a Route's Session Key Filters to an LNS export, without
+    /// making changes
+    Verify(VerifyFilters),
+    /// Move Session Key Filters from one Route to another
+    Move(MoveFilters),
+    /// Prompt for Session Key Filters one at a time, then add them all after
+    /// confirming a summary
+    Wizard(FilterWizard),
+}
+
+#[derive(Debug, Subcommand)]
 pub enum OrgCommands {
     /// Get all Orgs
+    #[command(alias = "ls")]
     List(ListOrgs),
     /// Get an Organization you own
     Get(GetOrg),
+    /// List the Organizations your keypair owns or is a delegate for
+    Whoami(Whoami),
+    /// List the Organizations a key is the owner, payer, or a delegate of
+    Find(FindOrg),
+    /// Compare an Org's Solana records with the config service
+    Verify(VerifyOrg),
+    /// Suggest the next unallocated block of devaddrs in an Org's
+    /// constraints, skipping ranges assigned to its Routes
+    NextFree(NextFreeDevaddrs),
     /// Create a new Helium Organization
     CreateHelium(CreateHelium),
     /// Create a new Roaming Organization (admin only)
     CreateRoaming(CreateRoaming),
+    /// Create a Helium Organization, wait for it to sync, and create its
+    /// first Route covering the Org's devaddrs
+    Onboard(OnboardOrg),
     /// Enable a locked Oui
     Enable(EnableOrg),
+    /// Transfer an Org to a new owner, removing its delegate keys first if
+    /// asked, and wait for the new owner to be visible
+    ///
+    /// Without `--commit` the steps are printed in the order they will run.
+    Transfer(OrgTransfer),
     /// Update Org record
     Update {
         #[command(subcommand)]
@@ -463,8 +1296,20 @@ pub enum OrgCommands {
 
 #[derive(Debug, Args)]
 pub struct ListFilters {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    /// `jsonl` writes one record per line as they arrive
+    #[arg(long, value_enum, default_value = "json")]
+    pub out_format: OutFormat,
+    /// Write the list to a file instead of printing it
+    #[arg(long, required_if_eq("out_format", "jsonl"))]
+    pub out_file: Option<PathBuf>,
+    /// Only list filters for devaddrs in a subnet, e.g. `48000000/22`
+    #[arg(long, value_parser = DevaddrConstraint::from_cidr)]
+    pub subnet: Option<DevaddrConstraint>,
+    /// Only list filters for devaddrs starting with these hex digits, e.g. `4800`
+    #[arg(long, value_parser = DevaddrConstraint::from_prefix, conflicts_with = "subnet")]
+    pub devaddr_prefix: Option<DevaddrConstraint>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -475,7 +1320,7 @@ pub struct ListFilters {
 
 #[derive(Debug, Args)]
 pub struct GetFilters {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub devaddr: hex_field::HexDevAddr,
@@ -489,80 +1334,214 @@ pub struct GetFilters {
 
 #[derive(Debug, Args)]
 pub struct AddFilter {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_devaddr,
+        required_unless_present = "stdin"
+    )]
+    pub devaddr: Option<hex_field::HexDevAddr>,
+    /// Hex encoded session key
+    #[arg(short, long, required_unless_present = "stdin")]
+    pub session_key: Option<String>,
+    /// Defaults to the Route's max_copies when neither the argument nor the
+    /// environment is set
+    #[arg(short, long, env = ENV_MAX_COPIES)]
+    pub max_copies: Option<u32>,
+    /// Read `<devaddr> <session_key> [max_copies]` lines from stdin instead
+    #[arg(long, conflicts_with_all = ["devaddr", "session_key"])]
+    pub stdin: bool,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
+    /// Add EUI entry to a Route
     #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RemoveFilter {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub devaddr: hex_field::HexDevAddr,
     /// Hex encoded session key
     #[arg(short, long)]
     pub session_key: String,
-    #[arg(short, long)]
-    pub max_copies: Option<u32>,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
     /// Add EUI entry to a Route
     #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct RemoveFilter {
-    #[arg(short, long)]
+pub struct SetFilterMaxCopies {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub devaddr: hex_field::HexDevAddr,
     /// Hex encoded session key
     #[arg(short, long)]
     pub session_key: String,
+    #[arg(short, long)]
+    pub max_copies: u32,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Add EUI entry to a Route
+    #[arg(from_global)]
+    pub no_preflight: bool,
     #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ClearFilters {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
     #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportFilters {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    #[arg(long)]
+    pub out_file: PathBuf,
+    /// `json` writes an array, `jsonl` one record per line
+    #[arg(long, value_enum, default_value = "json")]
+    pub out_format: OutFormat,
+    /// Compress the file with gzip as it is written
+    #[arg(long)]
+    pub gzip: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveFilters {
+    /// Route to remove the filters from
+    #[arg(long, value_parser = validate_route_id)]
+    pub from: String,
+    /// Route to add the filters to
+    #[arg(long, value_parser = validate_route_id)]
+    pub to: String,
+    /// Only move the filters for this devaddr
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub devaddr: Option<hex_field::HexDevAddr>,
     #[arg(from_global)]
     pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
     #[arg(short, long)]
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct FilterWizard {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// Suggested max_copies for each filter, defaults to the Route's
+    #[arg(short, long, env = ENV_MAX_COPIES)]
+    pub max_copies: Option<u32>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyFilters {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// Path to a json list of `{devaddr, session_key, max_copies}` records, or
+    /// CSV with the header `devaddr,session_key[,max_copies]`. `-` reads from
+    /// stdin. max_copies is only compared when given.
+    #[arg(long)]
+    pub against_lns: PathBuf,
+    /// Defaults to `csv` for files ending in `.csv`, `json` otherwise
+    #[arg(long, value_enum)]
+    pub format: Option<UpdateFileFormat>,
+    #[arg(from_global)]
+    pub strict: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
 #[derive(Debug, Args)]
 pub struct UpdateFilters {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
-    /// Path to a file containing a json-encoded list of route_skf_update_v1 records
+    /// Path to a file containing a json-encoded list of route_skf_update_v1 records,
+    /// or CSV with the header `devaddr,session_key,action,max_copies`.
+    /// `-` reads from stdin
     #[arg(short, long)]
     pub update_file: PathBuf,
+    /// Defaults to `csv` for files ending in `.csv`, `json` otherwise
+    #[arg(long, value_enum)]
+    pub format: Option<UpdateFileFormat>,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub strict: bool,
     #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ListEuis {
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     /// Filter the list of EUIs by provided app_eui.
     #[arg(short, long, value_parser = hex_field::validate_eui)]
@@ -570,6 +1549,18 @@ pub struct ListEuis {
     /// Filter the list of EUIS by provided dev_eui.
     #[arg(short, long, value_parser = hex_field::validate_eui)]
     pub dev_eui: Option<hex_field::HexEui>,
+    /// Only list pairs where either EUI is the zero wildcard
+    #[arg(long)]
+    pub wildcards_only: bool,
+    /// Print the number of pairs, in total and per app_eui, instead of the pairs
+    #[arg(long, conflicts_with = "out_file")]
+    pub count: bool,
+    /// `jsonl` writes one record per line as they arrive
+    #[arg(long, value_enum, default_value = "json")]
+    pub out_format: OutFormat,
+    /// Write the list to a file instead of printing it
+    #[arg(long, required_if_eq("out_format", "jsonl"))]
+    pub out_file: Option<PathBuf>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -582,9 +1573,19 @@ pub struct ListEuis {
 pub struct AddEui {
     #[arg(short, long, value_parser = hex_field::validate_eui)]
     pub dev_eui: hex_field::HexEui,
-    #[arg(short, long, value_parser = hex_field::validate_eui)]
-    pub app_eui: hex_field::HexEui,
-    #[arg(long)]
+    /// A zero app_eui is only accepted with `--wildcard-app-eui`
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_eui,
+        required_unless_present = "wildcard_app_eui"
+    )]
+    pub app_eui: Option<hex_field::HexEui>,
+    /// Add the pair with the zero app_eui, matching joins from `dev_eui`
+    /// regardless of app_eui
+    #[arg(long, conflicts_with = "app_eui")]
+    pub wildcard_app_eui: bool,
+    #[arg(long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(from_global)]
     pub config_host: String,
@@ -592,6 +1593,10 @@ pub struct AddEui {
     pub config_pubkey: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
     /// Add EUI entry to a Route
     #[arg(short, long)]
     pub commit: bool,
@@ -603,7 +1608,7 @@ pub struct RemoveEui {
     pub dev_eui: hex_field::HexEui,
     #[arg(short, long, value_parser = hex_field::validate_eui)]
     pub app_eui: hex_field::HexEui,
-    #[arg(long)]
+    #[arg(long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(from_global)]
     pub config_host: String,
@@ -611,18 +1616,44 @@ pub struct RemoveEui {
     pub config_pubkey: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
     /// Remove EUI entry from the Route
     #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct ClearEuis {
+pub struct CopyRouteMembers {
+    /// Route to read from, it is not changed
+    #[arg(long, value_parser = validate_route_id)]
+    pub from: String,
+    /// Route to add to
+    #[arg(long, value_parser = validate_route_id)]
+    pub to: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
     #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ClearEuis {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -632,9 +1663,52 @@ pub struct ClearEuis {
 }
 
 #[derive(Debug, Args)]
-pub struct ListDevaddrs {
+pub struct UpdateEuis {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    /// Path to a file containing a json-encoded list of eui update (app_eui, dev_eui, action) records
+    #[arg(
+        short,
+        long,
+        required_unless_present = "resume",
+        conflicts_with = "resume"
+    )]
+    pub update_file: Option<PathBuf>,
+    /// Retry the unprocessed records written by a previous partial failure
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
+    /// Accept adds with the zero app_eui
+    #[arg(long)]
+    pub wildcard_app_eui: bool,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub strict: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
     #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ListDevaddrs {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    /// `jsonl` writes one record per line as they arrive
+    #[arg(long, value_enum, default_value = "json")]
+    pub out_format: OutFormat,
+    /// Write the list to a file instead of printing it
+    #[arg(long, required_if_eq("out_format", "jsonl"))]
+    pub out_file: Option<PathBuf>,
+    /// Print the number of ranges and the devaddrs they cover instead of the ranges
+    #[arg(long, conflicts_with = "out_file")]
+    pub count: bool,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -645,45 +1719,131 @@ pub struct ListDevaddrs {
 
 #[derive(Debug, Args)]
 pub struct AddDevaddr {
+    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
+    pub start_addr: hex_field::HexDevAddr,
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_devaddr,
+        required_unless_present = "count"
+    )]
+    pub end_addr: Option<hex_field::HexDevAddr>,
+    /// Number of devaddrs in the range, instead of --end-addr
+    #[arg(long, conflicts_with = "end_addr")]
+    pub count: Option<u32>,
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: String,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
+    /// Add Devaddr entry to a Route
+    #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RemoveDevaddr {
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub start_addr: hex_field::HexDevAddr,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub end_addr: hex_field::HexDevAddr,
-    #[arg(long)]
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: String,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub idempotent: bool,
+    /// Remove Devaddr entry from a Route
+    #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplaceDevaddr {
+    #[arg(long, value_parser = validate_route_id)]
     pub route_id: String,
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub remove_start: hex_field::HexDevAddr,
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub remove_end: hex_field::HexDevAddr,
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub add_start: hex_field::HexDevAddr,
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub add_end: hex_field::HexDevAddr,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(short, long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ClearDevaddrs {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub no_preflight: bool,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
-    #[arg(from_global)]
-    pub keypair: PathBuf,
-    /// Add Devaddr entry to a Route
+    /// Remove ALL Devaddrs from a route
     #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct RemoveDevaddr {
-    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
-    pub start_addr: hex_field::HexDevAddr,
-    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
-    pub end_addr: hex_field::HexDevAddr,
-    #[arg(long)]
+pub struct UpdateDevaddrs {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    /// Path to a file containing a json-encoded list of devaddr update (start_addr, end_addr, action) records
+    #[arg(
+        short,
+        long,
+        required_unless_present = "resume",
+        conflicts_with = "resume"
+    )]
+    pub update_file: Option<PathBuf>,
+    /// Retry the unprocessed records written by a previous partial failure
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Remove Devaddr entry from a Route
+    #[arg(from_global)]
+    pub no_preflight: bool,
+    #[arg(from_global)]
+    pub strict: bool,
     #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct ClearDevaddrs {
-    #[arg(short, long)]
+pub struct RouteSubnetMask {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
     #[arg(from_global)]
     pub keypair: PathBuf,
@@ -691,15 +1851,17 @@ pub struct ClearDevaddrs {
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
-    /// Remove ALL Devaddrs from a route
-    #[arg(short, long)]
-    pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct RouteSubnetMask {
-    #[arg(short, long)]
+pub struct ExportDevaddrs {
+    #[arg(short, long, value_parser = validate_route_id)]
     pub route_id: String,
+    #[arg(long, value_enum, default_value = "cidr")]
+    pub format: DevaddrExportFormat,
+    /// Write the export to a file instead of printing it
+    #[arg(long)]
+    pub out_file: Option<PathBuf>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -728,6 +1890,16 @@ pub struct EnvInfo {
     pub oui: Option<Oui>,
     #[arg(long, env = ENV_MAX_COPIES)]
     pub max_copies: Option<u32>,
+    #[arg(long, env = ENV_SOLANA_URL, default_value="unset")]
+    pub solana_url: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct VersionInfo {
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
 }
 
 #[derive(Debug, Args)]
@@ -738,13 +1910,68 @@ pub struct GenerateKeypair {
     /// The helium network for which to issue keys
     #[arg(long, short, value_enum, default_value = "mainnet")]
     pub network: NetworkArg,
+    #[arg(long, value_enum, default_value = "ed25519")]
+    pub key_type: KeyTypeArg,
+    /// Also print the public key as a QR code
+    #[arg(long)]
+    pub qr: bool,
     /// overwrite <out_file> if it already exists
     #[arg(long)]
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct WalletInfo {
+    /// Solana keypair file, defaults to ~/.config/solana/id.json
+    #[arg(long, env = ENV_SOLANA_KEYPAIR)]
+    pub solana_keypair: Option<PathBuf>,
+    #[arg(from_global)]
+    pub solana_url: String,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ListNetIds {
+    /// Only NetIDs the Solana keypair is the authority of
+    #[arg(long)]
+    pub mine: bool,
+    /// Solana keypair file for `--mine`, defaults to ~/.config/solana/id.json
+    #[arg(long, env = ENV_SOLANA_KEYPAIR)]
+    pub solana_keypair: Option<PathBuf>,
+    #[arg(from_global)]
+    pub solana_url: String,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(from_global)]
+    pub concurrency: NonZeroUsize,
+}
+
 #[derive(Debug, Args)]
 pub struct ListOrgs {
+    /// Include each Org's devaddr constraints and subnet masks, for `json`
+    /// output
+    #[arg(long)]
+    pub with_constraints: bool,
+    /// Columns for `table` and `csv` output. `escrow_key` and `approved` are
+    /// read from the Org's account on Solana.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "oui,owner,payer,locked"
+    )]
+    pub columns: Vec<OrgColumn>,
+    #[arg(long, value_enum, default_value = "json")]
+    pub output: OrgListFormat,
+    #[arg(from_global)]
+    pub concurrency: NonZeroUsize,
+    #[arg(from_global)]
+    pub solana_url: String,
     #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
@@ -753,9 +1980,60 @@ pub struct ListOrgs {
 
 #[derive(Debug, Args)]
 pub struct GetOrg {
-    #[arg(long, env = "HELIUM_OUI")]
+    /// Defaults to the Org managed by your keypair
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Option<Oui>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct NextFreeDevaddrs {
+    /// Defaults to the Org managed by your keypair
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Option<Oui>,
+    /// Number of devaddrs in the block
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct Whoami {
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct FindOrg {
+    #[arg(long)]
+    pub pubkey: PublicKey,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyOrg {
+    #[arg(long)]
     pub oui: Oui,
     #[arg(from_global)]
+    pub solana_url: String,
+    #[arg(from_global)]
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
@@ -783,6 +2061,52 @@ pub struct CreateHelium {
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct OnboardOrg {
+    #[arg(long)]
+    pub owner: PublicKey,
+    /// Defaults to the owner
+    #[arg(long)]
+    pub payer: Option<PublicKey>,
+    #[arg(long)]
+    pub delegate: Option<Vec<PublicKey>>,
+    #[arg(long)]
+    pub devaddr_count: u64,
+    #[arg(long, value_enum)]
+    pub net_id: HeliumNetId,
+    /// Protocol of the Route created for the Org, no Route is created without it
+    #[arg(long, value_enum, requires_all = ["host", "port"])]
+    pub route_template: Option<RouteTemplate>,
+    /// Route server host
+    #[arg(long, requires = "route_template")]
+    pub host: Option<String>,
+    /// Route server port
+    #[arg(long, value_parser = server::parse_port, requires = "route_template")]
+    pub port: Option<u32>,
+    /// Path of the Server URL for `http-roaming`
+    #[arg(long, default_value = "/")]
+    pub path: String,
+    #[arg(long, env = ENV_MAX_COPIES, default_value = "5")]
+    pub max_copies: u32,
+    /// Seconds to wait for the new Org to become visible
+    #[arg(long, default_value = "60")]
+    pub wait_timeout: u64,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    #[arg(long)]
+    pub commit: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteTemplate {
+    PacketRouter,
+    HttpRoaming,
+}
+
 #[derive(Debug, Args)]
 pub struct CreateRoaming {
     #[arg(long)]
@@ -793,6 +2117,15 @@ pub struct CreateRoaming {
     pub delegate: Option<Vec<PublicKey>>,
     #[arg(long)]
     pub net_id: HexNetID,
+    /// Start of a devaddr constraint to add once the Org exists, paired in
+    /// order with `--end-addr`
+    #[arg(long, value_parser = hex_field::validate_devaddr, requires = "end_addr")]
+    pub start_addr: Vec<hex_field::HexDevAddr>,
+    #[arg(long, value_parser = hex_field::validate_devaddr, requires = "start_addr")]
+    pub end_addr: Vec<hex_field::HexDevAddr>,
+    /// Devaddr constraint as a subnet, e.g. `48000800/29`
+    #[arg(long, value_parser = DevaddrConstraint::from_cidr)]
+    pub cidr: Vec<DevaddrConstraint>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
@@ -803,10 +2136,31 @@ pub struct CreateRoaming {
     pub commit: bool,
 }
 
+impl CreateRoaming {
+    /// Constraints from `--start-addr/--end-addr` pairs followed by `--cidr`.
+    pub fn constraints(&self) -> Result<Vec<DevaddrConstraint>> {
+        if self.start_addr.len() != self.end_addr.len() {
+            return Err(anyhow::anyhow!(
+                "{} --start-addr but {} --end-addr, they must be given in pairs",
+                self.start_addr.len(),
+                self.end_addr.len()
+            ));
+        }
+        let mut constraints = self
+            .start_addr
+            .iter()
+            .zip(&self.end_addr)
+            .map(|(start, end)| DevaddrConstraint::new(*start, *end))
+            .collect::<Result<Vec<_>>>()?;
+        constraints.extend(self.cidr.iter().cloned());
+        Ok(constraints)
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum OrgUpdateCommand {
-    /// Update the org owner pubkey
-    Owner(OrgUpdateKey),
+    /// Transfer the org to a new owner pubkey
+    Owner(OrgUpdateOwner),
     /// Update the org payer pubkey
     Payer(OrgUpdateKey),
     /// Add delegate key to org
@@ -833,6 +2187,40 @@ pub struct OrgUpdateKey {
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+    #[arg(from_global)]
+    pub idempotent: bool,
+    /// After committing, poll the config service until the change is visible
+    #[arg(long)]
+    pub wait_for_config_service: bool,
+    /// Seconds to wait for the change to become visible
+    #[arg(long, default_value = "60")]
+    pub wait_timeout: u64,
+    #[arg(long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct OrgUpdateOwner {
+    #[arg(long, short)]
+    pub oui: u64,
+    /// The pubkey taking ownership, the signing keypair must be the current owner
+    #[arg(long, short = 'p', alias = "pubkey")]
+    pub new_owner: PublicKey,
+    /// Also replace the payer pubkey as part of the transfer
+    #[arg(long)]
+    pub payer: Option<PublicKey>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+    /// After committing, poll the config service until the change is visible
+    #[arg(long)]
+    pub wait_for_config_service: bool,
+    /// Seconds to wait for the change to become visible
+    #[arg(long, default_value = "60")]
+    pub wait_timeout: u64,
     #[arg(long)]
     pub commit: bool,
 }
@@ -867,6 +2255,41 @@ pub struct DevaddrUpdateConstraint {
     pub config_host: String,
     #[arg(from_global)]
     pub config_pubkey: String,
+    #[arg(from_global)]
+    pub idempotent: bool,
+    /// After committing, poll the config service until the change is visible
+    #[arg(long)]
+    pub wait_for_config_service: bool,
+    /// Seconds to wait for the change to become visible
+    #[arg(long, default_value = "60")]
+    pub wait_timeout: u64,
+    #[arg(long)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct OrgTransfer {
+    #[arg(long, short)]
+    pub oui: Oui,
+    /// The pubkey taking ownership, the signing keypair must be the current owner
+    #[arg(long)]
+    pub to: PublicKey,
+    /// Also replace the payer pubkey as part of the transfer
+    #[arg(long)]
+    pub payer: Option<PublicKey>,
+    /// Remove every delegate key in the same update as the transfer, while
+    /// the current owner still signs for the Org
+    #[arg(long)]
+    pub remove_delegates: bool,
+    /// Seconds to wait for the new owner to become visible
+    #[arg(long, default_value = "60")]
+    pub wait_timeout: u64,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
     #[arg(long)]
     pub commit: bool,
 }
@@ -890,9 +2313,15 @@ pub enum AdminCommands {
     /// Push a region params collection.
     LoadRegion(AdminLoadRegionParams),
     /// Add a pubkey
+    ///
+    /// The admin service cannot list keys, so adding an Oracle key that is
+    /// already registered is only reported by the service, if at all.
     AddKey(AdminAddKey),
     /// Remove a pubkey
     RemoveKey(AdminRemoveKey),
+    /// Map the devaddr constraints of every Org by NetID, with overlaps
+    /// between Orgs and unallocated gaps
+    ConstraintReport(AdminConstraintReport),
 }
 
 #[derive(Debug, Args)]
@@ -941,11 +2370,333 @@ pub struct AdminRemoveKey {
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct AdminConstraintReport {
+    /// Write the JSON report here instead of printing it
+    #[arg(long)]
+    pub out_file: Option<PathBuf>,
+    /// Also write the allocations, overlaps, and gaps as CSV
+    #[arg(long)]
+    pub csv: Option<PathBuf>,
+    #[arg(from_global)]
+    pub concurrency: NonZeroUsize,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[arg(from_global)]
+    pub config_pubkey: String,
+}
+
 pub fn subnet_mask(args: SubnetMask) -> Result<Msg> {
     let devaddr_range = DevaddrConstraint::new(args.start_addr, args.end_addr)?;
     Msg::ok(devaddr_range.to_subnet().pretty_json()?)
 }
 
+/// Rows, the first being the header, as aligned columns.
+pub(crate) fn format_table(rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|idx| rows.iter().map(|row| row[idx].len()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{value:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rows, the first being the header, as CSV.
+pub(crate) fn format_csv(rows: &[Vec<String>]) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| csv_field(value))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `value` as a CSV field, quoted when it holds a comma, quote, or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Records are submitted in chunks this size so a failure only
+/// leaves the unprocessed remainder to be retried. `--adaptive-chunking`
+/// sends smaller chunks while the config service is slow.
+pub const BULK_CHUNK_SIZE: usize = 100;
+
+/// The result of an `--idempotent` add or remove that found its change
+/// already in place.
+pub fn already_applied(state: String) -> Result<Msg> {
+    Msg::ok(format!("{state}, nothing to do"))
+}
+
+/// Read all of `path`, or stdin when `path` is `-`.
+pub fn read_input(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        return std::io::read_to_string(std::io::stdin()).context("reading stdin");
+    }
+    std::fs::read_to_string(path).context(format!("reading {}", path.display()))
+}
+
+/// The HTTP auth header from `file` when given, otherwise `auth_header`.
+/// A trailing newline in the file is not part of the header.
+pub fn auth_header(auth_header: Option<String>, file: Option<&Path>) -> Result<Option<String>> {
+    let Some(file) = file else {
+        return Ok(auth_header);
+    };
+    let header = read_input(file)?.trim_end_matches(['\r', '\n']).to_string();
+    if header.is_empty() {
+        anyhow::bail!("auth header file {} is empty", file.display());
+    }
+    Ok(Some(header))
+}
+
+/// Updates read from a file for a bulk command that can be resumed.
+pub struct BulkInput<T> {
+    pub updates: Vec<T>,
+    /// What was dropped or looks suspicious in the input.
+    pub report: Vec<String>,
+    /// Where unprocessed updates are written when a chunk fails.
+    pub failed_file: PathBuf,
+    resuming: bool,
+}
+
+impl<T: BulkUpdate + DeserializeOwned + Serialize> BulkInput<T> {
+    /// Read from `resume` when retrying a previous failure, otherwise from `update_file`.
+    pub fn read(update_file: Option<&Path>, resume: Option<&Path>, strict: bool) -> Result<Self> {
+        let (path, failed_file, resuming) = match (resume, update_file) {
+            (Some(resume), _) => (resume, resume.to_path_buf(), true),
+            (None, Some(update_file)) => (update_file, failed_file_for(update_file), false),
+            (None, None) => anyhow::bail!("an update file or `--resume` file is required"),
+        };
+        let data = std::fs::read_to_string(path).context("reading updates json file")?;
+        let updates = strict::from_str(&data, strict)
+            .context(format!("parsing update file {}", path.display()))?;
+        let normalized = normalize(updates)?;
+        Ok(Self {
+            report: normalized.report()?,
+            updates: normalized.updates,
+            failed_file,
+            resuming,
+        })
+    }
+
+    /// Record every update after the first `done` for a later `--resume`.
+    pub fn fail(&self, done: usize, err: anyhow::Error) -> Result<Msg> {
+        let remaining = &self.updates[done..];
+        std::fs::write(&self.failed_file, remaining.pretty_json()?)
+            .context("writing failed updates file")?;
+        Msg::err(format!(
+            "{err}\n{} of {} updates not applied, written to {}\nretry with `--resume {}`",
+            remaining.len(),
+            self.updates.len(),
+            self.failed_file.display(),
+            self.failed_file.display()
+        ))
+    }
+
+    /// `msg` preceded by the normalization report.
+    pub fn summarize(&self, msg: String) -> String {
+        let mut lines = self.report.clone();
+        lines.push(msg);
+        lines.join("\n")
+    }
+
+    /// Clean up the failed updates file once a resumed run has applied everything.
+    pub fn finish(&self) -> Result {
+        if self.resuming {
+            std::fs::remove_file(&self.failed_file).context("removing failed updates file")?;
+        }
+        Ok(())
+    }
+}
+
+/// `<input>.failed.json` next to the original input file.
+fn failed_file_for(update_file: &Path) -> PathBuf {
+    let mut name = update_file.as_os_str().to_owned();
+    name.push(".failed.json");
+    PathBuf::from(name)
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteSort {
+    Oui,
+    Id,
+    /// By `host:port`
+    Server,
+    MaxCopies,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+pub enum RouteColumn {
+    Id,
+    Oui,
+    NetId,
+    Server,
+    Protocol,
+    MaxCopies,
+    Active,
+    Locked,
+    IgnoreEmptySkf,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RouteListFormat {
+    /// The full Routes, ignoring `--columns`
+    #[default]
+    Json,
+    /// Aligned columns for reading in a terminal
+    Table,
+    Csv,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+pub enum OrgColumn {
+    Oui,
+    Owner,
+    Payer,
+    DelegateKeys,
+    Locked,
+    EscrowKey,
+    Approved,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrgListFormat {
+    /// The full Orgs, ignoring `--columns`
+    #[default]
+    Json,
+    /// Aligned columns for reading in a terminal
+    Table,
+    Csv,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateFileFormat {
+    Json,
+    Csv,
+}
+
+impl UpdateFileFormat {
+    /// `format` if given, otherwise guessed from the extension of `path`.
+    pub fn resolve(format: Option<Self>, path: &Path) -> Self {
+        format.unwrap_or_else(|| match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Json,
+        })
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DevaddrExportFormat {
+    /// One `devaddr/prefix` per line, the subnets covering every range
+    #[default]
+    Cidr,
+    /// A `{devaddrs, [...]}` term for a packet-purchaser sys.config
+    Erlang,
+    /// The ranges with their subnets, as `subnet-mask` prints them
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutFormat {
+    /// A single pretty printed array
+    #[default]
+    Json,
+    /// One compact record per line
+    Jsonl,
+}
+
+/// Collect `records` into pretty json, printing it or writing it to `out_file`.
+pub async fn write_json<T: Serialize>(
+    records: impl Stream<Item = Result<T>>,
+    out_file: Option<&Path>,
+) -> Result<Msg> {
+    let records: Vec<T> = records.try_collect().await?;
+    match out_file {
+        Some(path) => {
+            std::fs::write(path, records.pretty_json()?).context("writing out file")?;
+            Msg::ok(format!(
+                "wrote {} records to {}",
+                records.len(),
+                path.display()
+            ))
+        }
+        None => Msg::ok(records.pretty_json()?),
+    }
+}
+
+/// Write each of `records` to `out_file` as a line of json as soon as it arrives.
+pub async fn write_jsonl<T: Serialize>(
+    records: impl Stream<Item = Result<T>>,
+    out_file: &Path,
+) -> Result<Msg> {
+    let file = File::create(out_file).context("creating out file")?;
+    let count = write_stream(records, OutFormat::Jsonl, BufWriter::new(file)).await?;
+
+    Msg::ok(format!("wrote {count} records to {}", out_file.display()))
+}
+
+/// Write `records` to `out` as they arrive, without holding them in memory.
+/// Returns the number of records written.
+pub async fn write_stream<T: Serialize>(
+    records: impl Stream<Item = Result<T>>,
+    out_format: OutFormat,
+    mut out: impl Write,
+) -> Result<usize> {
+    futures::pin_mut!(records);
+    let mut count = 0;
+    if out_format == OutFormat::Json {
+        out.write_all(b"[")?;
+    }
+    while let Some(record) = records.try_next().await? {
+        match out_format {
+            OutFormat::Json => {
+                out.write_all(if count == 0 { b"\n  " } else { b",\n  " })?;
+                serde_json::to_writer(&mut out, &record)?;
+            }
+            OutFormat::Jsonl => {
+                serde_json::to_writer(&mut out, &record)?;
+                out.write_all(b"\n")?;
+            }
+        }
+        count += 1;
+    }
+    if out_format == OutFormat::Json {
+        out.write_all(if count == 0 { b"]\n" } else { b"\n]\n" })?;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+/// Write `records` in the requested `out_format`.
+pub async fn write_records<T: Serialize>(
+    records: impl Stream<Item = Result<T>>,
+    out_format: OutFormat,
+    out_file: Option<&Path>,
+) -> Result<Msg> {
+    match (out_format, out_file) {
+        (OutFormat::Jsonl, Some(path)) => write_jsonl(records, path).await,
+        (OutFormat::Jsonl, None) => Msg::err("`--out-file` is required for jsonl".to_string()),
+        (OutFormat::Json, out_file) => write_json(records, out_file).await,
+    }
+}
+
 pub trait PathBufKeypair {
     fn to_keypair(&self) -> Result<helium_crypto::Keypair>;
 }
@@ -956,3 +2707,128 @@ impl PathBufKeypair for PathBuf {
         Ok(helium_crypto::Keypair::try_from(&data[..])?)
     }
 }
+
+/// The network a config host serves, from its name. `None` for hosts that
+/// name neither network, e.g. a local config service.
+fn config_host_network(config_host: &str) -> Option<helium_crypto::Network> {
+    let host = config_host.to_lowercase();
+    if host.contains("mainnet") {
+        Some(helium_crypto::Network::MainNet)
+    } else if host.contains("testnet") {
+        Some(helium_crypto::Network::TestNet)
+    } else {
+        None
+    }
+}
+
+/// Keypairs sign for one network, a config service rejects requests signed
+/// for the other. A warning when the keypair at `keypair` does not belong to
+/// the network of `config_host`, missing or unreadable keypairs are left to
+/// the commands that use them.
+pub fn keypair_network_warning(keypair: &Path, config_host: &str) -> Option<String> {
+    let keypair = keypair.to_path_buf().to_keypair().ok()?;
+    network_mismatch(keypair.public_key().network, config_host)
+}
+
+fn network_mismatch(network: helium_crypto::Network, config_host: &str) -> Option<String> {
+    let host_network = config_host_network(config_host)?;
+    if network == host_network {
+        return None;
+    }
+    Some(format!(
+        "your keypair is a {network:?} key but {config_host} is a {host_network:?} config service, \
+         signed requests will be rejected. Make a matching keypair with \
+         `env generate-keypair --network {}`",
+        match host_network {
+            helium_crypto::Network::MainNet => "mainnet",
+            helium_crypto::Network::TestNet => "testnet",
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{auth_header, network_mismatch, write_stream, OutFormat};
+    use crate::{hex_field, Skf};
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use std::io::Read;
+
+    fn skf(devaddr: u64) -> Skf {
+        Skf {
+            route_id: "route".to_string(),
+            devaddr: hex_field::devaddr(devaddr),
+            session_key: format!("key-{devaddr}"),
+            max_copies: Some(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn streamed_json_parses() {
+        let mut out = vec![];
+        let records = futures::stream::iter([Ok(skf(1)), Ok(skf(2))]);
+        assert_eq!(
+            2,
+            write_stream(records, OutFormat::Json, &mut out)
+                .await
+                .unwrap()
+        );
+        let parsed: Vec<Skf> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(vec![skf(1), skf(2)], parsed);
+
+        let mut out = vec![];
+        let records = futures::stream::iter(Vec::<crate::Result<Skf>>::new());
+        write_stream(records, OutFormat::Json, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(b"[]\n".to_vec(), out);
+    }
+
+    #[tokio::test]
+    async fn streamed_jsonl_gzips() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        let records = futures::stream::iter([Ok(skf(1)), Ok(skf(2))]);
+        write_stream(records, OutFormat::Jsonl, &mut encoder)
+            .await
+            .unwrap();
+
+        let mut data = String::new();
+        GzDecoder::new(&encoder.finish().unwrap()[..])
+            .read_to_string(&mut data)
+            .unwrap();
+        let lines: Vec<Skf> = data
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(vec![skf(1), skf(2)], lines);
+    }
+
+    #[test]
+    fn auth_header_from_file() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let file = dir.child("auth-header");
+        std::fs::write(&file, "Bearer secret\n").unwrap();
+        assert_eq!(
+            Some("Bearer secret".to_string()),
+            auth_header(Some("from flag".to_string()), Some(&file)).unwrap()
+        );
+        assert_eq!(
+            Some("from flag".to_string()),
+            auth_header(Some("from flag".to_string()), None).unwrap()
+        );
+        std::fs::write(&file, "\n").unwrap();
+        assert!(auth_header(None, Some(&file)).is_err());
+    }
+
+    #[test]
+    fn keypair_network_mismatch() {
+        use helium_crypto::Network;
+        let mainnet_host = "https://config.iot.mainnet.helium.io:6080";
+        assert!(network_mismatch(Network::MainNet, mainnet_host).is_none());
+        let warning = network_mismatch(Network::TestNet, mainnet_host).unwrap();
+        assert!(warning.contains("--network mainnet"));
+        assert!(
+            network_mismatch(Network::MainNet, "https://config.iot.testnet.helium.io").is_some()
+        );
+        assert!(network_mismatch(Network::TestNet, "http://localhost:6080").is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use super::ListNetIds;
+use crate::{
+    client, hex_field,
+    netid::devaddr_range,
+    solana::{SolanaKeypair, SolanaRpc},
+    DevaddrConstraint, HeliumNetId, Msg, PrettyJson, Result,
+};
+use anyhow::anyhow;
+use clap::ValueEnum;
+use serde_json::json;
+use std::collections::BTreeSet;
+
+/// The NetIDs `org create-helium --net-id` accepts, with the devaddrs each can assign.
+pub fn list_helium() -> Result<Msg> {
+    let mut net_ids = vec![];
+    for helium_net_id in HeliumNetId::value_variants() {
+        let value = helium_net_id
+            .to_possible_value()
+            .expect("helium net ids are not skipped");
+        let net_id = helium_net_id.net_id();
+        let range = devaddr_range(net_id)?;
+        net_ids.push(json!({
+            "net_id": hex_field::net_id(net_id as u64),
+            "arg": value.get_name(),
+            "aliases": value.get_name_and_aliases().skip(1).collect::<Vec<_>>(),
+            "devaddr_range": range.to_subnet(),
+        }));
+    }
+    Msg::ok(net_ids.pretty_json()?)
+}
+
+/// The NetIDs on chain, or with `--mine` those the wallet is the authority
+/// of, with the devaddrs allocated from each. NetID accounts are looked up
+/// for Helium's NetIDs and those of the config service's Orgs, so a NetID
+/// no Org uses yet is only found if it is one of Helium's.
+pub async fn list_net_ids(args: ListNetIds) -> Result<Msg> {
+    let authority = match args.mine {
+        true => Some(SolanaKeypair::read_or_default(args.solana_keypair)?.address()),
+        false => None,
+    };
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let mut candidates: BTreeSet<u64> = HeliumNetId::value_variants()
+        .iter()
+        .map(|net_id| net_id.net_id() as u64)
+        .collect();
+    let orgs = client.list_with_constraints(args.concurrency.get()).await?;
+    candidates.extend(orgs.iter().map(|org| org.net_id.0));
+    let candidates: Vec<u64> = candidates.into_iter().collect();
+
+    let rpc = SolanaRpc::new(&args.solana_url);
+    let mut net_ids = vec![];
+    for chain in rpc.iot_net_ids(&candidates, authority.as_deref()).await? {
+        let net_id = u32::try_from(chain.net_id)
+            .map_err(|_| anyhow!("{} has NetID {} over 24 bits", chain.address, chain.net_id))?;
+        let range = devaddr_range(net_id)?;
+        let (allocated, total) = allocation(&range, chain.current_addr_offset);
+        net_ids.push(json!({
+            "net_id": hex_field::net_id(net_id),
+            "address": chain.address,
+            "authority": chain.authority,
+            "devaddr_range": range.to_subnet(),
+            "current_addr_offset": chain.current_addr_offset,
+            "allocated": allocated,
+            "total": total,
+            "utilization": format!("{:.2}%", allocated as f64 * 100.0 / total as f64),
+        }));
+    }
+    Msg::ok(net_ids.pretty_json()?)
+}
+
+/// Devaddrs allocated from `range` and its size, when the next allocation
+/// starts `offset` devaddrs into it.
+fn allocation(range: &DevaddrConstraint, offset: u64) -> (u64, u64) {
+    let total = range.end_addr.0 - range.start_addr.0 + 1;
+    (offset.min(total), total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::allocation;
+    use crate::netid::devaddr_range;
+
+    #[test]
+    fn allocated_devaddrs() {
+        // type 6 NetIDs have 2^10 devaddrs
+        let range = devaddr_range(0xC0_0053).unwrap();
+        assert_eq!((0, 1024), allocation(&range, 0));
+        assert_eq!((256, 1024), allocation(&range, 256));
+        assert_eq!((1024, 1024), allocation(&range, 4096));
+    }
+}
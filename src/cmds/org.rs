@@ -1,23 +1,270 @@
 use super::{
-    CreateHelium, CreateRoaming, DevaddrSlabAdd, DevaddrUpdateConstraint, EnableOrg, GetOrg,
-    ListOrgs, OrgUpdateKey, PathBufKeypair, ENV_NET_ID, ENV_OUI,
+    already_applied, CreateHelium, CreateRoaming, DevaddrSlabAdd, DevaddrUpdateConstraint,
+    EnableOrg, FindOrg, GetOrg, ListOrgs, NextFreeDevaddrs, OnboardOrg, OrgColumn, OrgListFormat,
+    OrgTransfer, OrgUpdateKey, OrgUpdateOwner, PathBufKeypair, VerifyOrg, Whoami, ENV_NET_ID,
+    ENV_OUI,
+};
+use crate::{
+    client,
+    cmds::{format_csv, format_table, route::templated_route},
+    hex_field, max_copies_warning,
+    route::Route,
+    solana::{OrgEscrow, SolanaRpc},
+    subnet::{self, DevaddrConstraint, DevaddrSubnet},
+    warning, DevaddrRange, Msg, Org, OrgConstraintList, OrgResponse, Oui, PrettyJson, Result,
+};
+use anyhow::{anyhow, Context};
+use helium_crypto::PublicKey;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    time::{Duration, Instant},
 };
-use crate::{client, subnet::DevaddrConstraint, Msg, PrettyJson, Result};
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 pub async fn list_orgs(args: ListOrgs) -> Result<Msg> {
     let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-    let org = client.list().await?;
 
+    if args.output != OrgListFormat::Json {
+        let mut orgs = client.list().await?.orgs;
+        orgs.sort_by_key(|org| org.oui);
+        let on_chain = args
+            .columns
+            .iter()
+            .any(|column| matches!(column, OrgColumn::EscrowKey | OrgColumn::Approved));
+        let escrows = if on_chain {
+            SolanaRpc::new(&args.solana_url)
+                .iot_organization_escrows(&orgs.iter().map(|org| org.oui).collect::<Vec<_>>())
+                .await
+                .context("reading org accounts from solana")?
+        } else {
+            BTreeMap::new()
+        };
+        let rows = org_rows(&orgs, &args.columns, &escrows);
+        return match args.output {
+            OrgListFormat::Table => Msg::ok(format_table(&rows)),
+            _ => Msg::ok(format_csv(&rows)),
+        };
+    }
+
+    if args.with_constraints {
+        let org_list = OrgConstraintList {
+            orgs: client.list_with_constraints(args.concurrency.get()).await?,
+        };
+        return Msg::ok(org_list.pretty_json()?);
+    }
+
+    let org = client.list().await?;
     Msg::ok(org.pretty_json()?)
 }
 
+/// A header row and a row per Org. Orgs without an account on chain have
+/// empty `escrow_key` and `approved` cells.
+fn org_rows(
+    orgs: &[Org],
+    columns: &[OrgColumn],
+    escrows: &BTreeMap<u64, OrgEscrow>,
+) -> Vec<Vec<String>> {
+    let header = columns
+        .iter()
+        .map(|column| {
+            match column {
+                OrgColumn::Oui => "oui",
+                OrgColumn::Owner => "owner",
+                OrgColumn::Payer => "payer",
+                OrgColumn::DelegateKeys => "delegate_keys",
+                OrgColumn::Locked => "locked",
+                OrgColumn::EscrowKey => "escrow_key",
+                OrgColumn::Approved => "approved",
+            }
+            .to_string()
+        })
+        .collect();
+    let rows = orgs.iter().map(|org| {
+        let escrow = escrows.get(&org.oui);
+        columns
+            .iter()
+            .map(|column| match column {
+                OrgColumn::Oui => org.oui.to_string(),
+                OrgColumn::Owner => org.owner.to_string(),
+                OrgColumn::Payer => org.payer.to_string(),
+                OrgColumn::DelegateKeys => org
+                    .delegate_keys
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                OrgColumn::Locked => org.locked.to_string(),
+                OrgColumn::EscrowKey => {
+                    escrow.map_or_else(String::new, |escrow| escrow.escrow_key.clone())
+                }
+                OrgColumn::Approved => {
+                    escrow.map_or_else(String::new, |escrow| escrow.approved.to_string())
+                }
+            })
+            .collect()
+    });
+    std::iter::once(header).chain(rows).collect()
+}
+
 pub async fn get_org(args: GetOrg) -> Result<Msg> {
+    let oui = resolve_oui(
+        args.oui,
+        &args.keypair,
+        &args.config_host,
+        &args.config_pubkey,
+    )
+    .await?;
     let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-    let org = client.get(args.oui).await?;
+    let org = client.get(oui).await?;
 
     Msg::ok(org.pretty_json()?)
 }
 
+pub async fn whoami(args: Whoami) -> Result<Msg> {
+    let pubkey = args.keypair.to_keypair()?.public_key().to_owned();
+    let orgs = orgs_managed_by(&pubkey, &args.config_host, &args.config_pubkey).await?;
+
+    if orgs.is_empty() {
+        return Msg::err(format!("{pubkey} does not own or delegate for any Orgs"));
+    }
+    Msg::ok(orgs.pretty_json()?)
+}
+
+/// The config service has no lookup by key, so every Org is scanned.
+pub async fn find_org(args: FindOrg) -> Result<Msg> {
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let orgs: Vec<_> = client
+        .list()
+        .await?
+        .orgs
+        .into_iter()
+        .filter_map(|org| {
+            let roles = org.roles(&args.pubkey);
+            (!roles.is_empty())
+                .then(|| json!({"oui": org.oui, "roles": roles, "locked": org.locked}))
+        })
+        .collect();
+
+    if orgs.is_empty() {
+        return Msg::err(format!(
+            "{} is not the owner, payer, or a delegate of any Org",
+            args.pubkey
+        ));
+    }
+    Msg::ok(orgs.pretty_json()?)
+}
+
+pub async fn next_free_devaddrs(args: NextFreeDevaddrs) -> Result<Msg> {
+    let oui = resolve_oui(
+        args.oui,
+        &args.keypair,
+        &args.config_host,
+        &args.config_pubkey,
+    )
+    .await?;
+    let keypair = args.keypair.to_keypair()?;
+    let org = client::OrgClient::new(&args.config_host, &args.config_pubkey)
+        .await?
+        .get(oui)
+        .await?;
+    let routes = client::RouteClient::new(&args.config_host, &args.config_pubkey)
+        .await?
+        .list(oui, &keypair)
+        .await?
+        .routes;
+
+    let mut devaddr_client =
+        client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+    let mut used = vec![];
+    for route in &routes {
+        let ranges = devaddr_client.get_devaddrs(&route.id, &keypair).await?;
+        used.extend(ranges.into_iter().map(DevaddrConstraint::from));
+    }
+
+    match subnet::next_free_block(&org.devaddr_constraints, &used, args.count) {
+        Some(block) => Msg::ok(
+            json!({
+                "start_addr": block.start_addr,
+                "end_addr": block.end_addr,
+                "subnets": block.to_subnet().subnets,
+            })
+            .pretty_json()?,
+        ),
+        None => Msg::err(format!(
+            "no free block of {} devaddrs in the constraints of oui {oui}",
+            args.count
+        )),
+    }
+}
+
+/// Orgs are created on Solana and mirrored to the config service, so a
+/// mismatch means the mirror is behind or was changed directly.
+pub async fn verify_org(args: VerifyOrg) -> Result<Msg> {
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let org = client.get(args.oui).await?;
+    let chain = SolanaRpc::new(&args.solana_url)
+        .iot_organization(&org)
+        .await?;
+
+    let divergences = chain.divergences(&org);
+    let report = json!({
+        "oui": args.oui,
+        "chain": chain,
+        "config_service": org,
+        "divergences": divergences,
+    })
+    .pretty_json()?;
+    if divergences.is_empty() {
+        return Msg::ok(report);
+    }
+    Msg::err(report)
+}
+
+/// Use the provided `oui`, or find the single Org managed by the keypair.
+pub async fn resolve_oui(
+    oui: Option<Oui>,
+    keypair: &Path,
+    config_host: &str,
+    config_pubkey: &str,
+) -> Result<Oui> {
+    if let Some(oui) = oui {
+        return Ok(oui);
+    }
+
+    let pubkey = keypair.to_path_buf().to_keypair()?.public_key().to_owned();
+    let orgs = orgs_managed_by(&pubkey, config_host, config_pubkey).await?;
+    match orgs.as_slice() {
+        [org] => Ok(org.oui),
+        [] => Err(anyhow!(
+            "no Org found for {pubkey}, provide `--oui` or set {ENV_OUI}"
+        )),
+        _ => Err(anyhow!(
+            "{pubkey} manages multiple Orgs ({}), provide `--oui` or set {ENV_OUI}",
+            orgs.iter()
+                .map(|org| org.oui.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+async fn orgs_managed_by(
+    pubkey: &PublicKey,
+    config_host: &str,
+    config_pubkey: &str,
+) -> Result<Vec<Org>> {
+    let mut client = client::OrgClient::new(config_host, config_pubkey).await?;
+    let org_list = client.list().await?;
+    Ok(org_list
+        .orgs
+        .into_iter()
+        .filter(|org| org.is_managed_by(pubkey))
+        .collect())
+}
+
 pub async fn create_helium_org(args: CreateHelium) -> Result<Msg> {
     let delegates = if let Some(ref delegate_keys) = &args.delegate {
         delegate_keys.to_vec()
@@ -53,17 +300,45 @@ pub async fn create_roaming_org(args: CreateRoaming) -> Result<Msg> {
     } else {
         vec![]
     };
+    let constraints = args.constraints()?;
     if args.commit {
         let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
         let created_org = client
             .create_roamer(
                 &args.owner,
                 &args.payer,
                 delegates,
                 args.net_id.into(),
-                args.keypair.to_keypair()?,
+                &keypair,
             )
             .await?;
+        // the create request has no constraints, they go in an update once
+        // the Org exists
+        let created_org = if constraints.is_empty() {
+            created_org
+        } else {
+            let oui = created_org.org.oui;
+            match client
+                .add_devaddr_constraints(oui, constraints.clone(), &keypair)
+                .await
+            {
+                Ok(updated) => updated,
+                Err(err) => {
+                    return Msg::err(
+                        [
+                            format!("OUI {oui} created without its devaddr constraints: {err}"),
+                            created_org.pretty_json()?,
+                            "== Add them with ==".to_string(),
+                        ]
+                        .into_iter()
+                        .chain(constraint_add_commands(oui, &constraints))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    )
+                }
+            }
+        };
         return Msg::ok(
             [
                 "== Roaming Organization Created ==".to_string(),
@@ -75,111 +350,364 @@ pub async fn create_roaming_org(args: CreateRoaming) -> Result<Msg> {
             .join("\n"),
         );
     }
-    Msg::dry_run(format!(
+    let mut report = vec![format!(
         "create Roaming organization for NetId {}",
         args.net_id
+    )];
+    for constraint in constraints {
+        report.push(format!(
+            "with devaddr constraint {}",
+            constraint.to_subnet().subnets.join(", ")
+        ));
+    }
+    Msg::dry_run(report.join("\n"))
+}
+
+fn constraint_add_commands(oui: u64, constraints: &[DevaddrConstraint]) -> Vec<String> {
+    constraints
+        .iter()
+        .map(|constraint| {
+            format!(
+                "helium-config-cli org update devaddr-constraint-add --oui {oui} \
+                 --start-addr {} --end-addr {} --commit",
+                constraint.start_addr, constraint.end_addr
+            )
+        })
+        .collect()
+}
+
+/// Creates the Org, waits for its devaddr constraints to be visible, then
+/// creates the templated Route and adds every constraint to it. If a later
+/// step fails the IDs created so far are reported so it can be finished by
+/// hand.
+pub async fn onboard_org(args: OnboardOrg) -> Result<Msg> {
+    let payer = args.payer.clone().unwrap_or_else(|| args.owner.clone());
+    let net_id = hex_field::net_id(args.net_id.net_id());
+    if let Some(too_many) = max_copies_warning(args.max_copies) {
+        warning::warn(too_many)?;
+    }
+    // built up front so a bad server is rejected before the Org exists
+    let route = match (args.route_template, &args.host, args.port) {
+        (Some(template), Some(host), Some(port)) => Some(templated_route(
+            template,
+            net_id,
+            0,
+            args.max_copies,
+            host.clone(),
+            port,
+            args.path.clone(),
+        )?),
+        _ => None,
+    };
+
+    if !args.commit {
+        let mut plan = vec![
+            format!(
+                "create Helium organization for NetId {net_id} owned by {} with {} devaddrs",
+                args.owner, args.devaddr_count
+            ),
+            format!(
+                "wait up to {}s for the config service to sync",
+                args.wait_timeout
+            ),
+        ];
+        if let Some(route) = &route {
+            plan.push(format!("create route\n{}", route.redacted().pretty_json()?));
+            plan.push("add the Org's devaddr constraints to the route".to_string());
+        }
+        return Msg::dry_run(plan.join("\n"));
+    }
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let created = client
+        .create_helium(
+            &args.owner,
+            &payer,
+            args.delegate.clone().unwrap_or_default(),
+            args.devaddr_count,
+            args.net_id,
+            &keypair,
+        )
+        .await?;
+    let oui = created.org.oui;
+    let mut summary = json!({ "oui": oui, "net_id": net_id });
+
+    let org = match wait_for_org(&mut client, oui, args.wait_timeout, |res| {
+        !res.devaddr_constraints.is_empty()
+    })
+    .await
+    {
+        Ok(org) => org,
+        Err(err) => return onboard_failed(summary, err),
+    };
+    summary["devaddr_constraints"] = json!(org.devaddr_constraints);
+
+    let Some(route) = route else {
+        return Msg::ok(summary.pretty_json()?);
+    };
+    let route = Route { oui, ..route };
+    if let Err(err) = onboard_route(&args, route, &org, &keypair, &mut summary).await {
+        return onboard_failed(summary, err);
+    }
+    Msg::ok(summary.pretty_json()?)
+}
+
+async fn onboard_route(
+    args: &OnboardOrg,
+    route: Route,
+    org: &OrgResponse,
+    keypair: &helium_crypto::Keypair,
+    summary: &mut serde_json::Value,
+) -> Result {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let route = client.create_route(route, keypair).await?;
+    summary["route_id"] = json!(route.id);
+
+    let ranges = org
+        .devaddr_constraints
+        .iter()
+        .map(|constraint| {
+            DevaddrRange::new(route.id.clone(), constraint.start_addr, constraint.end_addr)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+    client.add_devaddrs(ranges.clone(), keypair).await?;
+    summary["devaddr_ranges"] = json!(ranges);
+    Ok(())
+}
+
+fn onboard_failed(summary: serde_json::Value, err: anyhow::Error) -> Result<Msg> {
+    Msg::err(format!(
+        "onboarding stopped: {err}\ncreated so far:\n{}",
+        summary.pretty_json()?
     ))
 }
 
 pub async fn enable_org(args: EnableOrg) -> Result<Msg> {
     if args.commit {
         let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        client.enable(args.oui, args.keypair.to_keypair()?).await?;
+        client.enable(args.oui, &args.keypair.to_keypair()?).await?;
         return Msg::ok(format!("OUI {} enabled", args.oui));
     }
     Msg::dry_run(format!("enable OUI {}", args.oui))
 }
 
-pub async fn update_owner(args: OrgUpdateKey) -> Result<Msg> {
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        let updated_org = client
-            .update_owner(args.oui, &args.pubkey, args.keypair.to_keypair()?)
-            .await?;
-        return Msg::ok(
-            [
-                "== Organization Updated ==".to_string(),
-                updated_org.pretty_json()?,
-            ]
-            .join("\n"),
+pub async fn update_owner(args: OrgUpdateOwner) -> Result<Msg> {
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let mut proposed = current.clone();
+    proposed.org.owner = args.new_owner.clone();
+    if let Some(payer) = &args.payer {
+        proposed.org.payer = payer.clone();
+    }
+    if !args.commit {
+        return Msg::dry_run(org_diff(&current, &proposed)?);
+    }
+
+    let updated_org = client
+        .update_owner(
+            args.oui,
+            &args.new_owner,
+            args.payer.as_ref(),
+            &args.keypair.to_keypair()?,
+        )
+        .await?;
+    let updated_org = if args.wait_for_config_service {
+        wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+            res.org.owner == args.new_owner
+                && args
+                    .payer
+                    .as_ref()
+                    .map_or(true, |payer| &res.org.payer == payer)
+        })
+        .await?
+    } else {
+        updated_org
+    };
+    Msg::ok(org_diff(&current, &updated_org)?)
+}
+
+/// Delegate keys are removed before the owner changes, afterwards only the
+/// new owner could remove them.
+pub async fn transfer_org(args: OrgTransfer) -> Result<Msg> {
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let keypair = args.keypair.to_keypair()?;
+    if keypair.public_key() != &current.org.owner {
+        return Msg::err(format!(
+            "{} is not the owner of OUI {}, only its owner can transfer it",
+            keypair.public_key(),
+            args.oui
+        ));
+    }
+    let plan = transfer_plan(&current, &args)?;
+    if !args.commit {
+        return Msg::dry_run(plan.join("\n"));
+    }
+
+    let delegate_keys = if args.remove_delegates {
+        current.org.delegate_keys.as_slice()
+    } else {
+        &[]
+    };
+    client
+        .transfer(
+            args.oui,
+            &args.to,
+            args.payer.as_ref(),
+            delegate_keys,
+            &keypair,
+        )
+        .await
+        .context(format!("OUI {} not transferred", args.oui))?;
+    let transferred = wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+        res.org.owner == args.to
+            && args
+                .payer
+                .as_ref()
+                .map_or(true, |payer| &res.org.payer == payer)
+    })
+    .await
+    .context(format!(
+        "transferred OUI {} to {}, but could not verify it",
+        args.oui, args.to
+    ))?;
+    Msg::ok(org_diff(&current, &transferred)?)
+}
+
+/// The steps of a transfer, in the order they run. The delegate keys, owner
+/// and payer are changed by a single update.
+fn transfer_plan(current: &OrgResponse, args: &OrgTransfer) -> Result<Vec<String>> {
+    let oui = current.org.oui;
+    if current.org.owner == args.to {
+        return Err(anyhow!("{} already owns OUI {oui}", args.to));
+    }
+    let mut steps = vec![format!("1. update OUI {oui} in one request:")];
+    if args.remove_delegates {
+        steps.extend(
+            current
+                .org
+                .delegate_keys
+                .iter()
+                .map(|delegate| format!("   remove delegate key {delegate}")),
         );
     }
-    Msg::dry_run(format!(
-        "update organization: owner pubkey {}",
-        &args.pubkey
-    ))
+    steps.push(format!(
+        "   owner from {} to {}",
+        current.org.owner, args.to
+    ));
+    if let Some(payer) = &args.payer {
+        steps.push(format!("   payer from {} to {payer}", current.org.payer));
+    }
+    steps.push(format!(
+        "2. get OUI {oui} until {} is its owner, up to {}s",
+        args.to, args.wait_timeout
+    ));
+    Ok(steps)
 }
 
 pub async fn update_payer(args: OrgUpdateKey) -> Result<Msg> {
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        let updated_org = client
-            .update_payer(args.oui, &args.pubkey, args.keypair.to_keypair()?)
-            .await?;
-        return Msg::ok(
-            [
-                "== Organization Updated ==".to_string(),
-                updated_org.pretty_json()?,
-            ]
-            .join("\n"),
-        );
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let mut proposed = current.clone();
+    proposed.org.payer = args.pubkey.clone();
+    if !args.commit {
+        return Msg::dry_run(org_diff(&current, &proposed)?);
     }
-    Msg::dry_run(format!(
-        "update organization: payer pubkey {}",
-        &args.pubkey
-    ))
+
+    let updated_org = client
+        .update_payer(args.oui, &args.pubkey, &args.keypair.to_keypair()?)
+        .await?;
+    let updated_org = if args.wait_for_config_service {
+        wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+            res.org.payer == args.pubkey
+        })
+        .await?
+    } else {
+        updated_org
+    };
+    Msg::ok(org_diff(&current, &updated_org)?)
 }
 
 pub async fn add_delegate_key(args: OrgUpdateKey) -> Result<Msg> {
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        let updated_org = client
-            .add_delegate_key(args.oui, &args.pubkey, args.keypair.to_keypair()?)
-            .await?;
-        return Msg::ok(
-            [
-                "== Organization Updated ==".to_string(),
-                updated_org.pretty_json()?,
-            ]
-            .join("\n"),
-        );
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let mut proposed = current.clone();
+    if !proposed.org.delegate_keys.contains(&args.pubkey) {
+        proposed.org.delegate_keys.push(args.pubkey.clone());
+    } else if args.idempotent {
+        return already_applied(format!(
+            "delegate key {} already in OUI {}",
+            args.pubkey, args.oui
+        ));
     }
-    Msg::dry_run(format!(
-        "update organization: add delegate key {}",
-        &args.pubkey
-    ))
+    if !args.commit {
+        return Msg::dry_run(org_diff(&current, &proposed)?);
+    }
+
+    let updated_org = client
+        .add_delegate_key(args.oui, &args.pubkey, &args.keypair.to_keypair()?)
+        .await?;
+    let updated_org = if args.wait_for_config_service {
+        wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+            res.org.delegate_keys.contains(&args.pubkey)
+        })
+        .await?
+    } else {
+        updated_org
+    };
+    Msg::ok(org_diff(&current, &updated_org)?)
 }
 
 pub async fn remove_delegate_key(args: OrgUpdateKey) -> Result<Msg> {
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        let updated_org = client
-            .remove_delegate_key(args.oui, &args.pubkey, args.keypair.to_keypair()?)
-            .await?;
-        return Msg::ok(
-            [
-                "== Organization Updated ==".to_string(),
-                updated_org.pretty_json()?,
-            ]
-            .join("\n"),
-        );
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let mut proposed = current.clone();
+    proposed.org.delegate_keys.retain(|key| key != &args.pubkey);
+    if args.idempotent && !current.org.delegate_keys.contains(&args.pubkey) {
+        return already_applied(format!(
+            "delegate key {} not in OUI {}",
+            args.pubkey, args.oui
+        ));
     }
-    Msg::dry_run(format!(
-        "update organization: remove delegate key {}",
-        &args.pubkey
-    ))
+    if !args.commit {
+        return Msg::dry_run(org_diff(&current, &proposed)?);
+    }
+
+    let updated_org = client
+        .remove_delegate_key(args.oui, &args.pubkey, &args.keypair.to_keypair()?)
+        .await?;
+    let updated_org = if args.wait_for_config_service {
+        wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+            !res.org.delegate_keys.contains(&args.pubkey)
+        })
+        .await?
+    } else {
+        updated_org
+    };
+    Msg::ok(org_diff(&current, &updated_org)?)
 }
 
 pub async fn add_devaddr_slab(args: DevaddrSlabAdd) -> Result<Msg> {
     if args.commit {
         let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+        let existing = client.get(args.oui).await?.devaddr_constraints;
         let updated_org = client
-            .add_devaddr_slab(args.oui, args.devaddr_count, args.keypair.to_keypair()?)
+            .add_devaddr_slab(args.oui, args.devaddr_count, &args.keypair.to_keypair()?)
             .await?;
+        let allotted: Vec<DevaddrSubnet> = updated_org
+            .devaddr_constraints
+            .iter()
+            .filter(|constraint| !existing.contains(constraint))
+            .map(|constraint| constraint.clone().to_subnet())
+            .collect();
         return Msg::ok(
             [
                 "== Organization Updated ==".to_string(),
                 updated_org.pretty_json()?,
+                "== Allotted Devaddr Constraints ==".to_string(),
+                allotted.pretty_json()?,
             ]
             .join("\n"),
         );
@@ -192,42 +720,233 @@ pub async fn add_devaddr_slab(args: DevaddrSlabAdd) -> Result<Msg> {
 
 pub async fn add_devaddr_constraint(args: DevaddrUpdateConstraint) -> Result<Msg> {
     let constraint = DevaddrConstraint::new(args.start_addr, args.end_addr)?;
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        let updated_org = client
-            .add_devaddr_constraint(args.oui, constraint, args.keypair.to_keypair()?)
-            .await?;
-        return Msg::ok(
-            [
-                "== Organization Updated ==".to_string(),
-                updated_org.pretty_json()?,
-            ]
-            .join("\n"),
-        );
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let mut proposed = current.clone();
+    if !proposed.devaddr_constraints.contains(&constraint) {
+        proposed.devaddr_constraints.push(constraint.clone());
+    } else if args.idempotent {
+        return already_applied(format!("{constraint:?} already in OUI {}", args.oui));
     }
-    Msg::dry_run(format!(
-        "update organization: add devaddr constraint {} - {}",
-        constraint.start_addr, constraint.end_addr
-    ))
+    if !args.commit {
+        return Msg::dry_run(org_diff(&current, &proposed)?);
+    }
+
+    let updated_org = client
+        .add_devaddr_constraint(args.oui, constraint.clone(), &args.keypair.to_keypair()?)
+        .await?;
+    let updated_org = if args.wait_for_config_service {
+        wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+            res.devaddr_constraints.contains(&constraint)
+        })
+        .await?
+    } else {
+        updated_org
+    };
+    Msg::ok(
+        [
+            org_diff(&current, &updated_org)?,
+            "== Added Devaddr Constraint ==".to_string(),
+            constraint.to_subnet().pretty_json()?,
+        ]
+        .join("\n"),
+    )
 }
 
 pub async fn remove_devaddr_constraint(args: DevaddrUpdateConstraint) -> Result<Msg> {
     let constraint = DevaddrConstraint::new(args.start_addr, args.end_addr)?;
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
-        let updated_org = client
-            .remove_devaddr_constraint(args.oui, constraint, args.keypair.to_keypair()?)
-            .await?;
-        return Msg::ok(
-            [
-                "== Organization Updated ==".to_string(),
-                updated_org.pretty_json()?,
-            ]
-            .join("\n"),
-        );
+    let mut client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let current = client.get(args.oui).await?;
+    let mut proposed = current.clone();
+    proposed
+        .devaddr_constraints
+        .retain(|existing| existing != &constraint);
+    if args.idempotent && !current.devaddr_constraints.contains(&constraint) {
+        return already_applied(format!("{constraint:?} not in OUI {}", args.oui));
     }
-    Msg::dry_run(format!(
-        "update organization: remove devaddr constraint {} - {}",
-        constraint.start_addr, constraint.end_addr
+    if !args.commit {
+        return Msg::dry_run(org_diff(&current, &proposed)?);
+    }
+
+    let updated_org = client
+        .remove_devaddr_constraint(args.oui, constraint.clone(), &args.keypair.to_keypair()?)
+        .await?;
+    let updated_org = if args.wait_for_config_service {
+        wait_for_org(&mut client, args.oui, args.wait_timeout, |res| {
+            !res.devaddr_constraints.contains(&constraint)
+        })
+        .await?
+    } else {
+        updated_org
+    };
+    Msg::ok(org_diff(&current, &updated_org)?)
+}
+
+fn org_diff(old_org: &OrgResponse, new_org: &OrgResponse) -> Result<String> {
+    Ok(format!(
+        "Updated OUI {}\n== Old\n{}\n== New\n{}",
+        new_org.org.oui,
+        old_org.pretty_json()?,
+        new_org.pretty_json()?
     ))
 }
+
+/// Poll the config service until `visible` holds for the Org or `timeout_secs`
+/// elapses. Progress is written to stderr as JSON lines so stdout stays
+/// parseable by scripts waiting on the final Org.
+async fn wait_for_org(
+    client: &mut client::OrgClient,
+    oui: Oui,
+    timeout_secs: u64,
+    visible: impl Fn(&OrgResponse) -> bool,
+) -> Result<OrgResponse> {
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(timeout_secs);
+    let mut attempt: u32 = 1;
+    loop {
+        let org = client.get(oui).await?;
+        let elapsed_secs = started.elapsed().as_secs();
+        if visible(&org) {
+            wait_progress("visible", oui, attempt, elapsed_secs);
+            return Ok(org);
+        }
+        if Instant::now() >= deadline {
+            return Err(anyhow!(
+                "change to OUI {oui} not visible in config service after {timeout_secs}s"
+            ));
+        }
+        wait_progress("waiting", oui, attempt, elapsed_secs);
+        tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+        attempt += 1;
+    }
+}
+
+fn wait_progress(event: &str, oui: Oui, attempt: u32, elapsed_secs: u64) {
+    let progress = json!({
+        "event": event,
+        "oui": oui,
+        "attempt": attempt,
+        "elapsed_secs": elapsed_secs,
+    });
+    eprintln!("{progress}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constraint_add_commands, org_rows, transfer_plan, OrgColumn};
+    use crate::{
+        cmds::{format_csv, Cli, OrgTransfer},
+        hex_field,
+        solana::OrgEscrow,
+        subnet::DevaddrConstraint,
+        testing::fixtures,
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn onboard_server_needs_a_template() {
+        use clap::Parser;
+        let owner = fixtures::keypair().public_key().to_string();
+        let onboard = |extra: &[&str]| {
+            let args = [
+                "cli",
+                "org",
+                "onboard",
+                "--owner",
+                &owner,
+                "--devaddr-count",
+                "8",
+                "--net-id",
+                "0x00003c",
+            ];
+            Cli::try_parse_from(args.iter().chain(extra))
+        };
+        assert!(onboard(&[]).is_ok());
+        assert!(onboard(&["--host", "lns.example.com"]).is_err());
+        assert!(onboard(&["--port", "8080"]).is_err());
+        assert!(onboard(&[
+            "--route-template",
+            "packet-router",
+            "--host",
+            "lns.example.com",
+            "--port",
+            "8080"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn constraint_recovery_commands() {
+        let constraint = DevaddrConstraint::new(
+            hex_field::devaddr(0x4800_0800),
+            hex_field::devaddr(0x4800_0807),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                "helium-config-cli org update devaddr-constraint-add --oui 7 \
+                 --start-addr 48000800 --end-addr 48000807 --commit"
+                    .to_string()
+            ],
+            constraint_add_commands(7, &[constraint])
+        );
+    }
+
+    #[test]
+    fn transfer_steps() {
+        let owner = fixtures::keypair().public_key().to_owned();
+        let (to, delegate) = (
+            fixtures::keypair().public_key().to_owned(),
+            fixtures::keypair().public_key().to_owned(),
+        );
+        let mut org = fixtures::org(7, &owner);
+        org.org.delegate_keys = vec![delegate.clone()];
+        let args = |to: &helium_crypto::PublicKey, remove_delegates| OrgTransfer {
+            oui: 7,
+            to: to.clone(),
+            payer: None,
+            remove_delegates,
+            wait_timeout: 60,
+            keypair: "keypair.bin".into(),
+            config_host: String::new(),
+            config_pubkey: String::new(),
+            commit: false,
+        };
+
+        assert_eq!(
+            vec![
+                "1. update OUI 7 in one request:".to_string(),
+                format!("   remove delegate key {delegate}"),
+                format!("   owner from {owner} to {to}"),
+                format!("2. get OUI 7 until {to} is its owner, up to 60s"),
+            ],
+            transfer_plan(&org, &args(&to, true)).unwrap()
+        );
+        assert_eq!(3, transfer_plan(&org, &args(&to, false)).unwrap().len());
+        assert!(transfer_plan(&org, &args(&owner, false)).is_err());
+    }
+
+    #[test]
+    fn org_csv_export() {
+        let owner = fixtures::keypair().public_key().to_owned();
+        let orgs = [fixtures::org(1, &owner).org, fixtures::org(2, &owner).org];
+        let escrows = BTreeMap::from([(
+            1,
+            OrgEscrow {
+                escrow_key: "OUI_1".to_string(),
+                approved: true,
+            },
+        )]);
+        let columns = [
+            OrgColumn::Oui,
+            OrgColumn::Owner,
+            OrgColumn::EscrowKey,
+            OrgColumn::Approved,
+            OrgColumn::Locked,
+        ];
+        assert_eq!(
+            format!("oui,owner,escrow_key,approved,locked\n1,{owner},OUI_1,true,false\n2,{owner},,,false"),
+            format_csv(&org_rows(&orgs, &columns, &escrows))
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use super::{
+    org::resolve_oui,
+    route::{count_stream, RouteStats},
+    InventoryReport, PathBufKeypair,
+};
+use crate::{client, route::Route, DevaddrRange, Msg, OrgResponse, Oui, PrettyJson, Result};
+use anyhow::Context;
+use futures::{StreamExt, TryStreamExt};
+use helium_crypto::Keypair;
+use serde::Serialize;
+use std::time::SystemTime;
+
+#[derive(Debug, Serialize)]
+struct Inventory {
+    generated_at: String,
+    orgs: Vec<OrgInventory>,
+    anomalies: Vec<Anomaly>,
+}
+
+#[derive(Debug, Serialize)]
+struct OrgInventory {
+    #[serde(flatten)]
+    org: OrgResponse,
+    routes: Vec<RouteInventory>,
+}
+
+#[derive(Debug, Serialize)]
+struct RouteInventory {
+    id: String,
+    net_id: String,
+    server: String,
+    protocol: Option<&'static str>,
+    max_copies: u32,
+    active: bool,
+    locked: bool,
+    members: RouteStats,
+}
+
+/// What the inventory needs of a Route's members, counted as they stream in
+/// so large Routes are never held in memory.
+#[derive(Debug)]
+struct RouteCensus {
+    stats: RouteStats,
+    /// Devaddr Ranges outside every devaddr constraint of the Org
+    overflowing: Vec<DevaddrRange>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum AnomalyKind {
+    /// Inactive, but still holding members that would route if reactivated
+    InactiveWithMembers,
+    /// No Devaddr Ranges, EUIs, or Session Key Filters
+    EmptyRoute,
+    /// A Devaddr Range outside every devaddr constraint of the Org
+    ConstraintOverflow,
+}
+
+#[derive(Debug, Serialize)]
+struct Anomaly {
+    oui: Oui,
+    route_id: String,
+    kind: AnomalyKind,
+    detail: String,
+}
+
+pub async fn inventory(args: InventoryReport) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let mut org_client = client::OrgClient::new(&args.config_host, &args.config_pubkey).await?;
+    let orgs = if args.all {
+        org_client
+            .list_with_constraints(args.concurrency.get())
+            .await?
+    } else {
+        let oui = resolve_oui(
+            args.oui,
+            &args.keypair,
+            &args.config_host,
+            &args.config_pubkey,
+        )
+        .await?;
+        vec![org_client.get(oui).await?]
+    };
+
+    let mut inventory = Inventory {
+        generated_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        orgs: vec![],
+        anomalies: vec![],
+    };
+    for org in orgs {
+        let routes = org_routes(&args, &org, &keypair).await?;
+        for (route, census) in &routes {
+            inventory
+                .anomalies
+                .extend(route_anomalies(&org, route, census));
+        }
+        inventory.orgs.push(OrgInventory {
+            org,
+            routes: routes
+                .into_iter()
+                .map(|(route, census)| RouteInventory::new(route, census.stats))
+                .collect(),
+        });
+    }
+
+    let report = inventory.pretty_json()?;
+    match args.out_file {
+        Some(path) => {
+            std::fs::write(&path, report).context("writing out file")?;
+            Msg::ok(format!(
+                "wrote inventory of {} orgs with {} anomalies to {}",
+                inventory.orgs.len(),
+                inventory.anomalies.len(),
+                path.display()
+            ))
+        }
+        None => Msg::ok(report),
+    }
+}
+
+/// Every Route of `org` with a census of its members, fetching `concurrency`
+/// Routes at a time.
+async fn org_routes(
+    args: &InventoryReport,
+    org: &OrgResponse,
+    keypair: &Keypair,
+) -> Result<Vec<(Route, RouteCensus)>> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let routes = client
+        .list(org.org.oui, keypair)
+        .await
+        .context(format!("listing routes for oui {}", org.org.oui))?
+        .routes;
+    futures::stream::iter(routes)
+        .map(|route| async move {
+            let census = route_census(args, org, &route.id, keypair).await?;
+            Ok((route, census))
+        })
+        .buffered(args.concurrency.get())
+        .try_collect()
+        .await
+}
+
+async fn route_census(
+    args: &InventoryReport,
+    org: &OrgResponse,
+    route_id: &str,
+    keypair: &Keypair,
+) -> Result<RouteCensus> {
+    let (config_host, config_pubkey) = (&args.config_host, &args.config_pubkey);
+    let devaddrs = async {
+        let mut client = client::DevaddrClient::new(config_host, config_pubkey).await?;
+        client
+            .stream_devaddrs(route_id, keypair)
+            .await?
+            .try_fold((0, vec![]), |(count, mut overflowing), range| {
+                if !within_constraints(org, &range) {
+                    overflowing.push(range);
+                }
+                futures::future::ready(Ok((count + 1, overflowing)))
+            })
+            .await
+    };
+    let eui_count = async {
+        let mut client = client::EuiClient::new(config_host, config_pubkey).await?;
+        count_stream(client.stream_euis(route_id, keypair).await?).await
+    };
+    let skf_count = async {
+        let mut client = client::SkfClient::new(config_host, config_pubkey).await?;
+        count_stream(client.stream_filters(route_id, keypair).await?).await
+    };
+    let ((devaddr_count, overflowing), eui_count, skf_count) =
+        futures::try_join!(devaddrs, eui_count, skf_count)?;
+    Ok(RouteCensus {
+        stats: RouteStats {
+            devaddr_count,
+            eui_count,
+            skf_count,
+        },
+        overflowing,
+    })
+}
+
+fn within_constraints(org: &OrgResponse, range: &DevaddrRange) -> bool {
+    org.devaddr_constraints.iter().any(|constraint| {
+        constraint.contains(range.start_addr) && constraint.contains(range.end_addr)
+    })
+}
+
+impl RouteInventory {
+    fn new(route: Route, members: RouteStats) -> Self {
+        Self {
+            net_id: route.net_id.to_string(),
+            server: format!("{}:{}", route.server.host, route.server.port),
+            protocol: route
+                .server
+                .protocol
+                .as_ref()
+                .map(|protocol| protocol.name()),
+            max_copies: route.max_copies,
+            active: route.active,
+            locked: route.locked,
+            members,
+            id: route.id,
+        }
+    }
+}
+
+fn route_anomalies(org: &OrgResponse, route: &Route, census: &RouteCensus) -> Vec<Anomaly> {
+    let anomaly = |kind, detail: String| Anomaly {
+        oui: org.org.oui,
+        route_id: route.id.clone(),
+        kind,
+        detail,
+    };
+    let stats = &census.stats;
+    let member_count = stats.devaddr_count + stats.eui_count + stats.skf_count;
+
+    let mut anomalies = vec![];
+    if member_count == 0 {
+        anomalies.push(anomaly(
+            AnomalyKind::EmptyRoute,
+            "route has no devaddr ranges, euis, or session key filters".to_string(),
+        ));
+    } else if !route.active {
+        anomalies.push(anomaly(
+            AnomalyKind::InactiveWithMembers,
+            format!("route is inactive with {member_count} members"),
+        ));
+    }
+    for range in &census.overflowing {
+        anomalies.push(anomaly(
+            AnomalyKind::ConstraintOverflow,
+            format!(
+                "devaddr range {}-{} is outside the org's devaddr constraints",
+                range.start_addr, range.end_addr
+            ),
+        ));
+    }
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{route_anomalies, within_constraints, AnomalyKind, RouteCensus};
+    use crate::{
+        cmds::route::RouteStats, hex_field, route::Route, testing::fixtures, DevaddrRange,
+    };
+
+    #[test]
+    fn inventory_anomalies() {
+        let owner = fixtures::keypair().public_key().to_owned();
+        let org = fixtures::org(1, &owner);
+        let mut route = fixtures::route(1);
+        let kinds = |route: &Route, census: &RouteCensus| -> Vec<AnomalyKind> {
+            route_anomalies(&org, route, census)
+                .into_iter()
+                .map(|anomaly| anomaly.kind)
+                .collect()
+        };
+        let census = |devaddr_count, eui_count, overflowing| RouteCensus {
+            stats: RouteStats {
+                devaddr_count,
+                eui_count,
+                skf_count: 0,
+            },
+            overflowing,
+        };
+
+        let empty = census(0, 0, vec![]);
+        assert_eq!(vec![AnomalyKind::EmptyRoute], kinds(&route, &empty));
+
+        let range = |start, end| {
+            DevaddrRange::new(
+                route.id.clone(),
+                hex_field::devaddr(start),
+                hex_field::devaddr(end),
+            )
+            .unwrap()
+        };
+        assert!(within_constraints(&org, &range(0x6000_0000, 0x6000_0007)));
+        assert!(!within_constraints(&org, &range(0x6000_0000, 0x6000_0008)));
+
+        let members = census(1, 1, vec![]);
+        assert!(kinds(&route, &members).is_empty());
+
+        let overflowing = census(1, 0, vec![range(0x6000_0000, 0x6000_0008)]);
+        assert_eq!(
+            vec![AnomalyKind::ConstraintOverflow],
+            kinds(&route, &overflowing)
+        );
+
+        route.active = false;
+        assert_eq!(
+            vec![AnomalyKind::InactiveWithMembers],
+            kinds(&route, &members)
+        );
+        assert_eq!(vec![AnomalyKind::EmptyRoute], kinds(&route, &empty));
+    }
+}
@@ -1,79 +1,456 @@
 use helium_crypto::Keypair;
 
 use super::{
-    ActivateRoute, AddGwmpRegion, DeactivateRoute, DeleteRoute, GetRoute, ListRoutes, NewRoute,
-    RemoveGwmpRegion, SetIgnoreEmptySkf, UpdateHttp, UpdateMaxCopies, UpdatePacketRouter,
-    UpdateServer,
+    ActivateRoute, AddGwmpRegion, ClearProtocol, DeactivateRoute, DeleteRoute, GetRoute,
+    ListBackups, ListRoutes, NewRoute, ProbePropagation, RemoveGwmpRegion, RestoreBackup,
+    RouteColumn, RouteHistory, RouteListFormat, RouteSort, RouteSummary, RouteTarget,
+    RouteTemplate, SetIgnoreEmptySkf, ShowBackup, ShowRouteAt, StreamRoutes, UpdateHttp,
+    UpdateMaxCopies, UpdatePacketRouter, UpdateServer,
 };
 use crate::{
-    client, cmds::PathBufKeypair, route::Route, server::Protocol, Msg, PrettyJson, Result,
+    api::{OrgApi, RouteApi},
+    backup::Backups,
+    check_wildcard_app_euis, client,
+    cmds::{
+        already_applied, auth_header, format_csv, format_table, org::resolve_oui, PathBufKeypair,
+        BULK_CHUNK_SIZE,
+    },
+    hex_field::HexNetID,
+    max_copies_warning,
+    progress::{ProgressEvent, ProgressSink},
+    retry::{self, Chunker, RateLimiter},
+    route::{suggest_route_ids, validate_route_id, Route, RouteChange, RouteChangeItem, RouteFile},
+    route_cache::{self, RouteCache},
+    schema::Versioned,
+    server::{FlowType, GwmpMap, Port, Protocol, Server},
+    shared_file,
+    timestamp::{self, Timestamp},
+    warning, DevaddrRange, Eui, Msg, Oui, PrettyJson, Result, Skf, SkfUpdate, UpdateAction,
 };
+use anyhow::{anyhow, Context};
+use futures::{stream::TryChunksError, Future, Stream, StreamExt, TryStreamExt};
+use helium_proto::services::iot_config::{route_stream_res_v1, ActionV1, RouteStreamResV1};
+use rand::{rngs::OsRng, RngCore};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tracing::Instrument;
+
+/// Attempts after the first to post a change to a `route stream` webhook.
+const WEBHOOK_RETRIES: u32 = 5;
+
+/// Reconnects to the route stream in a row, without a change received in
+/// between, before `route stream` gives up.
+const STREAM_RECONNECTS: u32 = 5;
+
+/// How far before now the route stream is subscribed from, so a local clock
+/// slightly ahead of the config service does not filter out new changes.
+const STREAM_SINCE_MARGIN: Duration = Duration::from_secs(5);
 
 pub async fn list_routes(args: ListRoutes) -> Result<Msg> {
+    let oui = resolve_oui(
+        args.oui,
+        &args.keypair,
+        &args.config_host,
+        &args.config_pubkey,
+    )
+    .await?;
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    match client.list(args.oui, &args.keypair.to_keypair()?).await {
-        Ok(route_list) => Msg::ok(route_list.pretty_json()?),
-        Err(err) => Msg::err(format!("could not list routes: {err}")),
+    let mut route_list = match client.list(oui, &args.keypair.to_keypair()?).await {
+        Ok(route_list) => route_list,
+        Err(err) => return Msg::err(format!("could not list routes: {err}")),
+    };
+    route_list.routes.iter_mut().for_each(Route::redact);
+    // a full list is fresh, lookups can use it
+    RouteCache::new(None, route_cache::DEFAULT_MAX_AGE, args.no_cache)
+        .write(oui, &route_list.routes)?;
+
+    if let Some(sort) = args.sort {
+        sort_routes(&mut route_list.routes, sort);
+    }
+
+    if !args.with_counts {
+        return match args.output {
+            RouteListFormat::Json => Msg::ok(route_list.pretty_json()?),
+            RouteListFormat::Table => Msg::ok(route_table(&route_list.routes, &args.columns, None)),
+            RouteListFormat::Csv => Msg::ok(route_csv(&route_list.routes, &args.columns, None)),
+        };
+    }
+
+    let counts = counts_for_routes(
+        &client,
+        &args.config_host,
+        &args.config_pubkey,
+        &route_list.routes,
+        &args.keypair.to_keypair()?,
+        args.concurrency.get(),
+    )
+    .await?;
+    match args.output {
+        RouteListFormat::Json => {
+            let routes: Vec<RouteWithMembers> = route_list
+                .routes
+                .into_iter()
+                .zip(counts)
+                .map(|(route, counts)| RouteWithMembers {
+                    route,
+                    members: Members::Counts(counts),
+                })
+                .collect();
+            Msg::ok(json!({ "routes": routes }).pretty_json()?)
+        }
+        RouteListFormat::Table => Msg::ok(route_table(
+            &route_list.routes,
+            &args.columns,
+            Some(&counts),
+        )),
+        RouteListFormat::Csv => {
+            Msg::ok(route_csv(&route_list.routes, &args.columns, Some(&counts)))
+        }
+    }
+}
+
+/// Member counts for each of `routes`, in order, fetching `concurrency`
+/// Routes at a time.
+async fn counts_for_routes(
+    client: &client::RouteClient,
+    config_host: &str,
+    config_pubkey: &str,
+    routes: &[Route],
+    keypair: &Keypair,
+    concurrency: usize,
+) -> Result<Vec<RouteStats>> {
+    let progress = client.progress();
+    let total = routes.len();
+    let mut done = 0;
+    futures::stream::iter(routes)
+        .map(|route| stats_for_route(config_host, config_pubkey, &route.id, keypair))
+        .buffered(concurrency)
+        .inspect_ok(|_| {
+            done += 1;
+            progress.event(ProgressEvent::Chunk {
+                operation: "route counts",
+                done,
+                total,
+            });
+        })
+        .try_collect()
+        .await
+}
+
+fn sort_routes(routes: &mut [Route], sort: RouteSort) {
+    match sort {
+        RouteSort::Oui => routes.sort_by(|a, b| a.oui.cmp(&b.oui).then_with(|| a.id.cmp(&b.id))),
+        RouteSort::Id => routes.sort_by(|a, b| a.id.cmp(&b.id)),
+        RouteSort::Server => routes
+            .sort_by(|a, b| (&a.server.host, a.server.port).cmp(&(&b.server.host, b.server.port))),
+        RouteSort::MaxCopies => routes.sort_by_key(|route| route.max_copies),
+    }
+}
+
+fn route_column_name(column: RouteColumn) -> &'static str {
+    match column {
+        RouteColumn::Id => "id",
+        RouteColumn::Oui => "oui",
+        RouteColumn::NetId => "net_id",
+        RouteColumn::Server => "server",
+        RouteColumn::Protocol => "protocol",
+        RouteColumn::MaxCopies => "max_copies",
+        RouteColumn::Active => "active",
+        RouteColumn::Locked => "locked",
+        RouteColumn::IgnoreEmptySkf => "ignore_empty_skf",
+    }
+}
+
+fn route_column_value(route: &Route, column: RouteColumn) -> String {
+    match column {
+        RouteColumn::Id => route.id.clone(),
+        RouteColumn::Oui => route.oui.to_string(),
+        RouteColumn::NetId => route.net_id.to_string(),
+        RouteColumn::Server => format!("{}:{}", route.server.host, route.server.port),
+        RouteColumn::Protocol => route
+            .server
+            .protocol
+            .as_ref()
+            .map_or("", |protocol| protocol.name())
+            .to_string(),
+        RouteColumn::MaxCopies => route.max_copies.to_string(),
+        RouteColumn::Active => route.active.to_string(),
+        RouteColumn::Locked => route.locked.to_string(),
+        RouteColumn::IgnoreEmptySkf => route.ignore_empty_skf.to_string(),
     }
 }
 
+/// Member counts, when given, are appended to the requested columns.
+fn route_rows(
+    routes: &[Route],
+    columns: &[RouteColumn],
+    counts: Option<&[RouteStats]>,
+) -> Vec<Vec<String>> {
+    let mut header: Vec<String> = columns
+        .iter()
+        .map(|column| route_column_name(*column).to_string())
+        .collect();
+    if counts.is_some() {
+        header.extend(["devaddr_count", "eui_count", "skf_count"].map(String::from));
+    }
+    let rows = routes.iter().enumerate().map(|(idx, route)| {
+        let mut row: Vec<String> = columns
+            .iter()
+            .map(|column| route_column_value(route, *column))
+            .collect();
+        if let Some(stats) = counts.map(|counts| &counts[idx]) {
+            row.extend(
+                [stats.devaddr_count, stats.eui_count, stats.skf_count].map(|n| n.to_string()),
+            );
+        }
+        row
+    });
+    std::iter::once(header).chain(rows).collect()
+}
+
+fn route_table(routes: &[Route], columns: &[RouteColumn], counts: Option<&[RouteStats]>) -> String {
+    format_table(&route_rows(routes, columns, counts))
+}
+
+fn route_csv(routes: &[Route], columns: &[RouteColumn], counts: Option<&[RouteStats]>) -> String {
+    format_csv(&route_rows(routes, columns, counts))
+}
+
 pub async fn get_route(args: GetRoute) -> Result<Msg> {
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    let stats_str = if args.stats {
-        let RouteStats {
-            devaddr_count,
-            eui_count,
-            skf_count,
-        } = counts_for_route(
-            &args.config_host,
-            &args.config_pubkey,
-            &args.route_id,
-            &args.keypair.to_keypair()?,
-        )
-        .await?;
-        format!(
-            "\nDevAddr Range: {}\nEUI Count: {}\nSKF Count: {}",
-            devaddr_count, eui_count, skf_count
-        )
+    let keypair = args.keypair.to_keypair()?;
+    let fetched = match validate_route_id(&args.route_id) {
+        Ok(route_id) => client.get(&route_id, &keypair).await,
+        Err(err) => Err(err),
+    };
+    let route = match fetched {
+        Ok(route) => route.redacted(),
+        Err(err) => {
+            let cache = RouteCache::new(None, route_cache::DEFAULT_MAX_AGE, args.no_cache);
+            let suggestions = match args.oui {
+                Some(oui) => match cache.routes(&mut client, oui, &keypair).await {
+                    Ok(routes) => suggest_route_ids(&args.route_id, &routes)
+                        .into_iter()
+                        .map(|id| format!("\n  {id}"))
+                        .collect::<String>(),
+                    Err(_) => "".to_string(),
+                },
+                None => "".to_string(),
+            };
+            if suggestions.is_empty() {
+                return Msg::err(format!("could not get route: {err}"));
+            }
+            return Msg::err(format!(
+                "could not get route: {err}\ndid you mean:{suggestions}"
+            ));
+        }
+    };
+
+    let members = if args.stats || args.with_members {
+        Some(members_for_route(&args.config_host, &args.config_pubkey, &route.id, &keypair).await?)
     } else {
-        "".to_string()
+        None
     };
-    match client
-        .get(&args.route_id, &args.keypair.to_keypair()?)
-        .await
-    {
-        Ok(route) => Msg::ok(format!("{}{}", route.pretty_json()?, stats_str)),
-        Err(err) => Msg::err(format!("could not get route: {err}")),
+
+    let stats_str = match &members {
+        Some(members) if args.stats => {
+            let RouteStats {
+                devaddr_count,
+                eui_count,
+                skf_count,
+            } = members.into();
+            format!(
+                "\nDevAddr Range: {}\nEUI Count: {}\nSKF Count: {}",
+                devaddr_count, eui_count, skf_count
+            )
+        }
+        _ => "".to_string(),
+    };
+
+    let route_json = match members {
+        Some(members) if args.with_members => {
+            let members = if args.counts_only {
+                Members::Counts((&members).into())
+            } else {
+                Members::Full(members)
+            };
+            Versioned::new(RouteWithMembers { route, members }).pretty_json()?
+        }
+        _ => route.pretty_json()?,
+    };
+
+    Msg::ok(format!("{route_json}{stats_str}"))
+}
+
+pub async fn route_summary(args: RouteSummary) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let route = match client.get(&args.route_id, &keypair).await {
+        Ok(route) => route,
+        Err(err) => return Msg::err(format!("could not get route {}: {err}", args.route_id)),
+    };
+    let stats =
+        stats_for_route(&args.config_host, &args.config_pubkey, &route.id, &keypair).await?;
+    Msg::ok(summarize_route(&route, &stats))
+}
+
+/// A few plain lines describing `route`, short enough to paste into chat.
+fn summarize_route(route: &Route, stats: &RouteStats) -> String {
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    let mut lines = vec![
+        format!(
+            "route {} (oui {}, net_id {})",
+            route.id, route.oui, route.net_id
+        ),
+        format!("endpoint: {}:{}", route.server.host, route.server.port),
+    ];
+    match &route.server.protocol {
+        None => lines.push("protocol: none".to_string()),
+        Some(Protocol::PacketRouter) => lines.push("protocol: packet router".to_string()),
+        Some(Protocol::Gwmp(gwmp)) => {
+            let ports = gwmp
+                .mapping
+                .iter()
+                .map(|(region, port)| format!("{}={port}", helium_proto::Region::from(region)))
+                .collect::<Vec<_>>();
+            lines.push(match ports.is_empty() {
+                true => "protocol: gwmp, no region ports".to_string(),
+                false => format!("protocol: gwmp, region ports {}", ports.join(" ")),
+            });
+        }
+        Some(Protocol::Http(http)) => {
+            let flow_type = match http.flow_type {
+                FlowType::Sync => "sync",
+                FlowType::Async => "async",
+            };
+            lines.push(format!(
+                "protocol: http roaming, {flow_type} flow, path {}, dedupe {}ms",
+                http.path, http.dedupe_timeout
+            ));
+        }
+    }
+    lines.push(format!(
+        "active: {}, locked: {}, max_copies: {}",
+        yes_no(route.active),
+        yes_no(route.locked),
+        route.max_copies
+    ));
+    lines.push(format!(
+        "members: {} devaddr ranges, {} euis, {} session key filters",
+        stats.devaddr_count, stats.eui_count, stats.skf_count
+    ));
+    lines.join("\n")
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RouteMembers {
+    pub(crate) devaddr_ranges: Vec<DevaddrRange>,
+    pub(crate) euis: Vec<Eui>,
+    pub(crate) skfs: Vec<Skf>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RouteStats {
+    pub(crate) devaddr_count: usize,
+    pub(crate) eui_count: usize,
+    pub(crate) skf_count: usize,
+}
+
+impl From<&RouteMembers> for RouteStats {
+    fn from(members: &RouteMembers) -> Self {
+        Self {
+            devaddr_count: members.devaddr_ranges.len(),
+            eui_count: members.euis.len(),
+            skf_count: members.skfs.len(),
+        }
     }
 }
 
-struct RouteStats {
-    devaddr_count: usize,
-    eui_count: usize,
-    skf_count: usize,
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Members {
+    Full(RouteMembers),
+    Counts(RouteStats),
+}
+
+#[derive(Debug, Serialize)]
+struct RouteWithMembers {
+    #[serde(flatten)]
+    route: Route,
+    members: Members,
 }
 
-async fn counts_for_route(
+/// Fetch Devaddr Ranges, EUIs, and Session Key Filters for a Route concurrently.
+pub(crate) async fn members_for_route(
+    config_host: &str,
+    config_pubkey: &str,
+    route_id: &str,
+    keypair: &Keypair,
+) -> Result<RouteMembers> {
+    let devaddr_ranges = async {
+        client::DevaddrClient::new(config_host, config_pubkey)
+            .await?
+            .get_devaddrs(route_id, keypair)
+            .await
+    };
+    let euis = async {
+        client::EuiClient::new(config_host, config_pubkey)
+            .await?
+            .get_euis(route_id, keypair)
+            .await
+    };
+    let skfs = async {
+        client::SkfClient::new(config_host, config_pubkey)
+            .await?
+            .list_filters(route_id, keypair)
+            .await
+    };
+    let (mut devaddr_ranges, mut euis, mut skfs) = futures::try_join!(devaddr_ranges, euis, skfs)?;
+    // sorted like route files, so exports of the same members are identical
+    devaddr_ranges.sort_by_key(|range| (range.start_addr.0, range.end_addr.0));
+    euis.sort_by_key(|eui| (eui.app_eui.0, eui.dev_eui.0));
+    sort_filters(&mut skfs);
+    Ok(RouteMembers {
+        devaddr_ranges,
+        euis,
+        skfs,
+    })
+}
+
+/// Sort Session Key Filters by devaddr then session key, the order they are
+/// exported in.
+pub(crate) fn sort_filters(skfs: &mut [Skf]) {
+    skfs.sort_by(|a, b| (a.devaddr.0, &a.session_key).cmp(&(b.devaddr.0, &b.session_key)));
+}
+
+/// Count the Devaddr Ranges, EUIs, and Session Key Filters of a Route as they
+/// stream in, without holding the members in memory.
+pub(crate) async fn stats_for_route(
     config_host: &str,
     config_pubkey: &str,
     route_id: &str,
     keypair: &Keypair,
 ) -> Result<RouteStats> {
-    let devaddr_count = client::DevaddrClient::new(config_host, config_pubkey)
-        .await?
-        .get_devaddrs(route_id, keypair)
-        .await?
-        .len();
-    let skf_count = client::SkfClient::new(config_host, config_pubkey)
-        .await?
-        .list_filters(route_id, keypair)
-        .await?
-        .len();
-    let eui_count = client::EuiClient::new(config_host, config_pubkey)
-        .await?
-        .get_euis(route_id, keypair)
-        .await?
-        .len();
+    let devaddr_count = async {
+        let mut client = client::DevaddrClient::new(config_host, config_pubkey).await?;
+        count_stream(client.stream_devaddrs(route_id, keypair).await?).await
+    };
+    let eui_count = async {
+        let mut client = client::EuiClient::new(config_host, config_pubkey).await?;
+        count_stream(client.stream_euis(route_id, keypair).await?).await
+    };
+    let skf_count = async {
+        let mut client = client::SkfClient::new(config_host, config_pubkey).await?;
+        count_stream(client.stream_filters(route_id, keypair).await?).await
+    };
+    let (devaddr_count, eui_count, skf_count) =
+        futures::try_join!(devaddr_count, eui_count, skf_count)?;
     Ok(RouteStats {
         devaddr_count,
         eui_count,
@@ -81,9 +458,98 @@ async fn counts_for_route(
     })
 }
 
+pub(crate) async fn count_stream<T>(
+    records: impl futures::Stream<Item = Result<T>>,
+) -> Result<usize> {
+    records
+        .try_fold(0, |count, _| futures::future::ready(Ok(count + 1)))
+        .await
+}
+
+/// Send the members `source` streams from one Route with `add`, a chunk at a
+/// time as they arrive, so a large Route is never held in memory. `total` is
+/// only for progress. Returns the members copied.
+pub(crate) async fn copy_members<T, R, F, Fut>(
+    source: impl Stream<Item = Result<T>>,
+    total: usize,
+    operation: &'static str,
+    progress: Arc<dyn ProgressSink>,
+    mut add: F,
+) -> Result<usize>
+where
+    T: Clone,
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let limiter = RateLimiter::default().with_progress(progress.clone());
+    let batches = source
+        .try_chunks(BULK_CHUNK_SIZE)
+        .map_err(|TryChunksError(_, err)| err);
+    futures::pin_mut!(batches);
+    let mut copied = 0;
+    while let Some(batch) = batches.try_next().await? {
+        let mut chunks = Chunker::new(&batch, BULK_CHUNK_SIZE).with_progress(progress.clone());
+        while let Some(chunk) = chunks.next_chunk() {
+            let started = Instant::now();
+            let result = limiter
+                .retry(|| add(chunk.to_vec()))
+                .instrument(chunks.span())
+                .await;
+            chunks.record(result, started.elapsed()).context(format!(
+                "{operation}: copied {} of {total}",
+                copied + chunks.done()
+            ))?;
+            progress.event(ProgressEvent::Chunk {
+                operation,
+                done: copied + chunks.done(),
+                total,
+            });
+        }
+        copied += batch.len();
+    }
+    Ok(copied)
+}
+
+/// A new Route for `oui` sending to `host:port` with the template's protocol.
+pub fn templated_route(
+    template: RouteTemplate,
+    net_id: HexNetID,
+    oui: Oui,
+    max_copies: u32,
+    host: String,
+    port: Port,
+    path: String,
+) -> Result<Route> {
+    let protocol = match template {
+        RouteTemplate::PacketRouter => Protocol::PacketRouter,
+        RouteTemplate::HttpRoaming => Protocol::make_http(250, path, None, None),
+    };
+    let mut route = Route::new(net_id, oui, max_copies);
+    route.set_server(Server {
+        host,
+        port,
+        protocol: Some(protocol),
+    });
+    route.server.validate()?;
+    Ok(route)
+}
+
 pub async fn new_route(args: NewRoute) -> Result<Msg> {
+    if let Some(path) = &args.from_file {
+        return new_route_from_file(path, &args).await;
+    }
+    let oui = resolve_oui(
+        args.oui,
+        &args.keypair,
+        &args.config_host,
+        &args.config_pubkey,
+    )
+    .await?;
+    if let Some(too_many) = max_copies_warning(args.max_copies) {
+        warning::warn(too_many)?;
+    }
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    let route = Route::new(args.net_id, args.oui, args.max_copies);
+    let route = Route::new(args.net_id, oui, args.max_copies);
 
     if !args.commit {
         return Msg::dry_run(route.pretty_json()?);
@@ -96,573 +562,3603 @@ pub async fn new_route(args: NewRoute) -> Result<Msg> {
         Ok(created_route) => Msg::ok(format!(
             "created route {}\n{}",
             created_route.id,
-            created_route.pretty_json()?
+            created_route.redacted().pretty_json()?
         )),
         Err(err) => Msg::err(format!("route not created: {err}")),
     }
 }
 
-pub async fn delete_route(args: DeleteRoute) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-
-    if !args.commit {
-        return Msg::dry_run(format!("delete {}", args.route_id));
-    }
-
-    match client
-        .delete(&args.route_id, &args.keypair.to_keypair()?)
-        .await
-    {
-        Ok(removed_route) => Msg::ok(format!("deleted route {}", removed_route.id)),
-        Err(err) => Msg::err(format!("route not deleted: {err}")),
+/// A template from a profile is used whenever `route new` runs for its OUI,
+/// so one written for another OUI is refused rather than created there.
+fn check_template_oui(path: &Path, route: &Route, oui: Option<Oui>) -> Result {
+    match oui {
+        Some(oui) if oui != route.oui => Err(anyhow!(
+            "route file {} is for OUI {}, not OUI {oui}",
+            path.display(),
+            route.oui
+        )),
+        _ => Ok(()),
     }
 }
 
-pub async fn update_max_copies(args: UpdateMaxCopies) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    let keypair = args.keypair.to_keypair()?;
-
-    let mut route = client.get(&args.route_id, &keypair).await?;
-    let old_route = route.clone();
-
-    route.max_copies = args.max_copies;
+/// Members are only added once the Route exists, because their requests
+/// need the ID the config service assigns.
+async fn new_route_from_file(path: &Path, args: &NewRoute) -> Result<Msg> {
+    let mut route_file = RouteFile::read(path, args.strict)?;
+    check_template_oui(path, &route_file.route, args.oui)?;
+    if let Some(too_many) = max_copies_warning(route_file.route.max_copies) {
+        warning::warn(too_many)?;
+    }
+    check_wildcard_app_euis(
+        route_file.euis.iter().map(|eui| (eui.app_eui, eui.dev_eui)),
+        args.wildcard_app_eui,
+    )?;
+    let temp_id = if route_file.route.id.is_empty() {
+        path.display().to_string()
+    } else {
+        route_file.route.id.clone()
+    };
+    let summary = format!(
+        "{} euis, {} devaddr ranges",
+        route_file.euis.len(),
+        route_file.devaddr_ranges.len()
+    );
 
     if !args.commit {
         return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
+            "created route from {temp_id} with {summary}\n{}",
+            route_file.route.redacted().pretty_json()?
         ));
     }
 
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("could not update max_copies: {err}")),
-    }
-}
-
-pub async fn update_server(args: UpdateServer) -> Result<Msg> {
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
     let keypair = args.keypair.to_keypair()?;
+    let created = match client
+        .create_route(route_file.route.clone(), &keypair)
+        .await
+    {
+        Ok(created) => created,
+        Err(err) => return Msg::err(format!("route not created: {err}")),
+    };
+    route_file.assign_id(&created.id);
 
-    let mut route = client.get(&args.route_id, &keypair).await?;
-    let old_route = route.clone();
-
-    route.server.host = args.host;
-    route.server.port = args.port;
-
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+    let mapping = BTreeMap::from([(temp_id, created.id.clone())]);
+    let limiter = RateLimiter::default().with_progress(client.progress());
+    for chunk in route_file.devaddr_ranges.chunks(BULK_CHUNK_SIZE) {
+        limiter
+            .retry(|| {
+                let mut client = client.clone();
+                let keypair = &keypair;
+                async move { client.add_devaddrs(chunk.to_vec(), keypair).await }
+            })
+            .await
+            .context(format!(
+                "route {} created without all its devaddr ranges",
+                created.id
+            ))?;
     }
-
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-
-        Err(err) => Msg::err(format!("could not update server host and port: {err}")),
+    for chunk in route_file.euis.chunks(BULK_CHUNK_SIZE) {
+        limiter
+            .retry(|| {
+                let mut client = client.clone();
+                let keypair = &keypair;
+                async move { client.add_euis(chunk.to_vec(), keypair).await }
+            })
+            .await
+            .context(format!("route {} created without all its euis", created.id))?;
     }
-}
-
-pub async fn update_http(args: UpdateHttp) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    let keypair = args.keypair.to_keypair()?;
 
-    let mut route = client.get(&args.route_id, &keypair).await?;
-    let old_route = route.clone();
+    Msg::ok(format!(
+        "created route with {summary}\n{}\n{}",
+        mapping.pretty_json()?,
+        created.redacted().pretty_json()?
+    ))
+}
 
-    let http = Protocol::make_http(
-        args.dedupe_timeout,
-        args.path,
-        args.auth_header,
-        args.receiver_nsid,
-    );
-    route.server.protocol = Some(http);
+/// JSON Schema for route files, which also describes the output of `route get`.
+pub fn route_schema() -> Result<Msg> {
+    Msg::ok(schemars::schema_for!(RouteFile).pretty_json()?)
+}
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+pub fn list_backups(args: ListBackups) -> Result<Msg> {
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+    match args.route_id {
+        Some(route_id) => {
+            let listed = backups
+                .list(&route_id)?
+                .into_iter()
+                .map(|name| {
+                    Ok(json!({
+                        "saved_at": Timestamp::from_unix_millis(backup_millis(&name)?)?,
+                        "name": name,
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Msg::ok(listed.pretty_json()?)
+        }
+        None => Msg::ok(backups.routes()?.pretty_json()?),
     }
+}
 
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not update http protocol: {err}")),
-    }
+pub fn show_backup(args: ShowBackup) -> Result<Msg> {
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+    Msg::ok(
+        backups
+            .read(&args.route_id, args.backup.as_deref())?
+            .redacted()
+            .pretty_json()?,
+    )
 }
 
-pub async fn add_gwmp_region(args: AddGwmpRegion) -> Result<Msg> {
+/// A field of a Route that differs between two versions.
+#[derive(Debug, PartialEq, Serialize)]
+struct FieldChange {
+    field: String,
+    old: serde_json::Value,
+    new: serde_json::Value,
+}
+
+/// Each backup is the Route as it was until the change committed when it
+/// was saved, so the changes of a version are the differences from the next
+/// one, or from the current Route for the most recent backup.
+pub async fn route_history(args: RouteHistory) -> Result<Msg> {
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+    let names = backups.list(&args.route_id)?;
+    if names.is_empty() {
+        return Msg::err(format!("no backups for route {}", args.route_id));
+    }
+    let mut versions = names
+        .iter()
+        .map(|name| backups.read(&args.route_id, Some(name)))
+        .collect::<Result<Vec<_>>>()?;
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    let keypair = args.keypair.to_keypair()?;
+    versions.push(
+        client
+            .get(&args.route_id, &args.keypair.to_keypair()?)
+            .await?,
+    );
 
-    let mut route = client.get(&args.route_id, &keypair).await?;
-    let old_route = route.clone();
-    let old_protocol = route.server.protocol;
+    let history = names
+        .iter()
+        .zip(versions.windows(2))
+        .map(|(name, pair)| {
+            Ok(json!({
+                "changed_at": Timestamp::from_unix_millis(backup_millis(name)?)?,
+                "backup": name,
+                "changes": route_changes(&pair[0], &pair[1])?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Msg::ok(history.pretty_json()?)
+}
 
-    let gwmp = if let Some(protocol) = old_protocol.as_ref() {
-        if protocol.is_gwmp() {
-            let mut new_protocol = protocol.clone();
-            let map = Protocol::make_gwmp_mapping(args.region, args.region_port);
-            new_protocol.gwmp_add_mapping(map)?;
-            new_protocol
-        } else {
-            Protocol::make_gwmp(args.region, args.region_port)?
+/// The Route from the first backup saved after `at`, which is how it stayed
+/// until that change. Without a later backup it has not been changed by this
+/// CLI since, and the current Route is shown.
+///
+/// Backups past retention are pruned, so before the oldest one the Route may
+/// have been different and only a warning says so.
+pub async fn show_route_at(args: ShowRouteAt) -> Result<Msg> {
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+    let names = backups.list(&args.route_id)?;
+    if let Some(oldest) = names.first() {
+        let oldest = backup_millis(oldest)?;
+        if i64::try_from(args.at)? < oldest {
+            warning::warn(format!(
+                "{} is before the oldest backup of route {}, saved at {}, \
+                 older versions may have been pruned",
+                Timestamp::from_unix_millis(i64::try_from(args.at)?)?.time,
+                args.route_id,
+                Timestamp::from_unix_millis(oldest)?.time,
+            ))?;
+        }
+    }
+    let route = match version_at(&names, args.at)? {
+        Some(name) => backups.read(&args.route_id, Some(name))?,
+        None => {
+            client::RouteClient::new(&args.config_host, &args.config_pubkey)
+                .await?
+                .get(&args.route_id, &args.keypair.to_keypair()?)
+                .await?
         }
-    } else {
-        Protocol::make_gwmp(args.region, args.region_port)?
     };
+    Msg::ok(route.redacted().pretty_json()?)
+}
 
-    route.server.protocol = Some(gwmp);
+/// Backups are named by the unix millis they were taken at.
+fn backup_millis(name: &str) -> Result<i64> {
+    Ok(name.trim_end_matches(".json").parse()?)
+}
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+/// The first of the backups `names`, oldest first, saved after `at` millis.
+fn version_at(names: &[String], at: u64) -> Result<Option<&String>> {
+    for name in names {
+        if backup_millis(name)? > i64::try_from(at)? {
+            return Ok(Some(name));
+        }
     }
+    Ok(None)
+}
 
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not update gwmp protocol: {err}")),
+/// Fields of `new` that differ from `old`, by their dotted path. Lists are
+/// compared whole, and `auth_header` redacted since backups do not keep it.
+fn route_changes(old: &Route, new: &Route) -> Result<Vec<FieldChange>> {
+    let mut changes = vec![];
+    field_changes(
+        &serde_json::to_value(old.redacted())?,
+        &serde_json::to_value(new.redacted())?,
+        "",
+        &mut changes,
+    );
+    Ok(changes)
+}
+
+fn field_changes(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    path: &str,
+    changes: &mut Vec<FieldChange>,
+) {
+    use serde_json::Value;
+    if let (Value::Object(old), Value::Object(new)) = (old, new) {
+        let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for key in keys {
+            let field = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            let (old, new) = (
+                old.get(key).unwrap_or(&Value::Null),
+                new.get(key).unwrap_or(&Value::Null),
+            );
+            field_changes(old, new, &field, changes);
+        }
+    } else if old != new {
+        changes.push(FieldChange {
+            field: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        });
     }
 }
 
-pub async fn remove_gwmp_region(args: RemoveGwmpRegion) -> Result<Msg> {
+/// Push a backed up Route over the current one. The current Route is itself
+/// backed up first, so a restore can be undone.
+pub async fn restore_backup(args: RestoreBackup) -> Result<Msg> {
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
     let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
 
-    let mut route = client.get(&args.route_id, &keypair).await?;
-    let old_route = route.clone();
+    let restored = backups.read(&args.route_id, args.backup.as_deref())?;
+    let current = client.get(&args.route_id, &keypair).await?;
+    push_protocol_with_diff(
+        &mut client,
+        current,
+        restored,
+        args.commit,
+        args.force,
+        args.allow_protocol_change,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+}
 
-    let old_protocol = route.server.protocol;
+pub async fn delete_route(args: DeleteRoute) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
 
-    let mut new_protocol = if let Some(p) = old_protocol.as_ref() {
-        p.clone()
-    } else {
-        return Msg::err("Cannot remove region mapping, no protocol".to_string());
-    };
-    new_protocol.gwmp_remove_mapping(&args.region)?;
+    if !args.commit {
+        if args.purge_members {
+            return Msg::dry_run(format!(
+                "remove all members of {} then delete it",
+                args.route_id
+            ));
+        }
+        return Msg::dry_run(format!("delete {}", args.route_id));
+    }
 
-    route.server.protocol = Some(new_protocol);
+    let keypair = args.keypair.to_keypair()?;
+    let route = client.get(&args.route_id, &keypair).await?;
+    Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+        .check(route.oui, &keypair)
+        .await?;
+    let backup = Backups::new(args.backup_dir, args.backup_retention)?.save(&route)?;
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+    let mut purged = String::new();
+    if args.purge_members {
+        match purge_members(
+            &client,
+            &args.config_host,
+            &args.config_pubkey,
+            &route.id,
+            &keypair,
+        )
+        .await
+        {
+            Ok(stats) => {
+                purged = format!(
+                    "removed {} euis, {} devaddr ranges, and {} session key filters\n",
+                    stats.eui_count, stats.devaddr_count, stats.skf_count
+                )
+            }
+            Err(err) => {
+                return Msg::err(format!(
+                    "route not deleted, could not remove its members: {err}\nbackup saved to {}",
+                    backup.display()
+                ))
+            }
+        }
     }
 
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
+    match client.delete(&args.route_id, &keypair).await {
+        Ok(removed_route) => Msg::ok(format!(
+            "{purged}deleted route {}\nbackup saved to {}",
+            removed_route.id,
+            backup.display()
         )),
-        Err(err) => Msg::err(format!("Could not update gwmp protocol: {err}")),
+        Err(err) => Msg::err(format!("route not deleted: {err}")),
     }
 }
 
-pub async fn update_packet_router(args: UpdatePacketRouter) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
-    let keypair = args.keypair.to_keypair()?;
+/// Remove every EUI, Devaddr Range, and Session Key Filter of a Route, so
+/// packet routers drop them before the Route itself is deleted.
+async fn purge_members(
+    client: &client::RouteClient,
+    config_host: &str,
+    config_pubkey: &str,
+    route_id: &str,
+    keypair: &Keypair,
+) -> Result<RouteStats> {
+    let members = members_for_route(config_host, config_pubkey, route_id, keypair).await?;
+    let progress = client.progress();
 
-    let mut route = client.get(&args.route_id, &keypair).await?;
-    let old_route = route.clone();
+    remove_members(&members.euis, "remove euis", progress.clone(), |chunk| {
+        let mut client = client.clone();
+        async move { client.remove_euis(chunk, keypair).await }
+    })
+    .await?;
+    remove_members(
+        &members.devaddr_ranges,
+        "remove devaddr ranges",
+        progress.clone(),
+        |chunk| {
+            let mut client = client.clone();
+            async move { client.remove_devaddrs(chunk, keypair).await }
+        },
+    )
+    .await?;
+    remove_members(
+        &members.skfs,
+        "remove session key filters",
+        progress,
+        |chunk| {
+            let mut client = client.clone();
+            let updates = chunk
+                .into_iter()
+                .map(|skf| SkfUpdate {
+                    devaddr: skf.devaddr,
+                    session_key: skf.session_key,
+                    action: UpdateAction::Remove,
+                    max_copies: skf.max_copies,
+                })
+                .collect();
+            async move { client.update_filters(route_id, updates, keypair).await }
+        },
+    )
+    .await?;
+    Ok(RouteStats::from(&members))
+}
 
-    let new_protocol = Protocol::default_packet_router();
-    route.server.protocol = Some(new_protocol);
+/// Send `members` with `remove` a chunk at a time, rate limited and retried
+/// like the other bulk removals.
+async fn remove_members<T, R, F, Fut>(
+    members: &[T],
+    operation: &'static str,
+    progress: Arc<dyn ProgressSink>,
+    mut remove: F,
+) -> Result
+where
+    T: Clone,
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let total = members.len();
+    let limiter = RateLimiter::default().with_progress(progress.clone());
+    let mut chunks = Chunker::new(members, BULK_CHUNK_SIZE).with_progress(progress.clone());
+    while let Some(chunk) = chunks.next_chunk() {
+        let started = Instant::now();
+        let result = limiter
+            .retry(|| remove(chunk.to_vec()))
+            .instrument(chunks.span())
+            .await;
+        chunks
+            .record(result, started.elapsed())
+            .context(format!("{operation}: removed {} of {total}", chunks.done()))?;
+        progress.event(ProgressEvent::Chunk {
+            operation,
+            done: chunks.done(),
+            total,
+        });
+    }
+    Ok(())
+}
+
+impl RouteTarget {
+    /// The Route to update, read from the route file or the config service.
+    async fn route(&self, client: &mut impl RouteApi, keypair: &Keypair) -> Result<Route> {
+        match (&self.route_id, &self.route_file) {
+            (_, Some(path)) => {
+                let route = RouteFile::read(path, self.strict)?.route;
+                if route.id.is_empty() {
+                    return Err(anyhow!(
+                        "route file {} has no id, create the route with `route new` first",
+                        path.display()
+                    ));
+                }
+                Ok(route)
+            }
+            (Some(route_id), None) => client.get(route_id, keypair).await,
+            (None, None) => Err(anyhow!("one of --route-id or --route-file is required")),
+        }
+    }
+}
+
+/// Adds a canary Session Key Filter with a random key and removes it again,
+/// timing each change from the update until it arrives on the Route stream.
+pub async fn probe_propagation(args: ProbePropagation) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let ranges = client::DevaddrClient::new(&args.config_host, &args.config_pubkey)
+        .await?
+        .get_devaddrs(&args.route_id, &keypair)
+        .await?;
+    let Some(devaddr) = ranges.first().map(|range| range.start_addr) else {
+        return Msg::err(format!(
+            "route {} has no devaddr ranges to place a canary filter in",
+            args.route_id
+        ));
+    };
+    let mut key = [0u8; 16];
+    OsRng.fill_bytes(&mut key);
+    let session_key: String = key.iter().map(|byte| format!("{byte:02x}")).collect();
 
     if !args.commit {
         return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
+            "add and remove canary session key filter {devaddr} {session_key} on {}",
+            args.route_id
         ));
     }
 
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
+    // only changes from now, so the stream does not start with a snapshot of
+    // every Route that would count toward the latency
+    let since = timestamp::now_millis()?.saturating_sub(STREAM_SINCE_MARGIN.as_millis() as u64);
+    let stream = client::RouteClient::new(&args.config_host, &args.config_pubkey)
+        .await?
+        .stream(since, &keypair)
+        .await
+        .context("subscribing to the route stream")?;
+    futures::pin_mut!(stream);
+    let mut skf_client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+    let canary = |action| SkfUpdate {
+        devaddr,
+        session_key: session_key.clone(),
+        action,
+        max_copies: Some(1),
+    };
+
+    let started = Instant::now();
+    skf_client
+        .update_filters(&args.route_id, vec![canary(UpdateAction::Add)], &keypair)
+        .await?;
+    let added = canary_seen(&mut stream, &args, &session_key, UpdateAction::Add, started).await;
+
+    // removed whether or not the add was seen, so the canary never stays on
+    // the Route
+    let started = Instant::now();
+    skf_client
+        .update_filters(&args.route_id, vec![canary(UpdateAction::Remove)], &keypair)
+        .await
+        .context(format!(
+            "removing canary session key filter {devaddr} {session_key} from {}, remove it \
+             with `route skfs remove`",
+            args.route_id
+        ))?;
+    let add_latency = match added {
+        Ok(latency) => latency,
+        Err(err) => return Msg::err(format!("canary add {err}")),
+    };
+    let remove_latency = match canary_seen(
+        &mut stream,
+        &args,
+        &session_key,
+        UpdateAction::Remove,
+        started,
+    )
+    .await
+    {
+        Ok(latency) => latency,
+        Err(err) => return Msg::err(format!("canary remove {err}")),
+    };
+
+    Msg::ok(
+        json!({
+            "route_id": args.route_id,
+            "add_latency_ms": add_latency.as_millis() as u64,
+            "remove_latency_ms": remove_latency.as_millis() as u64,
+        })
+        .pretty_json()?,
+    )
+}
+
+/// Time from `started` until the canary `action` arrives on `stream`.
+async fn canary_seen(
+    stream: &mut (impl Stream<Item = Result<RouteStreamResV1>> + Unpin),
+    args: &ProbePropagation,
+    session_key: &str,
+    action: UpdateAction,
+    started: Instant,
+) -> Result<Duration> {
+    let arrived = tokio::time::timeout(Duration::from_secs(args.wait_timeout), async {
+        while let Some(res) = stream.try_next().await? {
+            if is_canary_event(&res, &args.route_id, session_key, action) {
+                return Ok(started.elapsed());
+            }
+        }
+        Err(anyhow!("route stream ended"))
+    })
+    .await;
+    match arrived {
+        Ok(Ok(latency)) => Ok(latency),
+        Ok(Err(err)) => Err(anyhow!("not seen: {err}")),
+        Err(_) => Err(anyhow!(
+            "not seen on the route stream within {}s",
+            args.wait_timeout
         )),
-        Err(_) => todo!(),
     }
 }
 
-pub async fn update_ignore_empty_skf(args: SetIgnoreEmptySkf) -> Result<Msg> {
+/// Follows the route stream until it cannot be reconnected. A closed or
+/// failed stream is subscribed to again from the last change received, so a
+/// change may be passed on twice but none are missed. A change the webhook
+/// still rejects after retrying stops the command instead.
+pub async fn stream_routes(args: StreamRoutes) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let since = match (args.since, &args.state_file) {
+        (Some(since), _) => since,
+        (None, Some(path)) if path.exists() => read_last_seen(path)?,
+        (None, _) => {
+            timestamp::now_millis()?.saturating_sub(STREAM_SINCE_MARGIN.as_millis() as u64)
+        }
+    };
+    let mut filter = StreamFilter {
+        route_id: args.route_id,
+        oui: None,
+        since,
+    };
+    if let Some(oui) = args.oui {
+        let routes = client.list(oui, &keypair).await?.routes;
+        filter.oui = Some((oui, routes.into_iter().map(|route| route.id).collect()));
+    }
+    let http = reqwest::Client::new();
+
+    let mut last_seen = since;
+    let mut reconnects = 0;
+    loop {
+        let closed = async {
+            let stream = client
+                .stream(last_seen, &keypair)
+                .await
+                .context("subscribing to the route stream")?;
+            futures::pin_mut!(stream);
+            while let Some(res) = stream.try_next().await? {
+                reconnects = 0;
+                let mut change = RouteChange::try_from(res)?;
+                last_seen = last_seen.max(change.timestamp);
+                if let RouteChangeItem::Route(route) = &mut change.item {
+                    route.redact();
+                }
+                if filter.matches(&change) {
+                    match &args.webhook {
+                        Some(url) => post_change(&http, url, &change).await?,
+                        None => println!("{}", serde_json::to_string(&change)?),
+                    }
+                }
+                if let Some(path) = &args.state_file {
+                    shared_file::write(path, last_seen.to_string())?;
+                }
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        let reason = match closed {
+            Ok(()) => "route stream closed".to_string(),
+            Err(err) if is_stream_error(&err) => format!("route stream failed: {err:#}"),
+            Err(err) => return Err(err),
+        };
+        if reconnects == STREAM_RECONNECTS {
+            return Msg::err(format!("{reason}, gave up after {reconnects} reconnects"));
+        }
+        warning::warn(format!("{reason}, reconnecting"))?;
+        tokio::time::sleep(retry::backoff_delay(reconnects)).await;
+        reconnects += 1;
+    }
+}
+
+/// Errors of the subscription itself, rather than of passing a change on.
+fn is_stream_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.downcast_ref::<tonic::Status>().is_some())
+}
+
+fn read_last_seen(path: &Path) -> Result<u64> {
+    let contents = std::fs::read_to_string(path).context(format!("reading {}", path.display()))?;
+    contents
+        .trim()
+        .parse()
+        .context(format!("{} is not a unix millis timestamp", path.display()))
+}
+
+/// The changes `route stream` passes on, everything by default.
+struct StreamFilter {
+    route_id: Option<String>,
+    /// The OUI and the ids of its Routes, which grows as Routes are created
+    oui: Option<(Oui, HashSet<String>)>,
+    /// Unix milliseconds, changes before are dropped when the config service
+    /// sends them anyway
+    since: u64,
+}
+
+impl StreamFilter {
+    fn matches(&mut self, change: &RouteChange) -> bool {
+        if change.timestamp < self.since {
+            return false;
+        }
+        let route_id = change.item.route_id();
+        if self.route_id.as_ref().is_some_and(|id| id != route_id) {
+            return false;
+        }
+        match (&mut self.oui, &change.item) {
+            (Some((oui, route_ids)), RouteChangeItem::Route(route)) if route.oui == *oui => {
+                route_ids.insert(route.id.clone());
+                true
+            }
+            (Some((_, route_ids)), _) => route_ids.contains(route_id),
+            (None, _) => true,
+        }
+    }
+}
+
+async fn post_change(http: &reqwest::Client, url: &reqwest::Url, change: &RouteChange) -> Result {
+    let mut attempt = 0;
+    loop {
+        let err = match http.post(url.clone()).json(change).send().await {
+            Ok(res) if res.status().is_success() => return Ok(()),
+            // the hook will not accept this change on retry
+            Ok(res) if res.status().is_client_error() && res.status().as_u16() != 429 => {
+                return Err(anyhow!("webhook rejected change: {}", res.status()))
+            }
+            Ok(res) => anyhow!("webhook responded {}", res.status()),
+            Err(err) => err.into(),
+        };
+        if attempt == WEBHOOK_RETRIES {
+            return Err(err.context(format!("posting change to {url}")));
+        }
+        warning::warn(format!("could not post change, retrying: {err}"))?;
+        tokio::time::sleep(retry::backoff_delay(attempt)).await;
+        attempt += 1;
+    }
+}
+
+fn is_canary_event(
+    res: &RouteStreamResV1,
+    route_id: &str,
+    session_key: &str,
+    action: UpdateAction,
+) -> bool {
+    let expected = match action {
+        UpdateAction::Add => ActionV1::Add,
+        UpdateAction::Remove => ActionV1::Remove,
+    };
+    match &res.data {
+        Some(route_stream_res_v1::Data::Skf(skf)) => {
+            res.action == expected as i32
+                && skf.route_id == route_id
+                && skf.session_key == session_key
+        }
+        _ => false,
+    }
+}
+
+pub async fn update_max_copies(args: UpdateMaxCopies) -> Result<Msg> {
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
     let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
 
-    let mut route = client.get(&args.route_id, &keypair).await?;
+    let mut route = args.target.route(&mut client, &keypair).await?;
     let old_route = route.clone();
 
-    route.ignore_empty_skf = args.ignore;
+    if let Some(too_many) = max_copies_warning(args.max_copies) {
+        warning::warn(too_many)?;
+    }
+    route.max_copies = args.max_copies;
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?,
+    push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("could not update max_copies"))
+}
+
+pub async fn update_server(args: UpdateServer) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    let old_route = route.clone();
+
+    route.server.host = args.host;
+    route.server.port = args.port;
+
+    push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("could not update server host and port"))
+}
+
+pub async fn update_http(args: UpdateHttp) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    let old_route = route.clone();
+
+    let auth_header = auth_header(args.auth_header, args.auth_header_file.as_deref())?;
+    let http = Protocol::make_http(
+        args.dedupe_timeout,
+        args.path,
+        auth_header,
+        args.receiver_nsid,
+    );
+    route.server.protocol = Some(http);
+
+    push_protocol_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        args.allow_protocol_change,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not update http protocol"))
+}
+
+pub async fn add_gwmp_region(args: AddGwmpRegion) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    let old_route = route.clone();
+    let old_protocol = route.server.protocol;
+
+    let gwmp = if let Some(protocol) = old_protocol.as_ref() {
+        if protocol.is_gwmp() {
+            let mut new_protocol = protocol.clone();
+            let map = Protocol::make_gwmp_mapping(args.region, args.region_port);
+            if let Err(err) = check_gwmp_conflicts(protocol, &map, args.replace) {
+                return Msg::err(format!("Could not update gwmp protocol: {err}"));
+            }
+            new_protocol.gwmp_add_mapping(map)?;
+            new_protocol
+        } else {
+            Protocol::make_gwmp(args.region, args.region_port)?
+        }
+    } else {
+        Protocol::make_gwmp(args.region, args.region_port)?
+    };
+
+    route.server.protocol = Some(gwmp);
+
+    push_protocol_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        args.allow_protocol_change,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not update gwmp protocol"))
+}
+
+/// Errors when `map` would move an already mapped region to another port,
+/// unless `replace` is set, in which case each replaced port is warned about.
+fn check_gwmp_conflicts(protocol: &Protocol, map: &GwmpMap, replace: bool) -> Result {
+    let conflicts = protocol.gwmp_conflicts(map);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    if !replace {
+        let conflicts: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+        return Err(anyhow!(
+            "{}, pass --replace to overwrite",
+            conflicts.join(", ")
         ));
     }
+    for conflict in conflicts {
+        warning::warn(format!("replacing gwmp mapping, {conflict}"))?;
+    }
+    Ok(())
+}
 
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!(
-            "Count not update route ignore empty skf setting: {err}"
-        )),
+pub async fn remove_gwmp_region(args: RemoveGwmpRegion) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    let old_route = route.clone();
+
+    let old_protocol = route.server.protocol;
+
+    let mut new_protocol = if let Some(p) = old_protocol.as_ref() {
+        p.clone()
+    } else {
+        return Msg::err("Cannot remove region mapping, no protocol".to_string());
+    };
+    for region in &args.regions {
+        new_protocol.gwmp_remove_mapping(region)?;
+    }
+
+    route.server.protocol = Some(new_protocol);
+
+    push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not update gwmp protocol"))
+}
+
+pub async fn clear_protocol(args: ClearProtocol) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    if route.server.protocol.is_none() {
+        return already_applied(format!("route {} has no protocol", route.id));
     }
+    if args.commit && !args.force {
+        return Msg::err(format!(
+            "clearing the protocol stops routing to {}, pass `--force` to commit",
+            route.id
+        ));
+    }
+    let old_route = route.clone();
+
+    route.server.protocol = None;
+
+    push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not clear protocol"))
+}
+
+pub async fn update_packet_router(args: UpdatePacketRouter) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    let old_route = route.clone();
+
+    let new_protocol = Protocol::default_packet_router();
+    route.server.protocol = Some(new_protocol);
+
+    push_protocol_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        args.allow_protocol_change,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not update packet router protocol"))
+}
+
+pub async fn update_ignore_empty_skf(args: SetIgnoreEmptySkf) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
+
+    let mut route = args.target.route(&mut client, &keypair).await?;
+    let old_route = route.clone();
+
+    route.ignore_empty_skf = args.ignore;
+
+    push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not update route ignore empty skf setting"))
 }
 
 pub async fn activate_route(args: ActivateRoute) -> Result<Msg> {
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
     let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
     let old_route = route.clone();
 
     route.active = true;
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
-    }
-
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not activate route: {err}")),
-    }
+    push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not activate route"))
 }
 
 pub async fn deactivate_route(args: DeactivateRoute) -> Result<Msg> {
     let mut client = client::RouteClient::new(&args.config_host, &args.config_pubkey).await?;
     let keypair = args.keypair.to_keypair()?;
+    let backups = Backups::new(args.backup_dir, args.backup_retention)?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
     let old_route = route.clone();
 
     route.active = false;
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
-    }
-
-    match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
+    let msg = push_with_diff(
+        &mut client,
+        old_route,
+        route,
+        args.commit,
+        args.force,
+        &backups,
+        &keypair,
+        &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+    )
+    .await
+    .map(|msg| msg.with_err_context("Could not deactivate route"))?;
+    let Some(duration) = args.reactivate_after else {
+        return Ok(msg);
+    };
+    let command = reactivate_command(&std::env::current_exe()?, &args)?;
+    match msg {
+        Msg::DryRun(diff) => Msg::dry_run(format!(
+            "{diff}\nreactivate after {}",
+            humantime::format_duration(duration)
+        )),
+        Msg::Success(diff) if !args.stay => Msg::ok(format!(
+            "{diff}\n{}",
+            reactivation_instructions(&args.route_id, &command, duration, SystemTime::now())
         )),
-        Err(err) => Msg::err(format!("Could not deactivate route: {err}")),
+        Msg::Success(diff) => {
+            // printed now so the route can be recovered if this process dies
+            eprintln!(
+                "{diff}\n{}",
+                reactivation_instructions(&args.route_id, &command, duration, SystemTime::now())
+            );
+            tokio::time::sleep(duration).await;
+
+            let mut route = client.get(&args.route_id, &keypair).await?;
+            let old_route = route.clone();
+            route.active = true;
+            push_with_diff(
+                &mut client,
+                old_route,
+                route,
+                true,
+                args.force,
+                &backups,
+                &keypair,
+                &Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight),
+            )
+            .await
+            .map(|msg| msg.with_err_context("Could not reactivate route"))
+        }
+        err => Ok(err),
     }
 }
 
-pub mod skfs {
-    use crate::{
-        client,
-        cmds::{
-            AddFilter, ClearFilters, GetFilters, ListFilters, PathBufKeypair, RemoveFilter,
-            UpdateFilters,
-        },
-        Msg, PrettyJson, Result, Skf, SkfUpdate,
+/// Nothing in the config service reactivates a route, so the CLI tells the
+/// operator how to schedule it.
+fn reactivation_instructions(
+    route_id: &str,
+    command: &str,
+    duration: Duration,
+    now: SystemTime,
+) -> String {
+    let secs = duration.as_secs();
+    format!(
+        "route {route_id} should be reactivated at {}, run\n  {command}\n\
+         or schedule it with\n  \
+         systemd-run --user --on-active={secs}s {command}\n  \
+         echo {} | at now + {} minutes",
+        humantime::format_rfc3339_seconds(now + duration),
+        shell_quote(command),
+        secs.div_ceil(60),
+    )
+}
+
+/// The `route activate` undoing `args`. systemd and at run it from another
+/// directory and environment, so paths are absolute and the keypair and
+/// config service are passed explicitly.
+fn reactivate_command(exe: &Path, args: &DeactivateRoute) -> Result<String> {
+    let absolute = |path: &Path| -> Result<String> {
+        Ok(std::path::absolute(path)?.to_string_lossy().into_owned())
     };
-    use anyhow::Context;
+    let config_pubkey = match Path::new(&args.config_pubkey).is_file() {
+        true => absolute(Path::new(&args.config_pubkey))?,
+        false => args.config_pubkey.clone(),
+    };
+    let mut command = vec![
+        absolute(exe)?,
+        "route".to_string(),
+        "activate".to_string(),
+        "--route-id".to_string(),
+        args.route_id.clone(),
+        "--keypair".to_string(),
+        absolute(&args.keypair)?,
+        "--config-host".to_string(),
+        args.config_host.clone(),
+        "--config-pubkey".to_string(),
+        config_pubkey,
+    ];
+    if let Some(backup_dir) = &args.backup_dir {
+        command.extend(["--backup-dir".to_string(), absolute(backup_dir)?]);
+    }
+    if args.no_preflight {
+        command.push("--no-preflight".to_string());
+    }
+    command.push("--commit".to_string());
+    Ok(command
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
 
-    pub async fn list_filters(args: ListFilters) -> Result<Msg> {
-        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
-        let filters = client
-            .list_filters(&args.route_id, &args.keypair.to_keypair()?)
-            .await?;
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@,+".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
 
-        Msg::ok(filters.pretty_json()?)
+/// Verifies the signing key is the owner or a delegate of a Route's OUI
+/// before a mutation is sent, since the config service's permission errors do
+/// not say which key or Org was checked.
+///
+/// Admin keys are not Org members, they need `--no-preflight`.
+pub struct Preflight<'a> {
+    config_host: &'a str,
+    config_pubkey: &'a str,
+    skip: bool,
+}
+
+impl<'a> Preflight<'a> {
+    pub fn new(config_host: &'a str, config_pubkey: &'a str, no_preflight: bool) -> Self {
+        Self {
+            config_host,
+            config_pubkey,
+            skip: no_preflight,
+        }
+    }
+
+    /// Check `oui`, the OUI of a Route the caller already read.
+    pub async fn check(&self, oui: Oui, keypair: &Keypair) -> Result {
+        if self.skip {
+            return Ok(());
+        }
+        let mut orgs = client::OrgClient::new(self.config_host, self.config_pubkey).await?;
+        check_signer(&mut orgs, oui, keypair).await
+    }
+
+    /// Check the OUI of `route_id`, for changes to a Route's members that do
+    /// not otherwise read the Route.
+    pub async fn check_route(&self, route_id: &str, keypair: &Keypair) -> Result {
+        if self.skip {
+            return Ok(());
+        }
+        let route = client::RouteClient::new(self.config_host, self.config_pubkey)
+            .await?
+            .get(route_id, keypair)
+            .await
+            .context(format!(
+                "preflight: key {} could not read route {route_id}",
+                keypair.public_key()
+            ))?;
+        self.check(route.oui, keypair).await
+    }
+}
+
+async fn check_signer(orgs: &mut impl OrgApi, oui: Oui, keypair: &Keypair) -> Result {
+    let org = orgs
+        .get(oui)
+        .await
+        .context(format!("preflight: could not get OUI {oui}"))?
+        .org;
+    let signer = keypair.public_key();
+    if org.is_managed_by(signer) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "key {signer} is not the owner or a delegate of OUI {oui}, pass `--no-preflight` to send anyway"
+    ))
+}
+
+/// The Routes are printed with their `auth_header` redacted, a changed one
+/// is noted after them.
+fn route_diff(route_id: &str, old_route: &Route, new_route: &Route) -> Result<String> {
+    let (old_redacted, new_redacted) = (old_route.redacted(), new_route.redacted());
+    let auth_header_changed = old_redacted == new_redacted && old_route != new_route;
+    Ok(format!(
+        "Updated {route_id}\n== Old\n{}\n== New\n{}{}",
+        old_redacted.pretty_json()?,
+        new_redacted.pretty_json()?,
+        if auth_header_changed {
+            "\nauth_header changed"
+        } else {
+            ""
+        }
+    ))
+}
+
+/// Push `new_route` when committing, reporting the difference from `old_route`.
+///
+/// Routes carry no version, so before committing the Route is read again and
+/// compared with `old_route`. A Route changed by someone else since it was
+/// read is not overwritten unless `force` is set. Route files keep the
+/// `auth_header` redacted, so it is taken from the Route read again and left
+/// out of the comparison.
+#[allow(clippy::too_many_arguments)]
+async fn push_with_diff(
+    client: &mut impl RouteApi,
+    old_route: Route,
+    new_route: Route,
+    commit: bool,
+    force: bool,
+    backups: &Backups,
+    keypair: &Keypair,
+    preflight: &Preflight<'_>,
+) -> Result<Msg> {
+    let mut new_route = new_route;
+    new_route.unredact(&old_route)?;
+    if let Err(err) = new_route.server.validate() {
+        return Msg::err(format!("invalid server: {err}"));
+    }
+    if !commit {
+        return Msg::dry_run(route_diff(&new_route.id, &old_route, &new_route)?);
+    }
+    let live_route = client.get(&new_route.id, keypair).await?;
+    preflight.check(live_route.oui, keypair).await?;
+    new_route.unredact(&live_route)?;
+    if live_route.redacted() != old_route.redacted() {
+        let changes = route_diff(&live_route.id, &old_route, &live_route)?;
+        if !force {
+            return Msg::err(format!(
+                "route {} changed on the server since it was read, \
+                 re-run to apply on top of it or pass --force to overwrite:\n{changes}",
+                live_route.id
+            ));
+        }
+        warning::warn(format!(
+            "overwriting changes made on the server:\n{changes}"
+        ))?;
+    }
+    let backup = backups.save(&live_route)?;
+
+    match client.push(new_route, keypair).await {
+        Ok(updated_route) => Msg::ok(format!(
+            "{}\nbackup saved to {}",
+            route_diff(&updated_route.id, &old_route, &updated_route)?,
+            backup.display()
+        )),
+        Err(err) => Msg::err(err.to_string()),
+    }
+}
+
+/// Like [`push_with_diff`], but refuses to commit a protocol type change
+/// unless it has been explicitly allowed.
+#[allow(clippy::too_many_arguments)]
+async fn push_protocol_with_diff(
+    client: &mut impl RouteApi,
+    old_route: Route,
+    new_route: Route,
+    commit: bool,
+    force: bool,
+    allow_protocol_change: bool,
+    backups: &Backups,
+    keypair: &Keypair,
+    preflight: &Preflight<'_>,
+) -> Result<Msg> {
+    if let Some(change) = describe_protocol_change(&old_route, &new_route) {
+        if !commit {
+            warning::warn(&change)?;
+            return Msg::dry_run(route_diff(&new_route.id, &old_route, &new_route)?);
+        }
+        if !allow_protocol_change {
+            return Msg::err(format!(
+                "{change}, pass `--allow-protocol-change` to commit"
+            ));
+        }
+    }
+
+    push_with_diff(
+        client, old_route, new_route, commit, force, backups, keypair, preflight,
+    )
+    .await
+}
+
+/// Describes a change between two different protocol types.
+///
+/// Setting a protocol on a Route without one is not considered a change.
+fn describe_protocol_change(old_route: &Route, new_route: &Route) -> Option<String> {
+    match (&old_route.server.protocol, &new_route.server.protocol) {
+        (Some(old), Some(new)) if old.name() != new.name() => {
+            Some(format!("protocol change {} -> {}", old.name(), new.name()))
+        }
+        _ => None,
+    }
+}
+
+pub mod skfs {
+    use super::{sort_filters, Preflight};
+    use crate::{
+        client,
+        cmds::{
+            already_applied, format_table, read_input, write_records, write_stream, AddFilter,
+            ClearFilters, ExportFilters, FilterWizard, GetFilters, ListFilters, MoveFilters,
+            PathBufKeypair, RemoveFilter, SetFilterMaxCopies, UpdateFileFormat, UpdateFilters,
+            VerifyFilters, BULK_CHUNK_SIZE,
+        },
+        hex_field::HexDevAddr,
+        max_copies_warning,
+        normalize::normalize,
+        progress::ProgressEvent,
+        retry::{Chunker, RateLimiter},
+        schema::{self, Versioned},
+        warning, LnsSkf, Msg, PrettyJson, Result, Skf, SkfUpdate, UpdateAction,
+        DEFAULT_SKF_MAX_COPIES, MAX_COPIES_LIMIT,
+    };
+    use anyhow::{anyhow, Context};
+    use dialoguer::{Confirm, Input};
+    use flate2::{write::GzEncoder, Compression};
+    use futures::TryStreamExt;
+    use helium_crypto::Keypair;
+    use serde::Serialize;
+    use std::{
+        collections::BTreeMap,
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+        time::Instant,
+    };
+    use tracing::Instrument;
+
+    pub async fn list_filters(args: ListFilters) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let filters = client.stream_filters(&args.route_id, &keypair).await?;
+
+        let Some(range) = args.subnet.or(args.devaddr_prefix) else {
+            return write_records(filters, args.out_format, args.out_file.as_deref()).await;
+        };
+        // Filtering happens client side, the config service streams every filter
+        let (mut seen, mut matched) = (0, 0);
+        let filters = filters.try_filter(|filter| {
+            seen += 1;
+            let keep = range.contains(filter.devaddr);
+            if keep {
+                matched += 1;
+            }
+            futures::future::ready(keep)
+        });
+        let msg = write_records(filters, args.out_format, args.out_file.as_deref()).await?;
+        eprintln!(
+            "{matched} of {seen} filters for devaddrs {} through {}",
+            range.start_addr, range.end_addr
+        );
+        Ok(msg)
+    }
+
+    pub async fn get_filters(args: GetFilters) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let filters = client
+            .get_filters(&args.route_id, args.devaddr, &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(filters.pretty_json()?)
+    }
+
+    pub async fn add_filter(args: AddFilter) -> Result<Msg> {
+        if args.stdin {
+            return add_filters_from_stdin(args).await;
+        }
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let (max_copies, source) = match args.max_copies {
+            Some(max_copies) => (max_copies, ""),
+            None => {
+                let keypair = args.keypair.to_keypair()?;
+                let route_max_copies = route_max_copies(
+                    &args.config_host,
+                    &args.config_pubkey,
+                    &args.route_id,
+                    &keypair,
+                )
+                .await?;
+                (route_max_copies, " from the route")
+            }
+        };
+        if let Some(too_many) = max_copies_warning(max_copies) {
+            warning::warn(too_many)?;
+        }
+        let filter = Skf::new(
+            args.route_id.clone(),
+            args.devaddr.context("devaddr is required")?,
+            args.session_key.context("session_key is required")?,
+            Some(max_copies),
+        )?;
+
+        if args.idempotent && has_filter(&mut client, &filter, &args.keypair.to_keypair()?).await? {
+            return already_applied(format!("{filter:?} already in {}", args.route_id));
+        }
+
+        if !args.commit {
+            return Msg::dry_run(format!(
+                "added {filter:?} with max_copies {max_copies}{source}"
+            ));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .add_filter(filter.clone(), &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(format!(
+            "added {filter:?} with max_copies {max_copies}{source}"
+        ))
+    }
+
+    /// Filters added without a max_copies buy as many copies as their Route,
+    /// not a fixed default.
+    async fn route_max_copies(
+        config_host: &str,
+        config_pubkey: &str,
+        route_id: &str,
+        keypair: &Keypair,
+    ) -> Result<u32> {
+        let route = client::RouteClient::new(config_host, config_pubkey)
+            .await?
+            .get(route_id, keypair)
+            .await
+            .context(format!("reading max_copies of route {route_id}"))?;
+        Ok(route.max_copies)
+    }
+
+    /// Give additions without a max_copies the Route's, fetching the Route
+    /// only when one needs it. A note of the value used for the report.
+    async fn fill_route_max_copies(
+        config_host: &str,
+        config_pubkey: &str,
+        route_id: &str,
+        updates: &mut [SkfUpdate],
+        keypair: &Keypair,
+    ) -> Result<Vec<String>> {
+        let needs_default =
+            |update: &SkfUpdate| update.action == UpdateAction::Add && update.max_copies.is_none();
+        let count = updates
+            .iter()
+            .filter(|update| needs_default(update))
+            .count();
+        if count == 0 {
+            return Ok(vec![]);
+        }
+        let max_copies = route_max_copies(config_host, config_pubkey, route_id, keypair).await?;
+        for update in updates.iter_mut().filter(|update| needs_default(update)) {
+            update.max_copies = Some(max_copies);
+        }
+        Ok(vec![format!(
+            "{count} filters without max_copies use the route's max_copies {max_copies}"
+        )])
+    }
+
+    /// One warning for every filter update over the network maximum.
+    fn warn_max_copies(updates: &[SkfUpdate]) -> Result {
+        let over: Vec<u32> = updates
+            .iter()
+            .filter_map(|update| update.max_copies)
+            .filter(|max_copies| max_copies_warning(*max_copies).is_some())
+            .collect();
+        match over.iter().max() {
+            Some(highest) => warning::warn(format!(
+                "{} filters have max_copies over the network maximum of {MAX_COPIES_LIMIT}, up to {highest}, the extra copies only spend DC",
+                over.len()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Session keys read from stdin are never echoed back, only counted.
+    async fn add_filters_from_stdin(args: AddFilter) -> Result<Msg> {
+        let data = read_input(Path::new("-"))?;
+        let updates = SkfUpdate::parse_add_lines(&data, args.max_copies)?;
+        let normalized = normalize(updates)?;
+        let mut report = normalized.report()?;
+        let mut updates = normalized.updates;
+        let update_count = updates.len();
+        let keypair = args.keypair.to_keypair()?;
+        report.extend(
+            fill_route_max_copies(
+                &args.config_host,
+                &args.config_pubkey,
+                &args.route_id,
+                &mut updates,
+                &keypair,
+            )
+            .await?,
+        );
+        warn_max_copies(&updates)?;
+
+        if !args.commit {
+            return Msg::dry_run(
+                [report, vec![format!("added {update_count} filters")]]
+                    .concat()
+                    .join("\n"),
+            );
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        let client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let limiter = RateLimiter::default();
+        let mut chunks = Chunker::new(&updates, BULK_CHUNK_SIZE);
+        while let Some(chunk) = chunks.next_chunk() {
+            let started = Instant::now();
+            let result = limiter
+                .retry(|| {
+                    let mut client = client.clone();
+                    let (route_id, keypair) = (&args.route_id, &keypair);
+                    async move {
+                        client
+                            .update_filters(route_id, chunk.to_vec(), keypair)
+                            .await
+                    }
+                })
+                .instrument(chunks.span())
+                .await;
+            chunks
+                .record(result, started.elapsed())
+                .context(format!("added {} of {update_count} filters", chunks.done()))?;
+        }
+
+        Msg::ok(
+            [report, vec![format!("added {update_count} filters")]]
+                .concat()
+                .join("\n"),
+        )
+    }
+
+    pub async fn remove_filter(args: RemoveFilter) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let filter = Skf::new(args.route_id.clone(), args.devaddr, args.session_key, None)?;
+
+        if args.idempotent && !has_filter(&mut client, &filter, &args.keypair.to_keypair()?).await?
+        {
+            return already_applied(format!("{filter:?} not in {}", args.route_id));
+        }
+
+        if !args.commit {
+            return Msg::dry_run(format!("removed {filter:?}"));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .remove_filter(filter.clone(), &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(format!("removed {filter:?}"))
+    }
+
+    /// Whether the Route has a filter for the devaddr and session key,
+    /// whatever its max_copies.
+    async fn has_filter(
+        client: &mut client::SkfClient,
+        filter: &Skf,
+        keypair: &Keypair,
+    ) -> Result<bool> {
+        let filters = client
+            .get_filters(&filter.route_id, filter.devaddr, keypair)
+            .await?;
+        Ok(filters
+            .iter()
+            .any(|existing| existing.session_key == filter.session_key))
+    }
+
+    pub async fn set_max_copies(args: SetFilterMaxCopies) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+
+        let existing = client
+            .get_filters(&args.route_id, args.devaddr, &keypair)
+            .await?
+            .into_iter()
+            .find(|filter| filter.session_key == args.session_key);
+        let Some(existing) = existing else {
+            return Msg::err(format!(
+                "no session key filter for devaddr {} with that session key",
+                args.devaddr
+            ));
+        };
+
+        if let Some(too_many) = max_copies_warning(args.max_copies) {
+            warning::warn(too_many)?;
+        }
+        let filter = Skf::new(
+            args.route_id.clone(),
+            args.devaddr,
+            args.session_key,
+            Some(args.max_copies),
+        )?;
+        let summary = format!(
+            "devaddr {} max_copies {} -> {}",
+            filter.devaddr,
+            existing.effective_max_copies(),
+            args.max_copies
+        );
+
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &keypair)
+            .await?;
+
+        client.replace_filter(filter, &keypair).await?;
+
+        Msg::ok(summary)
+    }
+
+    pub async fn clear_filters(args: ClearFilters) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+
+        if !args.commit {
+            return Msg::dry_run(format!(
+                "All Session Key Filters removed from {}",
+                args.route_id
+            ));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .delete_filters(args.route_id.clone(), &args.keypair.to_keypair()?)
+            .await?;
+        Msg::ok(format!(
+            "All Session Key Filters removed from {}",
+            args.route_id
+        ))
+    }
+
+    /// Each record carries the `schema_version` it is written in. Like route
+    /// files and devaddr exports, filters are sorted, so exports of the same
+    /// Route are identical.
+    pub async fn export_filters(args: ExportFilters) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let mut filters = client
+            .list_filters(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+        sort_filters(&mut filters);
+        let filters = futures::stream::iter(filters.into_iter().map(|skf| Ok(Versioned::new(skf))));
+
+        let file = File::create(&args.out_file).context("creating out file")?;
+        let out = BufWriter::new(file);
+        let count = if args.gzip {
+            let mut encoder = GzEncoder::new(out, Compression::default());
+            let count = write_stream(filters, args.out_format, &mut encoder).await?;
+            encoder.finish()?.flush()?;
+            count
+        } else {
+            write_stream(filters, args.out_format, out).await?
+        };
+
+        Msg::ok(format!(
+            "wrote {count} filters from {} to {}",
+            args.route_id,
+            args.out_file.display()
+        ))
+    }
+
+    /// Each chunk is added to the destination before it is removed from the
+    /// source, so devices stay routed while they move.
+    pub async fn move_filters(args: MoveFilters) -> Result<Msg> {
+        if args.from == args.to {
+            return Msg::err("--from and --to are the same route".to_string());
+        }
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let filters = match args.devaddr {
+            Some(devaddr) => client.get_filters(&args.from, devaddr, &keypair).await?,
+            None => client.list_filters(&args.from, &keypair).await?,
+        };
+        let total = filters.len();
+        let summary = format!("moved {total} filters from {} to {}", args.from, args.to);
+
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.to, &keypair)
+            .await?;
+
+        let progress = client.progress();
+        let limiter = RateLimiter::default().with_progress(progress.clone());
+        let mut chunks = Chunker::new(&filters, BULK_CHUNK_SIZE).with_progress(progress.clone());
+        while let Some(chunk) = chunks.next_chunk() {
+            let started = Instant::now();
+            let mut result = Ok(());
+            for (route_id, action) in [
+                (&args.to, UpdateAction::Add),
+                (&args.from, UpdateAction::Remove),
+            ] {
+                let updates = filter_updates(chunk, action);
+                result = limiter
+                    .retry(|| {
+                        let mut client = client.clone();
+                        let (updates, keypair) = (updates.clone(), &keypair);
+                        async move { client.update_filters(route_id, updates, keypair).await }
+                    })
+                    .instrument(chunks.span())
+                    .await
+                    .map(|_| ());
+                if result.is_err() {
+                    break;
+                }
+            }
+            chunks
+                .record(result, started.elapsed())
+                .context(format!("moved {} of {total} filters", chunks.done()))?;
+            progress.event(ProgressEvent::Chunk {
+                operation: "move filters",
+                done: chunks.done(),
+                total,
+            });
+        }
+
+        Msg::ok(summary)
+    }
+
+    /// Prompts are on stderr, like progress, so only the result is printed to
+    /// stdout. Nothing is added until the summary is confirmed.
+    pub async fn filter_wizard(args: FilterWizard) -> Result<Msg> {
+        let keypair = args.keypair.to_keypair()?;
+        let max_copies = match args.max_copies {
+            Some(max_copies) => max_copies,
+            None => {
+                route_max_copies(
+                    &args.config_host,
+                    &args.config_pubkey,
+                    &args.route_id,
+                    &keypair,
+                )
+                .await?
+            }
+        };
+        let mut updates: Vec<SkfUpdate> = vec![];
+        loop {
+            let devaddr: HexDevAddr = Input::new().with_prompt("Devaddr").interact_text()?;
+            let session_key: String = Input::new()
+                .with_prompt("Session key")
+                .validate_with(|key: &String| validate_session_key(key))
+                .interact_text()?;
+            let max_copies: u32 = Input::new()
+                .with_prompt("Max copies")
+                .default(max_copies)
+                .interact_text()?;
+            let update = SkfUpdate {
+                devaddr,
+                session_key: session_key.to_lowercase(),
+                action: UpdateAction::Add,
+                max_copies: Some(max_copies),
+            };
+            if updates.iter().any(|added| same_filter(added, &update)) {
+                eprintln!("{devaddr} with this session key is already in the batch, skipped");
+            } else {
+                updates.push(update);
+            }
+            if !Confirm::new()
+                .with_prompt("Add another filter?")
+                .default(true)
+                .interact()?
+            {
+                break;
+            }
+        }
+
+        eprintln!("{}", format_table(&wizard_rows(&updates)));
+        warn_max_copies(&updates)?;
+        let confirmed = Confirm::new()
+            .with_prompt(format!(
+                "Add {} filters to {}?",
+                updates.len(),
+                args.route_id
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            return Msg::ok("cancelled, no filters added".to_string());
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &keypair)
+            .await?;
+        let count = updates.len();
+        client::SkfClient::new(&args.config_host, &args.config_pubkey)
+            .await?
+            .update_filters(&args.route_id, updates, &keypair)
+            .await?;
+        Msg::ok(format!("added {count} filters to {}", args.route_id))
+    }
+
+    /// A LoRaWAN 1.0 NwkSKey, 16 bytes of hex.
+    pub(super) fn validate_session_key(key: &str) -> Result {
+        if key.len() != 32 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("expected 32 hex characters, got {}", key.len()));
+        }
+        Ok(())
+    }
+
+    fn same_filter(a: &SkfUpdate, b: &SkfUpdate) -> bool {
+        a.devaddr == b.devaddr && a.session_key.eq_ignore_ascii_case(&b.session_key)
+    }
+
+    /// Session keys are shortened so the summary can be confirmed without
+    /// printing them in full.
+    pub(super) fn wizard_rows(updates: &[SkfUpdate]) -> Vec<Vec<String>> {
+        let header = ["devaddr", "session_key", "max_copies"];
+        let mut rows = vec![header.map(String::from).to_vec()];
+        rows.extend(updates.iter().map(|update| {
+            let key = &update.session_key;
+            vec![
+                update.devaddr.to_string(),
+                format!("{}..{}", &key[..4], &key[key.len() - 4..]),
+                update
+                    .max_copies
+                    .unwrap_or(DEFAULT_SKF_MAX_COPIES)
+                    .to_string(),
+            ]
+        }));
+        rows
+    }
+
+    fn filter_updates(filters: &[Skf], action: UpdateAction) -> Vec<SkfUpdate> {
+        filters
+            .iter()
+            .map(|filter| SkfUpdate {
+                devaddr: filter.devaddr,
+                session_key: filter.session_key.clone(),
+                action,
+                max_copies: filter.max_copies,
+            })
+            .collect()
+    }
+
+    /// How a Route's filters differ from an LNS export.
+    #[derive(Debug, Default, Serialize, PartialEq)]
+    pub struct SkfDrift {
+        /// In the LNS export but not the Route
+        missing: Vec<LnsSkf>,
+        /// In the Route but not the LNS export
+        extra: Vec<Skf>,
+        max_copies: Vec<MaxCopiesDrift>,
+    }
+
+    #[derive(Debug, Serialize, PartialEq)]
+    struct MaxCopiesDrift {
+        devaddr: HexDevAddr,
+        session_key: String,
+        lns: u32,
+        route: u32,
+    }
+
+    impl SkfDrift {
+        /// Filters match by devaddr and case insensitive session key.
+        pub fn new(lns: Vec<LnsSkf>, route: Vec<Skf>) -> Self {
+            let key =
+                |devaddr: HexDevAddr, session_key: &str| (devaddr.0, session_key.to_lowercase());
+            let mut route: BTreeMap<_, Skf> = route
+                .into_iter()
+                .map(|filter| (key(filter.devaddr, &filter.session_key), filter))
+                .collect();
+            let mut drift = Self::default();
+            for expected in lns {
+                let Some(filter) = route.remove(&key(expected.devaddr, &expected.session_key))
+                else {
+                    drift.missing.push(expected);
+                    continue;
+                };
+                match expected.max_copies {
+                    Some(lns) if lns != filter.effective_max_copies() => {
+                        drift.max_copies.push(MaxCopiesDrift {
+                            devaddr: filter.devaddr,
+                            route: filter.effective_max_copies(),
+                            session_key: filter.session_key,
+                            lns,
+                        })
+                    }
+                    _ => {}
+                }
+            }
+            drift.extra = route.into_values().collect();
+            drift
+        }
+
+        fn is_empty(&self) -> bool {
+            self.missing.is_empty() && self.extra.is_empty() && self.max_copies.is_empty()
+        }
+    }
+
+    pub async fn verify_filters(args: VerifyFilters) -> Result<Msg> {
+        let data = read_input(&args.against_lns).context("reading lns export")?;
+        let lns: Vec<LnsSkf> = match UpdateFileFormat::resolve(args.format, &args.against_lns) {
+            UpdateFileFormat::Json => schema::records_from_str(&data, "lns export", args.strict),
+            UpdateFileFormat::Csv => LnsSkf::parse_csv(&data),
+        }
+        .context(format!("parsing lns export {}", args.against_lns.display()))?;
+        let lns_count = lns.len();
+
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+        let route = client
+            .list_filters(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+        let route_count = route.len();
+
+        let drift = SkfDrift::new(lns, route);
+        if drift.is_empty() {
+            return Msg::ok(format!(
+                "{route_count} filters in {} match the {lns_count} in the lns export",
+                args.route_id
+            ));
+        }
+        Msg::err(format!(
+            "{} missing, {} extra, {} with different max_copies\n{}",
+            drift.missing.len(),
+            drift.extra.len(),
+            drift.max_copies.len(),
+            drift.pretty_json()?
+        ))
+    }
+
+    pub async fn update_filters_from_file(args: UpdateFilters) -> Result<Msg> {
+        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+
+        let data =
+            read_input(&args.update_file).context("reading session key filter updates file")?;
+        let updates: Vec<SkfUpdate> =
+            match UpdateFileFormat::resolve(args.format, &args.update_file) {
+                UpdateFileFormat::Json => {
+                    schema::records_from_str(&data, "session key filter update", args.strict)
+                }
+                UpdateFileFormat::Csv => SkfUpdate::parse_csv(&data),
+            }
+            .context(format!(
+                "parsing session key filter update file {}",
+                &args.update_file.display()
+            ))?;
+        let normalized = normalize(updates)?;
+        let mut report = normalized.report()?;
+        let mut updates = normalized.updates;
+
+        let update_count = updates.len();
+        if update_count > 100 {
+            return Msg::err("exceeds max 100 update limit per request".to_string());
+        }
+        let keypair = args.keypair.to_keypair()?;
+        report.extend(
+            fill_route_max_copies(
+                &args.config_host,
+                &args.config_pubkey,
+                &args.route_id,
+                &mut updates,
+                &keypair,
+            )
+            .await?,
+        );
+        warn_max_copies(&updates)?;
+
+        if !args.commit {
+            return Msg::dry_run(
+                [
+                    report,
+                    vec![format!("updated filters applied {update_count}")],
+                ]
+                .concat()
+                .join("\n"),
+            );
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &keypair)
+            .await?;
+
+        client
+            .update_filters(&args.route_id, updates, &keypair)
+            .await?;
+
+        Msg::ok(
+            [report, vec!["updated filters".to_string()]]
+                .concat()
+                .join("\n"),
+        )
+    }
+}
+
+pub mod euis {
+    use super::{copy_members, count_stream, Preflight};
+    use crate::{
+        check_wildcard_app_euis, client,
+        cmds::{
+            already_applied, write_records, AddEui, BulkInput, ClearEuis, CopyRouteMembers,
+            ListEuis, PathBufKeypair, RemoveEui, UpdateEuis, BULK_CHUNK_SIZE,
+        },
+        eui_wildcard_warning, hex_field,
+        retry::{Chunker, RateLimiter},
+        warning, Eui, EuiUpdate, Msg, PrettyJson, Result, UpdateAction,
+    };
+    use futures::{Stream, TryStreamExt};
+    use helium_crypto::Keypair;
+    use serde::Serialize;
+    use std::{
+        collections::{BTreeMap, HashSet},
+        time::Instant,
+    };
+    use tracing::Instrument;
+
+    pub async fn list_euis(args: ListEuis) -> Result<Msg> {
+        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let (app_eui, dev_eui, wildcards_only) = (args.app_eui, args.dev_eui, args.wildcards_only);
+        let euis_for_route = client
+            .stream_euis(&args.route_id, &keypair)
+            .await?
+            .try_filter(move |eui| {
+                let keep = app_eui.map_or(true, |app_eui| eui.app_eui == app_eui)
+                    && dev_eui.map_or(true, |dev_eui| eui.dev_eui == dev_eui)
+                    && (!wildcards_only || eui.is_wildcard());
+                futures::future::ready(keep)
+            });
+
+        if args.count {
+            return Msg::ok(count_euis(euis_for_route).await?.pretty_json()?);
+        }
+        write_records(euis_for_route, args.out_format, args.out_file.as_deref()).await
+    }
+
+    #[derive(Debug, Default, PartialEq, Eq, Serialize)]
+    pub struct EuiCounts {
+        pub total: usize,
+        pub by_app_eui: BTreeMap<String, usize>,
+    }
+
+    /// Tally pairs as they stream in, without keeping them.
+    pub async fn count_euis(euis: impl Stream<Item = Result<Eui>>) -> Result<EuiCounts> {
+        euis.try_fold(EuiCounts::default(), |mut counts, eui| {
+            counts.total += 1;
+            *counts
+                .by_app_eui
+                .entry(eui.app_eui.to_string())
+                .or_default() += 1;
+            futures::future::ready(Ok(counts))
+        })
+        .await
+    }
+
+    pub async fn add_eui(args: AddEui) -> Result<Msg> {
+        let app_eui = match args.app_eui {
+            Some(app_eui) if app_eui.0 == 0 => {
+                return Msg::err(format!(
+                    "app_eui {app_eui} is the wildcard, pass --wildcard-app-eui instead if that is intended"
+                ))
+            }
+            Some(app_eui) => app_eui,
+            None => hex_field::eui(0),
+        };
+        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+        let eui_pair = Eui::new(args.route_id.clone(), app_eui, args.dev_eui)?;
+        if let Some(wildcard) = eui_wildcard_warning(app_eui, args.dev_eui) {
+            warning::warn(wildcard)?;
+        }
+
+        if args.idempotent && has_eui(&mut client, &eui_pair, &args.keypair.to_keypair()?).await? {
+            return already_applied(format!("{eui_pair:?} already in {}", args.route_id));
+        }
+
+        if !args.commit {
+            return Msg::dry_run(format!("added {eui_pair:?} to {}", args.route_id));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .add_euis(vec![eui_pair.clone()], &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(format!("added {eui_pair:?} to {}", args.route_id))
+    }
+
+    pub async fn remove_eui(args: RemoveEui) -> Result<Msg> {
+        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+        let eui_pair = Eui::new(args.route_id.clone(), args.app_eui, args.dev_eui)?;
+
+        if args.idempotent && !has_eui(&mut client, &eui_pair, &args.keypair.to_keypair()?).await? {
+            return already_applied(format!("{eui_pair:?} not in {}", args.route_id));
+        }
+
+        if !args.commit {
+            return Msg::dry_run(format!("removed {eui_pair:?} from {}", args.route_id));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .remove_euis(vec![eui_pair.clone()], &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(format!("removed {eui_pair:?} from {}", args.route_id))
+    }
+
+    async fn has_eui(
+        client: &mut client::EuiClient,
+        eui_pair: &Eui,
+        keypair: &Keypair,
+    ) -> Result<bool> {
+        let euis = client.get_euis(&eui_pair.route_id, keypair).await?;
+        Ok(euis.contains(eui_pair))
+    }
+
+    pub async fn clear_euis(args: ClearEuis) -> Result<Msg> {
+        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+
+        if !args.commit {
+            return Msg::dry_run(format!("All Euis removed from {}", args.route_id));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .delete_euis(args.route_id.clone(), &args.keypair.to_keypair()?)
+            .await?;
+        Msg::ok(format!("All Euis removed from {}", args.route_id))
+    }
+
+    /// With `--idempotent` the Route's EUIs are fetched first, so re-running
+    /// an onboarding file only sends the pairs that change the Route.
+    pub async fn update_euis_from_file(args: UpdateEuis) -> Result<Msg> {
+        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let mut input = BulkInput::<EuiUpdate>::read(
+            args.update_file.as_deref(),
+            args.resume.as_deref(),
+            args.strict,
+        )?;
+        check_wildcard_app_euis(
+            input
+                .updates
+                .iter()
+                .filter(|update| update.action == UpdateAction::Add)
+                .map(|update| (update.app_eui, update.dev_eui)),
+            args.wildcard_app_eui,
+        )?;
+        if args.idempotent {
+            let existing = client.get_euis(&args.route_id, &keypair).await?;
+            let skipped = skip_applied(&mut input.updates, &existing);
+            input.report.push(skipped);
+        }
+        let update_count = input.updates.len();
+
+        if !args.commit {
+            return Msg::dry_run(input.summarize(format!("updated euis applied {update_count}")));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &keypair)
+            .await?;
+
+        let limiter = RateLimiter::default();
+        let mut chunks = Chunker::new(&input.updates, BULK_CHUNK_SIZE);
+        while let Some(chunk) = chunks.next_chunk() {
+            let started = Instant::now();
+            let result = limiter
+                .retry(|| {
+                    let mut client = client.clone();
+                    let (route_id, keypair) = (&args.route_id, &keypair);
+                    async move { client.update_euis(route_id, chunk, keypair).await }
+                })
+                .instrument(chunks.span())
+                .await;
+            if let Err(err) = chunks.record(result, started.elapsed()) {
+                return input.fail(chunks.done(), err);
+            }
+        }
+        input.finish()?;
+
+        Msg::ok(input.summarize(format!("updated euis applied {update_count}")))
+    }
+
+    /// Drop adds of pairs in `existing` and removes of pairs missing from it,
+    /// returning how many of each were dropped.
+    pub(super) fn skip_applied(updates: &mut Vec<EuiUpdate>, existing: &[Eui]) -> String {
+        let existing: HashSet<_> = existing
+            .iter()
+            .map(|eui| (eui.app_eui, eui.dev_eui))
+            .collect();
+        let total = |updates: &[EuiUpdate], action| {
+            updates
+                .iter()
+                .filter(|update| update.action == action)
+                .count()
+        };
+        let (adds, removes) = (
+            total(updates, UpdateAction::Add),
+            total(updates, UpdateAction::Remove),
+        );
+        updates.retain(|update| {
+            let present = existing.contains(&(update.app_eui, update.dev_eui));
+            match update.action {
+                UpdateAction::Add => !present,
+                UpdateAction::Remove => present,
+            }
+        });
+        let added = total(updates, UpdateAction::Add);
+        let mut summary = format!("{added} added, {} already present", adds - added);
+        if removes > 0 {
+            let removed = total(updates, UpdateAction::Remove);
+            summary.push_str(&format!(
+                ", {removed} removed, {} already absent",
+                removes - removed
+            ));
+        }
+        summary
+    }
+
+    /// The source is read twice, once to count it and once to copy it, so
+    /// neither pass holds the whole Route.
+    pub async fn copy_euis(args: CopyRouteMembers) -> Result<Msg> {
+        if args.from == args.to {
+            return Msg::err("--from and --to are the same route".to_string());
+        }
+        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let total = count_stream(client.stream_euis(&args.from, &keypair).await?).await?;
+
+        if !args.commit {
+            return Msg::dry_run(format!(
+                "copied {total} euis from {} to {}",
+                args.from, args.to
+            ));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.to, &keypair)
+            .await?;
+
+        let source = client
+            .stream_euis(&args.from, &keypair)
+            .await?
+            .map_ok(|eui| Eui {
+                route_id: args.to.clone(),
+                ..eui
+            });
+        let copied = copy_members(source, total, "copy euis", client.progress(), |chunk| {
+            let mut client = client.clone();
+            let keypair = &keypair;
+            async move { client.add_euis(chunk, keypair).await }
+        })
+        .await?;
+
+        Msg::ok(format!(
+            "copied {copied} euis from {} to {}",
+            args.from, args.to
+        ))
+    }
+}
+
+pub mod devaddrs {
+    use super::{copy_members, count_stream, Preflight};
+    use crate::{
+        client,
+        cmds::{
+            already_applied, write_records, AddDevaddr, BulkInput, ClearDevaddrs, CopyRouteMembers,
+            DevaddrExportFormat, ExportDevaddrs, ListDevaddrs, PathBufKeypair, RemoveDevaddr,
+            ReplaceDevaddr, RouteSubnetMask, UpdateDevaddrs, BULK_CHUNK_SIZE,
+        },
+        retry::{Chunker, RateLimiter},
+        schema::Versioned,
+        subnet::DevaddrSubnet,
+        DevaddrConstraint, DevaddrRange, DevaddrUpdate, Msg, PrettyJson, Result, UpdateAction,
+    };
+    use anyhow::{anyhow, Context};
+    use futures::TryStreamExt;
+    use helium_crypto::Keypair;
+    use serde_json::json;
+    use std::time::Instant;
+    use tracing::Instrument;
+
+    pub async fn list_devaddrs(args: ListDevaddrs) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let devaddrs_for_route = client.stream_devaddrs(&args.route_id, &keypair).await?;
+
+        if args.count {
+            let (ranges, devaddrs) = devaddrs_for_route
+                .try_fold((0, 0), |(ranges, devaddrs), range| {
+                    let size = range.end_addr.0 - range.start_addr.0 + 1;
+                    futures::future::ready(Ok((ranges + 1, devaddrs + size)))
+                })
+                .await?;
+            return Msg::ok(json!({ "ranges": ranges, "devaddrs": devaddrs }).pretty_json()?);
+        }
+        write_records(
+            devaddrs_for_route,
+            args.out_format,
+            args.out_file.as_deref(),
+        )
+        .await
+    }
+
+    pub async fn add_devaddr(args: AddDevaddr) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let end_addr = match (args.end_addr, args.count) {
+            (Some(end_addr), _) => end_addr,
+            (None, Some(count)) => args.start_addr.checked_range(count)?.end_addr,
+            (None, None) => return Err(anyhow!("one of --end-addr or --count is required")),
+        };
+        let devaddr_range = DevaddrRange::new(args.route_id.clone(), args.start_addr, end_addr)?;
+
+        if args.idempotent
+            && has_devaddr(&mut client, &devaddr_range, &args.keypair.to_keypair()?).await?
+        {
+            return already_applied(format!("{devaddr_range:?} already in {}", args.route_id));
+        }
+
+        if !args.commit {
+            let subnets = devaddr_range.clone().to_subnet().subnets;
+            return Msg::dry_run(format!(
+                "added {devaddr_range:?}\nsubnets: {}",
+                subnets.join(", ")
+            ));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .add_devaddrs(vec![devaddr_range.clone()], &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(format!("added {devaddr_range:?}"))
+    }
+
+    pub async fn remove_devaddr(args: RemoveDevaddr) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let devaddr_range =
+            DevaddrRange::new(args.route_id.clone(), args.start_addr, args.end_addr)?;
+
+        if args.idempotent
+            && !has_devaddr(&mut client, &devaddr_range, &args.keypair.to_keypair()?).await?
+        {
+            return already_applied(format!("{devaddr_range:?} not in {}", args.route_id));
+        }
+
+        if !args.commit {
+            return Msg::dry_run(format!("removed {devaddr_range:?} from {}", args.route_id));
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
+        client
+            .remove_devaddrs(vec![devaddr_range.clone()], &args.keypair.to_keypair()?)
+            .await?;
+
+        Msg::ok(format!("removed {devaddr_range:?} from {}", args.route_id))
+    }
+
+    /// The add is sent ahead of the remove in the same stream, so devaddrs
+    /// in both ranges stay routed throughout.
+    pub async fn replace_devaddr(args: ReplaceDevaddr) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let removed = DevaddrRange::new(args.route_id.clone(), args.remove_start, args.remove_end)?;
+        let added = DevaddrRange::new(args.route_id.clone(), args.add_start, args.add_end)?;
+        if removed == added {
+            return Msg::err("the ranges to remove and add are the same".to_string());
+        }
+
+        let ranges = client.get_devaddrs(&args.route_id, &keypair).await?;
+        if !ranges.contains(&removed) {
+            return Msg::err(format!("{removed:?} not in {}", args.route_id));
+        }
+        if ranges.contains(&added) {
+            return Msg::err(format!("{added:?} already in {}", args.route_id));
+        }
+
+        let summary = format!(
+            "replaced {removed:?} with {added:?}\nsubnets: {}",
+            added.clone().to_subnet().subnets.join(", ")
+        );
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &keypair)
+            .await?;
+
+        let updates = replacement_updates(&removed, &added);
+        client
+            .update_devaddrs(&args.route_id, &updates, &keypair)
+            .await?;
+
+        Msg::ok(summary)
+    }
+
+    fn replacement_updates(removed: &DevaddrRange, added: &DevaddrRange) -> [DevaddrUpdate; 2] {
+        [
+            DevaddrUpdate {
+                start_addr: added.start_addr,
+                end_addr: added.end_addr,
+                action: UpdateAction::Add,
+            },
+            DevaddrUpdate {
+                start_addr: removed.start_addr,
+                end_addr: removed.end_addr,
+                action: UpdateAction::Remove,
+            },
+        ]
     }
 
-    pub async fn get_filters(args: GetFilters) -> Result<Msg> {
-        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
-        let filters = client
-            .get_filters(&args.route_id, args.devaddr, &args.keypair.to_keypair()?)
+    async fn has_devaddr(
+        client: &mut client::DevaddrClient,
+        devaddr_range: &DevaddrRange,
+        keypair: &Keypair,
+    ) -> Result<bool> {
+        let ranges = client
+            .get_devaddrs(&devaddr_range.route_id, keypair)
             .await?;
-
-        Msg::ok(filters.pretty_json()?)
+        Ok(ranges.contains(devaddr_range))
     }
 
-    pub async fn add_filter(args: AddFilter) -> Result<Msg> {
-        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
-        let filter = Skf::new(
-            args.route_id.clone(),
-            args.devaddr,
-            args.session_key,
-            args.max_copies,
-        )?;
+    pub async fn clear_devaddrs(args: ClearDevaddrs) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
 
         if !args.commit {
-            return Msg::dry_run(format!("added {filter:?}"));
+            return Msg::dry_run(format!("All Devadddrs removed from {}", args.route_id));
         }
 
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
+            .await?;
+
         client
-            .add_filter(filter.clone(), &args.keypair.to_keypair()?)
+            .delete_devaddrs(args.route_id.clone(), &args.keypair.to_keypair()?)
             .await?;
 
-        Msg::ok(format!("added {filter:?}"))
+        Msg::ok(format!("All Devaddrs removed from {}", args.route_id))
     }
 
-    pub async fn remove_filter(args: RemoveFilter) -> Result<Msg> {
-        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
-        let filter = Skf::new(args.route_id.clone(), args.devaddr, args.session_key, None)?;
+    pub async fn update_devaddrs_from_file(args: UpdateDevaddrs) -> Result<Msg> {
+        let client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let input = BulkInput::<DevaddrUpdate>::read(
+            args.update_file.as_deref(),
+            args.resume.as_deref(),
+            args.strict,
+        )?;
+        let update_count = input.updates.len();
 
         if !args.commit {
-            return Msg::dry_run(format!("removed {filter:?}"));
+            return Msg::dry_run(
+                input.summarize(format!("updated devaddrs applied {update_count}")),
+            );
         }
 
-        client
-            .remove_filter(filter.clone(), &args.keypair.to_keypair()?)
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.route_id, &args.keypair.to_keypair()?)
             .await?;
 
-        Msg::ok(format!("removed {filter:?}"))
+        let keypair = args.keypair.to_keypair()?;
+        let limiter = RateLimiter::default();
+        let mut chunks = Chunker::new(&input.updates, BULK_CHUNK_SIZE);
+        while let Some(chunk) = chunks.next_chunk() {
+            let started = Instant::now();
+            let result = limiter
+                .retry(|| {
+                    let mut client = client.clone();
+                    let (route_id, keypair) = (&args.route_id, &keypair);
+                    async move { client.update_devaddrs(route_id, chunk, keypair).await }
+                })
+                .instrument(chunks.span())
+                .await;
+            if let Err(err) = chunks.record(result, started.elapsed()) {
+                return input.fail(chunks.done(), err);
+            }
+        }
+        input.finish()?;
+
+        Msg::ok(input.summarize(format!("updated devaddrs applied {update_count}")))
     }
 
-    pub async fn clear_filters(args: ClearFilters) -> Result<Msg> {
-        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
+    /// Streamed like [`super::euis::copy_euis`].
+    pub async fn copy_devaddrs(args: CopyRouteMembers) -> Result<Msg> {
+        if args.from == args.to {
+            return Msg::err("--from and --to are the same route".to_string());
+        }
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let total = count_stream(client.stream_devaddrs(&args.from, &keypair).await?).await?;
 
         if !args.commit {
             return Msg::dry_run(format!(
-                "All Session Key Filters removed from {}",
-                args.route_id
+                "copied {total} devaddr ranges from {} to {}",
+                args.from, args.to
             ));
         }
 
-        client
-            .delete_filters(args.route_id.clone(), &args.keypair.to_keypair()?)
+        Preflight::new(&args.config_host, &args.config_pubkey, args.no_preflight)
+            .check_route(&args.to, &keypair)
             .await?;
+
+        let source = client
+            .stream_devaddrs(&args.from, &keypair)
+            .await?
+            .map_ok(|range| DevaddrRange {
+                route_id: args.to.clone(),
+                ..range
+            });
+        let copied = copy_members(
+            source,
+            total,
+            "copy devaddr ranges",
+            client.progress(),
+            |chunk| {
+                let mut client = client.clone();
+                let keypair = &keypair;
+                async move { client.add_devaddrs(chunk, keypair).await }
+            },
+        )
+        .await?;
+
         Msg::ok(format!(
-            "All Session Key Filters removed from {}",
-            args.route_id
+            "copied {copied} devaddr ranges from {} to {}",
+            args.from, args.to
         ))
     }
 
-    pub async fn update_filters_from_file(args: UpdateFilters) -> Result<Msg> {
-        let mut client = client::SkfClient::new(&args.config_host, &args.config_pubkey).await?;
-
-        let data = std::fs::read_to_string(&args.update_file)
-            .context("reading session key filter updates json file")?;
-        let updates: Vec<SkfUpdate> = serde_json::from_str(&data).context(format!(
-            "parsing session key filter update file {}",
-            &args.update_file.display()
-        ))?;
+    pub async fn subnet_mask(args: RouteSubnetMask) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let devaddrs_for_route: Vec<DevaddrSubnet> = client
+            .get_devaddrs(&args.route_id, &args.keypair.to_keypair()?)
+            .await?
+            .into_iter()
+            .map(|range| range.to_subnet())
+            .collect();
+        Msg::ok(devaddrs_for_route.pretty_json()?)
+    }
 
-        let update_count = updates.len();
-        if update_count > 100 {
-            return Msg::err("exceeds max 100 update limit per request".to_string());
+    pub async fn export_devaddrs(args: ExportDevaddrs) -> Result<Msg> {
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+        let mut ranges: Vec<DevaddrConstraint> = client
+            .get_devaddrs(&args.route_id, &args.keypair.to_keypair()?)
+            .await?
+            .into_iter()
+            .map(DevaddrConstraint::from)
+            .collect();
+        // sorted like route files, so exports of the same Route are identical
+        ranges.sort_by_key(|range| (range.start_addr.0, range.end_addr.0));
+        let export = export_ranges(&ranges, args.format)?;
+        match args.out_file {
+            Some(path) => {
+                std::fs::write(&path, export).context("writing out file")?;
+                Msg::ok(format!(
+                    "wrote {} devaddr ranges from {} to {}",
+                    ranges.len(),
+                    args.route_id,
+                    path.display()
+                ))
+            }
+            None => Msg::ok(export),
         }
+    }
 
-        if !args.commit {
-            return Msg::dry_run(format!("updated filters applied {update_count}"));
+    /// `ranges` in `format`. Erlang devaddrs are inclusive `{Start, End}`
+    /// ranges, each commented with its subnets.
+    pub(super) fn export_ranges(
+        ranges: &[DevaddrConstraint],
+        format: DevaddrExportFormat,
+    ) -> Result<String> {
+        let subnets: Vec<DevaddrSubnet> = ranges
+            .iter()
+            .cloned()
+            .map(DevaddrConstraint::to_subnet)
+            .collect();
+        match format {
+            DevaddrExportFormat::Json => subnets
+                .into_iter()
+                .map(Versioned::new)
+                .collect::<Vec<_>>()
+                .pretty_json(),
+            DevaddrExportFormat::Cidr => Ok(subnets
+                .iter()
+                .flat_map(|subnet| &subnet.subnets)
+                .map(|cidr| format!("{cidr}\n"))
+                .collect()),
+            DevaddrExportFormat::Erlang => {
+                let terms: Vec<String> = ranges
+                    .iter()
+                    .zip(&subnets)
+                    .map(|(range, subnet)| {
+                        format!(
+                            "    %% {}\n    {{16#{}, 16#{}}}",
+                            subnet.subnets.join(" "),
+                            range.start_addr,
+                            range.end_addr
+                        )
+                    })
+                    .collect();
+                Ok(format!("{{devaddrs, [\n{}\n]}}.\n", terms.join(",\n")))
+            }
         }
-
-        client
-            .update_filters(&args.route_id, updates, &args.keypair.to_keypair()?)
-            .await?;
-
-        Msg::ok("updated filters".to_string())
     }
 }
 
-pub mod euis {
+pub mod file {
+    use super::describe_protocol_change;
     use crate::{
-        client,
-        cmds::{AddEui, ClearEuis, ListEuis, PathBufKeypair, RemoveEui},
-        Eui, Msg, PrettyJson, Result,
+        check_wildcard_app_euis,
+        cmds::{
+            already_applied, auth_header, FmtRouteFile, RouteFileAddGwmpRegion, RouteFileDevaddr,
+            RouteFileEui, RouteFileHttp, RouteFileMaxCopies, RouteFileNew,
+            RouteFileRemoveGwmpRegion, RouteFileServer, RouteFileShow,
+        },
+        eui_wildcard_warning, max_copies_warning,
+        route::{Route, RouteFile},
+        server::Protocol,
+        warning, Msg, PrettyJson, Result,
     };
+    use anyhow::{anyhow, Context};
+    use std::path::Path;
 
-    pub async fn list_euis(args: ListEuis) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
-        let mut euis_for_route = client
-            .get_euis(&args.route_id, &args.keypair.to_keypair()?)
-            .await?;
-
-        if let Some(app_eui) = args.app_eui {
-            euis_for_route.retain(|eui| eui.app_eui == app_eui);
+    pub fn new_route_file(args: RouteFileNew) -> Result<Msg> {
+        if args.route_file.exists() && !args.commit {
+            return Msg::err(format!(
+                "{} already exists, pass `--commit` to overwrite",
+                args.route_file.display()
+            ));
         }
-        if let Some(dev_eui) = args.dev_eui {
-            euis_for_route.retain(|eui| eui.dev_eui == dev_eui);
+        if let Some(too_many) = max_copies_warning(args.max_copies) {
+            warning::warn(too_many)?;
         }
+        let file = RouteFile::new(Route::new(args.net_id, args.oui, args.max_copies));
+        file.write(&args.route_file)?;
+        Msg::ok(format!(
+            "Route file written to {}\n{}",
+            args.route_file.display(),
+            file.pretty_json()?
+        ))
+    }
 
-        Msg::ok(euis_for_route.pretty_json()?)
+    pub fn fmt(args: FmtRouteFile) -> Result<Msg> {
+        let path = &args.route_file;
+        let current = std::fs::read_to_string(path)
+            .context(format!("reading route file {}", path.display()))?;
+        // always strict, unlike the global flag: a lenient parse would drop
+        // unknown fields from the rewritten file
+        let canonical = RouteFile::read(path, true)?.canonical_json()?;
+        if current == canonical {
+            return Msg::ok(format!("{} is already formatted", path.display()));
+        }
+        // an error, not a message, so the exit status fails a CI check
+        if args.check {
+            return Err(anyhow!(
+                "{} is not formatted, run `route fmt --route-file {}`",
+                path.display(),
+                path.display()
+            ));
+        }
+        std::fs::write(path, canonical)
+            .context(format!("writing route file {}", path.display()))?;
+        Msg::ok(format!("formatted {}", path.display()))
     }
 
-    pub async fn add_eui(args: AddEui) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
-        let eui_pair = Eui::new(args.route_id.clone(), args.app_eui, args.dev_eui)?;
+    pub fn show(args: RouteFileShow) -> Result<Msg> {
+        Msg::ok(RouteFile::read(&args.route_file, args.strict)?.pretty_json()?)
+    }
 
-        if !args.commit {
-            return Msg::dry_run(format!("added {eui_pair:?} to {}", args.route_id));
+    pub fn set_max_copies(args: RouteFileMaxCopies) -> Result<Msg> {
+        if let Some(too_many) = max_copies_warning(args.max_copies) {
+            warning::warn(too_many)?;
         }
+        edit_route(&args.route_file, args.strict, |route| {
+            route.max_copies = args.max_copies;
+            Ok(())
+        })
+    }
 
-        client
-            .add_euis(vec![eui_pair.clone()], &args.keypair.to_keypair()?)
-            .await?;
+    pub fn set_server(args: RouteFileServer) -> Result<Msg> {
+        edit_route(&args.route_file, args.strict, |route| {
+            route.server.host = args.host;
+            route.server.port = args.port;
+            Ok(())
+        })
+    }
 
-        Msg::ok(format!("added {eui_pair:?} to {}", args.route_id))
+    pub fn set_http(args: RouteFileHttp) -> Result<Msg> {
+        let auth_header = auth_header(args.auth_header, args.auth_header_file.as_deref())?;
+        edit_route(&args.route_file, args.strict, |route| {
+            route.server.protocol = Some(Protocol::make_http(
+                args.dedupe_timeout,
+                args.path,
+                auth_header,
+                args.receiver_nsid,
+            ));
+            Ok(())
+        })
     }
 
-    pub async fn remove_eui(args: RemoveEui) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
-        let eui_pair = Eui::new(args.route_id.clone(), args.app_eui, args.dev_eui)?;
+    pub fn set_packet_router(args: RouteFileShow) -> Result<Msg> {
+        edit_route(&args.route_file, args.strict, |route| {
+            route.server.protocol = Some(Protocol::default_packet_router());
+            Ok(())
+        })
+    }
 
-        if !args.commit {
-            return Msg::dry_run(format!("removed {eui_pair:?} from {}", args.route_id));
-        }
+    pub fn add_gwmp_region(args: RouteFileAddGwmpRegion) -> Result<Msg> {
+        edit_route(&args.route_file, args.strict, |route| {
+            let gwmp = match route.server.protocol.take() {
+                Some(mut protocol) if protocol.is_gwmp() => {
+                    let map = Protocol::make_gwmp_mapping(args.region, args.region_port);
+                    super::check_gwmp_conflicts(&protocol, &map, args.replace)?;
+                    protocol.gwmp_add_mapping(map)?;
+                    protocol
+                }
+                _ => Protocol::make_gwmp(args.region, args.region_port)?,
+            };
+            route.server.protocol = Some(gwmp);
+            Ok(())
+        })
+    }
 
-        client
-            .remove_euis(vec![eui_pair.clone()], &args.keypair.to_keypair()?)
-            .await?;
+    pub fn remove_gwmp_region(args: RouteFileRemoveGwmpRegion) -> Result<Msg> {
+        edit_route(&args.route_file, args.strict, |route| {
+            let protocol = route
+                .server
+                .protocol
+                .as_mut()
+                .ok_or_else(|| anyhow!("Cannot remove region mapping, no protocol"))?;
+            args.regions
+                .iter()
+                .try_for_each(|region| protocol.gwmp_remove_mapping(region))
+        })
+    }
 
-        Msg::ok(format!("removed {eui_pair:?} from {}", args.route_id))
+    pub fn add_eui(args: RouteFileEui) -> Result<Msg> {
+        check_wildcard_app_euis([(args.app_eui, args.dev_eui)], args.wildcard_app_eui)?;
+        let mut file = RouteFile::read(&args.route_file, args.strict)?;
+        if let Some(wildcard) = eui_wildcard_warning(args.app_eui, args.dev_eui) {
+            warning::warn(wildcard)?;
+        }
+        if !file.add_eui(args.app_eui, args.dev_eui) {
+            let state = format!(
+                "EUI pair app_eui {} dev_eui {} already in route file",
+                args.app_eui, args.dev_eui
+            );
+            if args.idempotent {
+                return already_applied(state);
+            }
+            return Msg::err(state);
+        }
+        file.write(&args.route_file)?;
+        Msg::ok(format!(
+            "added EUI pair app_eui {} dev_eui {}",
+            args.app_eui, args.dev_eui
+        ))
     }
 
-    pub async fn clear_euis(args: ClearEuis) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host, &args.config_pubkey).await?;
+    pub fn remove_eui(args: RouteFileEui) -> Result<Msg> {
+        let mut file = RouteFile::read(&args.route_file, args.strict)?;
+        if !file.remove_eui(args.app_eui, args.dev_eui) {
+            let state = format!(
+                "EUI pair app_eui {} dev_eui {} not in route file",
+                args.app_eui, args.dev_eui
+            );
+            if args.idempotent {
+                return already_applied(state);
+            }
+            return Msg::err(state);
+        }
+        file.write(&args.route_file)?;
+        Msg::ok(format!(
+            "removed EUI pair app_eui {} dev_eui {}",
+            args.app_eui, args.dev_eui
+        ))
+    }
 
-        if !args.commit {
-            return Msg::dry_run(format!("All Euis removed from {}", args.route_id));
+    pub fn add_devaddr(args: RouteFileDevaddr) -> Result<Msg> {
+        let mut file = RouteFile::read(&args.route_file, args.strict)?;
+        if !file.add_devaddr_range(args.start_addr, args.end_addr)? {
+            let state = format!(
+                "devaddr range {} - {} already in route file",
+                args.start_addr, args.end_addr
+            );
+            if args.idempotent {
+                return already_applied(state);
+            }
+            return Msg::err(state);
         }
+        file.write(&args.route_file)?;
+        Msg::ok(format!(
+            "added devaddr range {} - {}",
+            args.start_addr, args.end_addr
+        ))
+    }
 
-        client
-            .delete_euis(args.route_id.clone(), &args.keypair.to_keypair()?)
-            .await?;
-        Msg::ok(format!("All Euis removed from {}", args.route_id))
+    pub fn remove_devaddr(args: RouteFileDevaddr) -> Result<Msg> {
+        let mut file = RouteFile::read(&args.route_file, args.strict)?;
+        if !file.remove_devaddr_range(args.start_addr, args.end_addr) {
+            let state = format!(
+                "devaddr range {} - {} not in route file",
+                args.start_addr, args.end_addr
+            );
+            if args.idempotent {
+                return already_applied(state);
+            }
+            return Msg::err(state);
+        }
+        file.write(&args.route_file)?;
+        Msg::ok(format!(
+            "removed devaddr range {} - {}",
+            args.start_addr, args.end_addr
+        ))
+    }
+
+    /// Apply `update` to the Route in `path` and write it back, reporting the
+    /// difference. Protocol type changes are noted but never refused locally.
+    fn edit_route(
+        path: &Path,
+        strict: bool,
+        update: impl FnOnce(&mut Route) -> Result,
+    ) -> Result<Msg> {
+        let mut file = RouteFile::read(path, strict)?;
+        let old_route = file.route.clone();
+        update(&mut file.route)?;
+        file.write(path)?;
+
+        let diff = route_diff(&path.display().to_string(), &old_route, &file.route)?;
+        if let Some(change) = describe_protocol_change(&old_route, &file.route) {
+            warning::warn(change)?;
+        }
+        Msg::ok(diff)
     }
 }
 
-pub mod devaddrs {
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_signer, check_template_oui, copy_members, devaddrs,
+        devaddrs::export_ranges,
+        euis,
+        euis::{count_euis, skip_applied},
+        is_canary_event, push_with_diff, reactivate_command, reactivation_instructions,
+        route_changes, route_csv, shell_quote,
+        skfs::{validate_session_key, wizard_rows, SkfDrift},
+        summarize_route, templated_route, version_at, Preflight, RouteStats, StreamFilter,
+    };
     use crate::{
-        client,
+        api::RouteApi,
+        backup::Backups,
         cmds::{
-            AddDevaddr, ClearDevaddrs, ListDevaddrs, PathBufKeypair, RemoveDevaddr, RouteSubnetMask,
+            CopyRouteMembers, DeactivateRoute, DevaddrExportFormat, RouteColumn, RouteTarget,
+            RouteTemplate, BULK_CHUNK_SIZE,
         },
-        subnet::DevaddrSubnet,
-        DevaddrRange, Msg, PrettyJson, Result,
+        hex_field,
+        progress::NoProgress,
+        region::Region,
+        route::{RouteChange, RouteChangeItem, RouteFile},
+        schema::SCHEMA_VERSION,
+        server::{Http, Protocol, Server},
+        testing::{fixtures, FakeRouteApi, FakeStorage},
+        DevaddrConstraint, Eui, EuiUpdate, LnsSkf, Msg, Route, Skf, SkfUpdate, UpdateAction,
+    };
+    use anyhow::anyhow;
+    use futures::StreamExt;
+    use helium_proto::services::iot_config::{
+        route_stream_res_v1, ActionV1, RouteStreamResV1, SkfV1,
+    };
+    use std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::{Duration, SystemTime},
     };
+    use temp_dir::TempDir;
 
-    pub async fn list_devaddrs(args: ListDevaddrs) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
-        let devaddrs_for_route = client
-            .get_devaddrs(&args.route_id, &args.keypair.to_keypair()?)
-            .await?;
+    #[tokio::test]
+    async fn eui_counts() {
+        let pair = |app_eui, dev_eui| {
+            Ok(Eui::new(
+                fixtures::ROUTE_ID.to_string(),
+                hex_field::eui(app_eui),
+                hex_field::eui(dev_eui),
+            )
+            .unwrap())
+        };
+        let euis = futures::stream::iter([pair(1, 1), pair(1, 2), pair(2, 1)]);
+        let counts = count_euis(euis).await.unwrap();
+        assert_eq!(3, counts.total);
+        assert_eq!(
+            vec![("0000000000000001", 2), ("0000000000000002", 1)],
+            counts
+                .by_app_eui
+                .iter()
+                .map(|(app_eui, count)| (app_eui.as_str(), *count))
+                .collect::<Vec<_>>()
+        );
+    }
 
-        Msg::ok(devaddrs_for_route.pretty_json()?)
+    #[test]
+    fn route_templates() {
+        let route = |template, port, path: &str| {
+            templated_route(
+                template,
+                hex_field::net_id(0xC00053),
+                4,
+                3,
+                "lns.example.com".to_string(),
+                port,
+                path.to_string(),
+            )
+        };
+        let packet_router = route(RouteTemplate::PacketRouter, 8080, "/").unwrap();
+        assert_eq!(Some(Protocol::PacketRouter), packet_router.server.protocol);
+        assert_eq!((4, 3), (packet_router.oui, packet_router.max_copies));
+
+        let http = route(RouteTemplate::HttpRoaming, 443, "/uplink").unwrap();
+        assert_eq!(
+            Some(Protocol::make_http(250, "/uplink".to_string(), None, None)),
+            http.server.protocol
+        );
+        assert!(route(RouteTemplate::HttpRoaming, 443, "uplink").is_err());
+        assert!(route(RouteTemplate::PacketRouter, 0, "/").is_err());
     }
 
-    pub async fn add_devaddr(args: AddDevaddr) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
-        let devaddr_range =
-            DevaddrRange::new(args.route_id.clone(), args.start_addr, args.end_addr)?;
+    #[tokio::test]
+    async fn updates_start_from_route_file() {
+        let dir = TempDir::new().unwrap();
+        let keypair = fixtures::keypair();
+        let mut api = FakeRouteApi::with_routes([fixtures::route(1)]);
+        let local = Route {
+            max_copies: 9,
+            ..fixtures::route(1)
+        };
+        let path = dir.path().join("route.json");
+        RouteFile::new(local.clone()).write(&path).unwrap();
 
-        if !args.commit {
-            return Msg::dry_run(format!("added {devaddr_range:?}"));
-        }
+        let target = |route_id: Option<&str>, route_file: Option<&Path>| RouteTarget {
+            route_id: route_id.map(str::to_string),
+            route_file: route_file.map(Path::to_path_buf),
+            strict: true,
+        };
 
-        client
-            .add_devaddrs(vec![devaddr_range.clone()], &args.keypair.to_keypair()?)
-            .await?;
+        let from_file = target(None, Some(&path))
+            .route(&mut api, &keypair)
+            .await
+            .unwrap();
+        assert_eq!(local, from_file);
+        let live = target(Some(fixtures::ROUTE_ID), None)
+            .route(&mut api, &keypair)
+            .await
+            .unwrap();
+        assert_eq!(fixtures::route(1), live);
 
-        Msg::ok(format!("added {devaddr_range:?}"))
+        let unsaved = dir.path().join("new.json");
+        RouteFile::new(Route::new(hex_field::net_id(0xC00053), 1, 5))
+            .write(&unsaved)
+            .unwrap();
+        assert!(target(None, Some(&unsaved))
+            .route(&mut api, &keypair)
+            .await
+            .is_err());
     }
 
-    pub async fn remove_devaddr(args: RemoveDevaddr) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
-        let devaddr_range =
-            DevaddrRange::new(args.route_id.clone(), args.start_addr, args.end_addr)?;
+    #[test]
+    fn canary_events() {
+        let event = |action: ActionV1, session_key: &str| RouteStreamResV1 {
+            action: action as i32,
+            data: Some(route_stream_res_v1::Data::Skf(SkfV1 {
+                route_id: fixtures::ROUTE_ID.to_string(),
+                devaddr: 0x4800_0000,
+                session_key: session_key.to_string(),
+                max_copies: 1,
+            })),
+            ..Default::default()
+        };
+        let is_canary = |res: &RouteStreamResV1, action| {
+            is_canary_event(res, fixtures::ROUTE_ID, "canary", action)
+        };
+        assert!(is_canary(
+            &event(ActionV1::Add, "canary"),
+            UpdateAction::Add
+        ));
+        assert!(!is_canary(
+            &event(ActionV1::Add, "canary"),
+            UpdateAction::Remove
+        ));
+        assert!(!is_canary(
+            &event(ActionV1::Add, "other"),
+            UpdateAction::Add
+        ));
+        assert!(is_canary(
+            &event(ActionV1::Remove, "canary"),
+            UpdateAction::Remove
+        ));
+        assert!(!is_canary(&RouteStreamResV1::default(), UpdateAction::Add));
+    }
 
-        if !args.commit {
-            return Msg::dry_run(format!("removed {devaddr_range:?} from {}", args.route_id));
-        }
+    #[test]
+    fn stream_filters() {
+        let change = |timestamp, item| RouteChange {
+            action: UpdateAction::Add,
+            timestamp,
+            item,
+        };
+        let skf = |route_id: &str| {
+            RouteChangeItem::Skf(Skf {
+                route_id: route_id.to_string(),
+                devaddr: hex_field::devaddr(0x4800_0000),
+                session_key: "key".to_string(),
+                max_copies: Some(1),
+            })
+        };
+        let new_route = Route {
+            id: "new-route".to_string(),
+            ..fixtures::route(1)
+        };
 
-        client
-            .remove_devaddrs(vec![devaddr_range.clone()], &args.keypair.to_keypair()?)
-            .await?;
+        let mut filter = StreamFilter {
+            route_id: None,
+            oui: Some((1, [fixtures::ROUTE_ID.to_string()].into())),
+            since: 100,
+        };
+        assert!(filter.matches(&change(100, skf(fixtures::ROUTE_ID))));
+        assert!(!filter.matches(&change(99, skf(fixtures::ROUTE_ID))));
+        assert!(!filter.matches(&change(100, skf("new-route"))));
+        // routes created for the OUI while streaming are followed
+        assert!(filter.matches(&change(100, RouteChangeItem::Route(new_route))));
+        assert!(filter.matches(&change(100, skf("new-route"))));
+        assert!(!filter.matches(&change(
+            100,
+            RouteChangeItem::Route(Route {
+                id: "other-route".to_string(),
+                ..fixtures::route(2)
+            })
+        )));
 
-        Msg::ok(format!("removed {devaddr_range:?} from {}", args.route_id))
+        let mut filter = StreamFilter {
+            route_id: Some("new-route".to_string()),
+            oui: None,
+            since: 0,
+        };
+        assert!(filter.matches(&change(0, skf("new-route"))));
+        assert!(!filter.matches(&change(0, skf(fixtures::ROUTE_ID))));
     }
 
-    pub async fn clear_devaddrs(args: ClearDevaddrs) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
+    #[test]
+    fn route_summaries() {
+        let stats = RouteStats {
+            devaddr_count: 1,
+            eui_count: 2,
+            skf_count: 0,
+        };
+        let mut route = fixtures::route(1);
+        route.server.host = "lns.example.com".to_string();
+        route.server.port = 1700;
+        route.server.protocol = Some(Protocol::make_gwmp(Region::Eu868, 1700).unwrap());
+        route
+            .gwmp_add_mapping(Protocol::make_gwmp_mapping(Region::Us915, 1701))
+            .unwrap();
+        route.active = false;
+        assert_eq!(
+            format!(
+                "route {} (oui 1, net_id C00053)\n\
+                 endpoint: lns.example.com:1700\n\
+                 protocol: gwmp, region ports US915=1701 EU868=1700\n\
+                 active: no, locked: no, max_copies: 5\n\
+                 members: 1 devaddr ranges, 2 euis, 0 session key filters",
+                fixtures::ROUTE_ID
+            ),
+            summarize_route(&route, &stats)
+        );
 
-        if !args.commit {
-            return Msg::dry_run(format!("All Devadddrs removed from {}", args.route_id));
-        }
+        route.server.protocol = Some(Protocol::make_http(250, "/uplink".to_string(), None, None));
+        assert!(summarize_route(&route, &stats)
+            .contains("protocol: http roaming, async flow, path /uplink, dedupe 250ms"));
+    }
 
-        client
-            .delete_devaddrs(args.route_id.clone(), &args.keypair.to_keypair()?)
-            .await?;
+    #[test]
+    fn reactivation_is_scheduled_from_now() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let instructions = reactivation_instructions(
+            fixtures::ROUTE_ID,
+            "helium-config-cli route activate",
+            Duration::from_secs(7230),
+            now,
+        );
+        assert!(instructions.contains("reactivated at 2023-11-15T00:13:50Z"));
+        assert!(instructions.contains("--on-active=7230s"));
+        // at only schedules to the minute, round up so the window isn't cut short
+        assert!(instructions.contains("at now + 121 minutes"));
+    }
 
-        Msg::ok(format!("All Devaddrs removed from {}", args.route_id))
+    #[test]
+    fn reactivate_command_runs_from_anywhere() {
+        let args = DeactivateRoute {
+            route_id: fixtures::ROUTE_ID.to_string(),
+            reactivate_after: Some(Duration::from_secs(60)),
+            stay: false,
+            keypair: PathBuf::from("my keys/keypair.bin"),
+            no_preflight: true,
+            force: false,
+            backup_dir: None,
+            backup_retention: 20,
+            config_host: "http://localhost:6080".to_string(),
+            config_pubkey: fixtures::keypair().public_key().to_string(),
+            commit: true,
+        };
+        let command = reactivate_command(Path::new("/usr/bin/helium-config-cli"), &args).unwrap();
+        let keypair = std::env::current_dir().unwrap().join("my keys/keypair.bin");
+        assert_eq!(
+            format!(
+                "/usr/bin/helium-config-cli route activate --route-id {} \
+                 --keypair '{}' --config-host http://localhost:6080 --config-pubkey {} \
+                 --no-preflight --commit",
+                fixtures::ROUTE_ID,
+                keypair.display(),
+                args.config_pubkey,
+            ),
+            command
+        );
+        assert_eq!(r"'it'\''s'", shell_quote("it's"));
     }
 
-    pub async fn subnet_mask(args: RouteSubnetMask) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host, &args.config_pubkey).await?;
-        let devaddrs_for_route: Vec<DevaddrSubnet> = client
-            .get_devaddrs(&args.route_id, &args.keypair.to_keypair()?)
-            .await?
+    #[tokio::test]
+    async fn push_with_diff_backs_up_on_commit() {
+        let no_preflight = Preflight::new("", "", true);
+        let dir = TempDir::new().unwrap();
+        let backups = Backups::new(Some(dir.path().to_path_buf()), 0).unwrap();
+        let keypair = fixtures::keypair();
+        let mut api = FakeRouteApi::with_routes([fixtures::route(1)]);
+        let old_route = fixtures::route(1);
+        let new_route = Route {
+            max_copies: 9,
+            ..old_route.clone()
+        };
+
+        let msg = push_with_diff(
+            &mut api,
+            old_route.clone(),
+            new_route.clone(),
+            false,
+            false,
+            &backups,
+            &keypair,
+            &no_preflight,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(msg, Msg::DryRun(_)));
+        assert_eq!(
+            old_route,
+            api.get(fixtures::ROUTE_ID, &keypair).await.unwrap()
+        );
+        assert!(backups.list(fixtures::ROUTE_ID).unwrap().is_empty());
+
+        let msg = push_with_diff(
+            &mut api,
+            old_route.clone(),
+            new_route.clone(),
+            true,
+            false,
+            &backups,
+            &keypair,
+            &no_preflight,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(msg, Msg::Success(_)));
+        assert_eq!(
+            new_route,
+            api.get(fixtures::ROUTE_ID, &keypair).await.unwrap()
+        );
+        assert_eq!(old_route, backups.read(fixtures::ROUTE_ID, None).unwrap());
+    }
+
+    #[tokio::test]
+    async fn push_with_diff_detects_conflicts() {
+        let no_preflight = Preflight::new("", "", true);
+        let dir = TempDir::new().unwrap();
+        let backups = Backups::new(Some(dir.path().to_path_buf()), 0).unwrap();
+        let keypair = fixtures::keypair();
+        let read_route = fixtures::route(1);
+        // changed by someone else after it was read
+        let live_route = Route {
+            active: false,
+            ..read_route.clone()
+        };
+        let mut api = FakeRouteApi::with_routes([live_route.clone()]);
+        let new_route = Route {
+            max_copies: 9,
+            ..read_route.clone()
+        };
+        let msg = push_with_diff(
+            &mut api,
+            read_route.clone(),
+            new_route.clone(),
+            true,
+            false,
+            &backups,
+            &keypair,
+            &no_preflight,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(msg, Msg::Error(_)));
+        assert_eq!(
+            live_route,
+            api.get(fixtures::ROUTE_ID, &keypair).await.unwrap()
+        );
+
+        let msg = push_with_diff(
+            &mut api,
+            read_route.clone(),
+            new_route.clone(),
+            true,
+            true,
+            &backups,
+            &keypair,
+            &no_preflight,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(msg, Msg::Success(_)));
+        assert_eq!(
+            new_route,
+            api.get(fixtures::ROUTE_ID, &keypair).await.unwrap()
+        );
+        assert_eq!(live_route, backups.read(fixtures::ROUTE_ID, None).unwrap());
+    }
+
+    #[tokio::test]
+    async fn push_with_diff_keeps_the_live_auth_header() {
+        let no_preflight = Preflight::new("", "", true);
+        let dir = TempDir::new().unwrap();
+        let backups = Backups::new(Some(dir.path().to_path_buf()), 0).unwrap();
+        let keypair = fixtures::keypair();
+        let mut live_route = fixtures::route(1);
+        live_route.server.protocol = Some(Protocol::make_http(
+            250,
+            "/uplink".to_string(),
+            Some("Bearer secret".to_string()),
+            None,
+        ));
+        let mut api = FakeRouteApi::with_routes([live_route.clone()]);
+        // as `route get` writes it to a route file
+        let old_route = live_route.redacted();
+        let new_route = Route {
+            max_copies: 9,
+            ..old_route.clone()
+        };
+
+        let msg = push_with_diff(
+            &mut api,
+            old_route,
+            new_route,
+            true,
+            false,
+            &backups,
+            &keypair,
+            &no_preflight,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(msg, Msg::Success(_)));
+        assert_eq!(
+            Route {
+                max_copies: 9,
+                ..live_route
+            },
+            api.get(fixtures::ROUTE_ID, &keypair).await.unwrap()
+        );
+    }
+
+    #[test]
+    fn fmt_check_fails_on_unformatted_files() {
+        use crate::cmds::FmtRouteFile;
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("route.json");
+        let file = RouteFile {
+            route: fixtures::route(1),
+            devaddr_ranges: vec![],
+            euis: vec![],
+        };
+        std::fs::write(&path, file.canonical_json().unwrap().trim_end()).unwrap();
+        let fmt = |check| {
+            super::file::fmt(FmtRouteFile {
+                route_file: path.clone(),
+                check,
+            })
+        };
+        assert!(fmt(true).is_err());
+        assert!(matches!(fmt(false).unwrap(), Msg::Success(_)));
+        assert!(matches!(fmt(true).unwrap(), Msg::Success(_)));
+    }
+
+    #[test]
+    fn template_oui_must_match() {
+        let path = std::path::Path::new("template.json");
+        let route = fixtures::route(1);
+        assert!(check_template_oui(path, &route, None).is_ok());
+        assert!(check_template_oui(path, &route, Some(1)).is_ok());
+        let err = check_template_oui(path, &route, Some(2)).unwrap_err();
+        assert_eq!(
+            "route file template.json is for OUI 1, not OUI 2",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn route_csv_with_counts() {
+        let routes = [fixtures::route(1)];
+        let columns = [RouteColumn::Oui, RouteColumn::MaxCopies];
+        assert_eq!("oui,max_copies\n1,5", route_csv(&routes, &columns, None));
+
+        let counts = [RouteStats {
+            devaddr_count: 2,
+            eui_count: 30,
+            skf_count: 400,
+        }];
+        assert_eq!(
+            "oui,max_copies,devaddr_count,eui_count,skf_count\n1,5,2,30,400",
+            route_csv(&routes, &columns, Some(&counts))
+        );
+    }
+
+    #[test]
+    fn route_versions() {
+        let names: Vec<String> = ["1000.json", "2000.json"]
             .into_iter()
-            .map(|range| range.to_subnet())
+            .map(String::from)
             .collect();
-        Msg::ok(devaddrs_for_route.pretty_json()?)
+        assert_eq!(Some(&names[0]), version_at(&names, 500).unwrap());
+        assert_eq!(Some(&names[1]), version_at(&names, 1000).unwrap());
+        assert_eq!(None, version_at(&names, 2500).unwrap());
+
+        let old = fixtures::route(1);
+        let new = Route {
+            max_copies: old.max_copies + 1,
+            ..old.clone()
+        };
+        let changes = route_changes(&old, &new).unwrap();
+        assert_eq!(1, changes.len());
+        assert_eq!("max_copies", changes[0].field);
+        assert_eq!(serde_json::json!(old.max_copies), changes[0].old);
+        assert!(route_changes(&old, &old).unwrap().is_empty());
+
+        let http = |auth_header: &str| Route {
+            server: Server {
+                protocol: Some(Protocol::Http(Http {
+                    auth_header: auth_header.to_string(),
+                    ..Http::default()
+                })),
+                ..old.server.clone()
+            },
+            ..old.clone()
+        };
+        assert!(route_changes(&http("old secret"), &http("new secret"))
+            .unwrap()
+            .is_empty());
+        let changes = route_changes(&old, &http("secret")).unwrap();
+        assert!(!serde_json::to_string(&changes).unwrap().contains("secret"));
+    }
+
+    #[test]
+    fn skips_applied_eui_updates() {
+        let update = |dev_eui, action| EuiUpdate {
+            app_eui: hex_field::eui(1),
+            dev_eui: hex_field::eui(dev_eui),
+            action,
+        };
+        let existing: Vec<Eui> = [1, 2]
+            .into_iter()
+            .map(|dev_eui| {
+                Eui::new(
+                    fixtures::ROUTE_ID.to_string(),
+                    hex_field::eui(1),
+                    hex_field::eui(dev_eui),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let mut updates = vec![
+            update(1, UpdateAction::Add),
+            update(3, UpdateAction::Add),
+            update(4, UpdateAction::Add),
+        ];
+        assert_eq!(
+            "2 added, 1 already present",
+            skip_applied(&mut updates, &existing)
+        );
+        assert_eq!(
+            vec![update(3, UpdateAction::Add), update(4, UpdateAction::Add)],
+            updates
+        );
+
+        let mut updates = vec![
+            update(2, UpdateAction::Remove),
+            update(5, UpdateAction::Remove),
+        ];
+        assert_eq!(
+            "0 added, 0 already present, 1 removed, 1 already absent",
+            skip_applied(&mut updates, &existing)
+        );
+        assert_eq!(vec![update(2, UpdateAction::Remove)], updates);
+    }
+
+    #[test]
+    fn devaddr_exports() {
+        let ranges = [
+            DevaddrConstraint::new(
+                hex_field::devaddr(0x4800_0000),
+                hex_field::devaddr(0x4800_03FF),
+            )
+            .unwrap(),
+            DevaddrConstraint::new(
+                hex_field::devaddr(0x4800_0400),
+                hex_field::devaddr(0x4800_0BFF),
+            )
+            .unwrap(),
+        ];
+        assert_eq!(
+            "48000000/22\n48000400/22\n48000800/22\n",
+            export_ranges(&ranges, DevaddrExportFormat::Cidr).unwrap()
+        );
+        assert_eq!(
+            "{devaddrs, [\n    \
+             %% 48000000/22\n    {16#48000000, 16#480003FF},\n    \
+             %% 48000400/22 48000800/22\n    {16#48000400, 16#48000BFF}\n\
+             ]}.\n",
+            export_ranges(&ranges, DevaddrExportFormat::Erlang).unwrap()
+        );
+        let json = export_ranges(&ranges, DevaddrExportFormat::Json).unwrap();
+        let subnets: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(2, subnets.len());
+        assert_eq!(SCHEMA_VERSION, subnets[0]["schema_version"]);
+    }
+
+    #[test]
+    fn wizard_entries() {
+        assert!(validate_session_key("00112233445566778899aabbccddeeff").is_ok());
+        assert!(validate_session_key("00112233445566778899AABBCCDDEEFF").is_ok());
+        assert!(validate_session_key("0011").is_err());
+        assert!(validate_session_key("00112233445566778899aabbccddeefg").is_err());
+
+        let update = SkfUpdate {
+            devaddr: hex_field::devaddr(0x4800_0001),
+            session_key: "00112233445566778899aabbccddeeff".to_string(),
+            action: UpdateAction::Add,
+            max_copies: Some(3),
+        };
+        assert_eq!(
+            vec![
+                vec!["devaddr", "session_key", "max_copies"],
+                vec!["48000001", "0011..eeff", "3"],
+            ],
+            wizard_rows(&[update])
+        );
+    }
+
+    #[test]
+    fn skf_drift_against_lns() {
+        let lns = LnsSkf::parse_csv(
+            "devaddr,session_key,max_copies\n\
+             00000001,AABB,\n\
+             00000002,ccdd,3\n\
+             00000003,eeff,1\n",
+        )
+        .unwrap();
+        let skf = |devaddr, session_key: &str, max_copies| {
+            Skf::new(
+                fixtures::ROUTE_ID.to_string(),
+                hex_field::devaddr(devaddr),
+                session_key.to_string(),
+                max_copies,
+            )
+            .unwrap()
+        };
+        let route = vec![
+            skf(1, "aabb", Some(7)),
+            skf(2, "CCDD", Some(5)),
+            skf(4, "0011", None),
+        ];
+
+        let drift = serde_json::to_value(SkfDrift::new(lns, route)).unwrap();
+        assert_eq!("00000003", drift["missing"][0]["devaddr"]);
+        assert_eq!(1, drift["missing"].as_array().unwrap().len());
+        assert_eq!("00000004", drift["extra"][0]["devaddr"]);
+        assert_eq!(1, drift["extra"].as_array().unwrap().len());
+        assert_eq!(
+            serde_json::json!([{
+                "devaddr": "00000002",
+                "session_key": "CCDD",
+                "lns": 3,
+                "route": 5,
+            }]),
+            drift["max_copies"]
+        );
+
+        assert!(LnsSkf::parse_csv("devaddr,key\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn copy_members_sends_chunks_as_they_stream() {
+        let total = BULK_CHUNK_SIZE * 2 + 3;
+        let mut sent: Vec<Vec<usize>> = vec![];
+        let copied = copy_members(
+            futures::stream::iter((0..total).map(Ok)),
+            total,
+            "copy",
+            Arc::new(NoProgress),
+            |chunk| {
+                sent.push(chunk);
+                async { Ok::<_, anyhow::Error>(()) }
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(total, copied);
+        assert_eq!(
+            vec![BULK_CHUNK_SIZE, BULK_CHUNK_SIZE, 3],
+            sent.iter().map(Vec::len).collect::<Vec<_>>()
+        );
+        assert_eq!((0..total).collect::<Vec<_>>(), sent.concat());
+
+        // chunks read before a failed read have been sent
+        let mut sent: Vec<Vec<usize>> = vec![];
+        let source = futures::stream::iter((0..BULK_CHUNK_SIZE).map(Ok)).chain(
+            futures::stream::once(async { Err(anyhow!("stream reset")) }),
+        );
+        let result = copy_members(source, total, "copy", Arc::new(NoProgress), |chunk| {
+            sent.push(chunk);
+            async { Ok::<_, anyhow::Error>(()) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(1, sent.len());
+    }
+
+    #[tokio::test]
+    async fn copies_reject_the_same_route() {
+        let args = || CopyRouteMembers {
+            from: fixtures::ROUTE_ID.to_string(),
+            to: fixtures::ROUTE_ID.to_string(),
+            keypair: PathBuf::from("keypair.bin"),
+            no_preflight: false,
+            config_host: "http://localhost:6080".to_string(),
+            config_pubkey: fixtures::keypair().public_key().to_string(),
+            commit: true,
+        };
+        assert!(matches!(euis::copy_euis(args()).await, Ok(Msg::Error(_))));
+        assert!(matches!(
+            devaddrs::copy_devaddrs(args()).await,
+            Ok(Msg::Error(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn preflight_checks_the_signer_manages_the_oui() {
+        let owner = fixtures::keypair();
+        let mut orgs = FakeStorage::default()
+            .with_orgs([fixtures::org(1, owner.public_key())])
+            .org_api();
+        assert!(check_signer(&mut orgs, 1, &owner).await.is_ok());
+
+        let stranger = fixtures::keypair();
+        let err = check_signer(&mut orgs, 1, &stranger).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("is not the owner or a delegate of OUI 1"));
+        assert!(check_signer(&mut orgs, 2, &owner).await.is_err());
     }
 }
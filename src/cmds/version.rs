@@ -0,0 +1,38 @@
+use super::VersionInfo;
+use crate::{client, Msg, PrettyJson, Result};
+use serde_json::json;
+
+pub const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const HELIUM_PROTO_REV: &str = env!("HELIUM_PROTO_REV");
+
+pub async fn version(args: VersionInfo) -> Result<Msg> {
+    let mut warnings = vec![];
+    let org_v1 = match probe_org_v1(&args.config_host, &args.config_pubkey).await {
+        Ok(()) => "ok".to_string(),
+        Err(err) => {
+            warnings.push(format!(
+                "{} did not serve OrgV1 endpoints, this CLI may be incompatible with the config service",
+                args.config_host
+            ));
+            format!("unavailable: {err}")
+        }
+    };
+
+    let output = json!({
+        "cli_version": CLI_VERSION,
+        "helium_proto_revision": HELIUM_PROTO_REV,
+        "config_host": args.config_host,
+        "capabilities": {
+            "org_v1": org_v1,
+        },
+        "warnings": warnings,
+    });
+    Msg::ok(output.pretty_json()?)
+}
+
+/// Listing orgs requires no signature, so any reachable compatible server will answer.
+async fn probe_org_v1(config_host: &str, config_pubkey: &str) -> Result {
+    let mut client = client::OrgClient::new(config_host, config_pubkey).await?;
+    client.list().await?;
+    Ok(())
+}
@@ -1,5 +1,10 @@
 use crate::{DevaddrConstraint, NetId, Result};
 use anyhow::anyhow;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{fmt::Display, str::FromStr};
 
@@ -47,14 +52,40 @@ impl<const WIDTH: usize> Display for HexField<WIDTH> {
     }
 }
 
+impl<const WIDTH: usize> HexField<WIDTH> {
+    /// The field name and an example value, for errors.
+    fn describe() -> (&'static str, &'static str) {
+        match WIDTH {
+            6 => ("net_id", "C00053"),
+            8 => ("devaddr", "48000001"),
+            16 => ("eui", "0ABD68FDE91EE0DB"),
+            _ => ("hex field", ""),
+        }
+    }
+}
+
+/// `s` without a `0x` prefix, which values copied from other tools often have.
+pub fn strip_hex_prefix(s: &str) -> &str {
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s)
+}
+
 impl<const WIDTH: usize> FromStr for HexField<WIDTH> {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<HexField<WIDTH>> {
         if "*" == s {
             return Ok(HexField::<WIDTH>(0));
         }
-        verify_len(s, WIDTH)?;
-        Ok(HexField::<WIDTH>(u64::from_str_radix(s, 16)?))
+        let digits = strip_hex_prefix(s);
+        let (name, example) = Self::describe();
+        verify_len(digits, WIDTH, name, example)?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!(
+                "{name} must be {WIDTH} hex chars like {example}; got non-hex characters in {s}"
+            ));
+        }
+        Ok(HexField::<WIDTH>(u64::from_str_radix(digits, 16)?))
     }
 }
 
@@ -67,6 +98,25 @@ impl<const WIDTH: usize> Serialize for HexField<WIDTH> {
     }
 }
 
+impl<const WIDTH: usize> JsonSchema for HexField<WIDTH> {
+    fn schema_name() -> String {
+        format!("HexField{WIDTH}")
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        // `*` is accepted as a wildcard for 0, and a `0x` prefix is ignored
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(format!("^((0[xX])?[0-9a-fA-F]{{{WIDTH}}}|\\*)$")),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl<'de, const WIDTH: usize> Deserialize<'de> for HexField<WIDTH> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -85,9 +135,7 @@ impl<'de, const WIDTH: usize> Deserialize<'de> for HexField<WIDTH> {
             where
                 E: serde::de::Error,
             {
-                let field = HexField::<IN_WIDTH>::from_str(value)
-                    .map_err(|_| serde::de::Error::invalid_length(value.len(), &self))?;
-                Ok(field)
+                HexField::<IN_WIDTH>::from_str(value).map_err(serde::de::Error::custom)
             }
 
             fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
@@ -103,15 +151,15 @@ impl<'de, const WIDTH: usize> Deserialize<'de> for HexField<WIDTH> {
 }
 
 pub fn validate_net_id(s: &str) -> Result<HexNetID> {
-    HexNetID::from_str(s).map_err(|e| anyhow!("could not parse {s} into net_id, {e}"))
+    HexNetID::from_str(s).map_err(|e| anyhow!("could not parse {s}, {e}"))
 }
 
 pub fn validate_devaddr(s: &str) -> Result<HexDevAddr> {
-    HexDevAddr::from_str(s).map_err(|e| anyhow!("could not parse {s} into devaddr, {e}"))
+    HexDevAddr::from_str(s).map_err(|e| anyhow!("could not parse {s}, {e}"))
 }
 
 pub fn validate_eui(s: &str) -> Result<HexEui> {
-    HexEui::from_str(s).map_err(|e| anyhow!("could not parse {s} into eui, {e}"))
+    HexEui::from_str(s).map_err(|e| anyhow!("could not parse {s}, {e}"))
 }
 
 pub fn devaddr(val: u64) -> HexDevAddr {
@@ -126,10 +174,12 @@ pub fn net_id(val: NetId) -> HexNetID {
     val.into()
 }
 
-fn verify_len(input: &str, expected_len: usize) -> Result<()> {
+fn verify_len(input: &str, expected_len: usize, name: &str, example: &str) -> Result<()> {
     match input.len() {
         len if len == expected_len => Ok(()),
-        len => Err(anyhow!("Found {len} chars long, should be {expected_len}"))?,
+        len => Err(anyhow!(
+            "{name} must be {expected_len} hex chars like {example}; got {len}"
+        )),
     }
 }
 
@@ -240,7 +290,7 @@ mod tests {
     };
     use pretty_assertions::assert_eq;
 
-    use super::HexEui;
+    use super::{HexDevAddr, HexEui};
 
     #[test]
     fn range_from_net_id() {
@@ -327,4 +377,40 @@ mod tests {
         let val: HexEui = serde_json::from_str(r#""*""#).expect("serde_json from_str");
         assert_eq!(0, val.0);
     }
+
+    #[test]
+    fn hex_prefix_is_stripped() {
+        assert_eq!(
+            devaddr(0x4800_0001),
+            HexDevAddr::from_str("0x48000001").unwrap()
+        );
+        assert_eq!(eui(0xAB), HexEui::from_str("0X00000000000000ab").unwrap());
+        let val: HexDevAddr = serde_json::from_str(r#""0x48000001""#).unwrap();
+        assert_eq!(devaddr(0x4800_0001), val);
+    }
+
+    #[test]
+    fn malformed_hex_errors() {
+        let err = HexDevAddr::from_str("4800001").unwrap_err();
+        assert_eq!(
+            "devaddr must be 8 hex chars like 48000001; got 7",
+            err.to_string()
+        );
+        let err = HexDevAddr::from_str("").unwrap_err();
+        assert_eq!(
+            "devaddr must be 8 hex chars like 48000001; got 0",
+            err.to_string()
+        );
+        let err = HexDevAddr::from_str("0x4800000G").unwrap_err();
+        assert_eq!(
+            "devaddr must be 8 hex chars like 48000001; got non-hex characters in 0x4800000G",
+            err.to_string()
+        );
+        // `+` is accepted by from_str_radix
+        assert!(HexEui::from_str("+000000000000001").is_err());
+        let err = serde_json::from_str::<HexEui>(r#""abc""#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("eui must be 16 hex chars like 0ABD68FDE91EE0DB; got 3"));
+    }
 }
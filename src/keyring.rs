@@ -0,0 +1,127 @@
+use crate::Result;
+use anyhow::{anyhow, Context};
+use helium_crypto::{PublicKey, Verify};
+use std::{path::Path, str::FromStr};
+
+/// Keys the config service may sign its responses with.
+///
+/// A response is accepted when any of them verifies it, so the CLI keeps
+/// working while the service rotates its signing key.
+#[derive(Debug, Clone)]
+pub struct ServerKeys(Vec<PublicKey>);
+
+impl ServerKeys {
+    /// The first key given, used where a single key is needed.
+    pub fn primary(&self) -> &PublicKey {
+        &self.0[0]
+    }
+
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.0
+    }
+
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> Result {
+        let mut last_err = None;
+        for key in &self.0 {
+            match key.verify(msg, signature) {
+                Ok(()) => return Ok(()),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        match last_err {
+            Some(err) if self.0.len() == 1 => Err(err.into()),
+            _ => Err(anyhow!(
+                "response not signed by any of {} config service keys",
+                self.0.len()
+            )),
+        }
+    }
+}
+
+/// Accepts a comma separated list of keys, or the path of a keyring file
+/// with keys separated by commas or newlines. Lines starting with `#` in a
+/// keyring file are ignored.
+impl FromStr for ServerKeys {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = Path::new(s);
+        let data = if path.is_file() {
+            std::fs::read_to_string(path).context(format!("reading keyring {}", path.display()))?
+        } else {
+            s.to_string()
+        };
+
+        let keys = data
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| PublicKey::from_str(key).context(format!("invalid config pubkey {key}")))
+            .collect::<Result<Vec<_>>>()?;
+
+        if keys.is_empty() {
+            return Err(anyhow!("no config pubkeys given"));
+        }
+        Ok(Self(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServerKeys;
+    use helium_crypto::{KeyTag, KeyType, Keypair, Network, Sign};
+    use rand::rngs::OsRng;
+    use std::str::FromStr;
+    use temp_dir::TempDir;
+
+    fn keypair() -> Keypair {
+        Keypair::generate(
+            KeyTag {
+                network: Network::MainNet,
+                key_type: KeyType::Ed25519,
+            },
+            &mut OsRng,
+        )
+    }
+
+    #[test]
+    fn any_key_verifies() {
+        let (old, new, other) = (keypair(), keypair(), keypair());
+        let keys =
+            ServerKeys::from_str(&format!("{}, {}", old.public_key(), new.public_key())).unwrap();
+        assert_eq!(2, keys.keys().len());
+        assert_eq!(old.public_key(), keys.primary());
+
+        let msg = b"response";
+        assert!(keys.verify(msg, &old.sign(msg).unwrap()).is_ok());
+        assert!(keys.verify(msg, &new.sign(msg).unwrap()).is_ok());
+        assert!(keys.verify(msg, &other.sign(msg).unwrap()).is_err());
+    }
+
+    #[test]
+    fn keyring_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("keyring");
+        let (old, new) = (keypair(), keypair());
+        std::fs::write(
+            &path,
+            format!(
+                "# rotating out\n{}\n\n{}\n",
+                old.public_key(),
+                new.public_key()
+            ),
+        )
+        .unwrap();
+
+        let keys = ServerKeys::from_str(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            &[old.public_key().clone(), new.public_key().clone()],
+            keys.keys()
+        );
+        assert!(ServerKeys::from_str("").is_err());
+        assert!(ServerKeys::from_str("not-a-key").is_err());
+    }
+}
@@ -1,15 +1,38 @@
+pub mod alias;
+pub mod api;
+pub mod backup;
 pub mod client;
 pub mod cmds;
 pub mod hex_field;
+pub mod keyring;
+pub mod metadata;
+pub mod netid;
+pub mod normalize;
+pub mod output;
+pub mod profile;
+pub mod progress;
+pub mod recording;
 pub mod region;
 pub mod region_params;
+pub mod retry;
 pub mod route;
+pub mod route_cache;
+pub mod schema;
 pub mod server;
+pub mod shared_file;
+pub mod solana;
+pub mod strict;
 pub mod subnet;
+pub mod telemetry;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timestamp;
+pub mod warning;
 
 use anyhow::{anyhow, Error};
 use helium_crypto::PublicKey;
 use route::Route;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use subnet::DevaddrConstraint;
@@ -25,6 +48,14 @@ pub mod proto {
 
 pub type Result<T = (), E = Error> = anyhow::Result<T, E>;
 
+/// Used for Session Key Filters that do not specify `max_copies`.
+pub const DEFAULT_SKF_MAX_COPIES: u32 = 1;
+
+/// The highest `max_copies` of a Route or Session Key Filter set without a
+/// warning. The config service accepts any value, but every copy bought
+/// costs DC.
+pub const MAX_COPIES_LIMIT: u32 = 15;
+
 type Oui = u64;
 type NetId = u32;
 
@@ -45,6 +76,13 @@ impl Msg {
     pub fn dry_run(msg: String) -> Result<Self> {
         Ok(Self::DryRun(msg))
     }
+    /// Prefix an error message with `context`, other messages are unchanged.
+    pub fn with_err_context(self, context: &str) -> Self {
+        match self {
+            Msg::Error(msg) => Msg::Error(format!("{context}: {msg}")),
+            msg => msg,
+        }
+    }
     pub fn into_inner(self) -> String {
         match self {
             Msg::DryRun(s) => s,
@@ -90,6 +128,16 @@ pub enum HeliumNetId {
     Type6_0xc00053,
 }
 
+impl HeliumNetId {
+    pub fn net_id(&self) -> NetId {
+        match self {
+            HeliumNetId::Type0_0x00003c => 0x00003c,
+            HeliumNetId::Type3_0x60002d => 0x60002d,
+            HeliumNetId::Type6_0xc00053 => 0xc00053,
+        }
+    }
+}
+
 impl From<HeliumNetId> for proto::HeliumNetId {
     fn from(id: HeliumNetId) -> Self {
         match id {
@@ -100,10 +148,11 @@ impl From<HeliumNetId> for proto::HeliumNetId {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OrgResponse {
     pub org: Org,
     pub net_id: hex_field::HexNetID,
+    #[serde(serialize_with = "subnet::serialize_with_subnets")]
     pub devaddr_constraints: Vec<DevaddrConstraint>,
 }
 
@@ -126,6 +175,12 @@ pub struct OrgList {
     pub orgs: Vec<Org>,
 }
 
+/// Orgs with their devaddr constraints, in the same shape as `org get`.
+#[derive(Debug, Serialize)]
+pub struct OrgConstraintList {
+    pub orgs: Vec<OrgResponse>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Org {
     pub oui: Oui,
@@ -135,12 +190,34 @@ pub struct Org {
     pub locked: bool,
 }
 
+impl Org {
+    /// True when `pubkey` is the owner or one of the delegate keys of this Org.
+    pub fn is_managed_by(&self, pubkey: &PublicKey) -> bool {
+        &self.owner == pubkey || self.delegate_keys.contains(pubkey)
+    }
+
+    /// Every role `pubkey` has in this Org: `owner`, `payer`, and `delegate`.
+    pub fn roles(&self, pubkey: &PublicKey) -> Vec<&'static str> {
+        let mut roles = vec![];
+        if &self.owner == pubkey {
+            roles.push("owner");
+        }
+        if &self.payer == pubkey {
+            roles.push("payer");
+        }
+        if self.delegate_keys.contains(pubkey) {
+            roles.push("delegate");
+        }
+        roles
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct RouteList {
     pub routes: Vec<Route>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone, Hash)]
 pub struct DevaddrRange {
     pub route_id: String,
     pub start_addr: hex_field::HexDevAddr,
@@ -165,7 +242,7 @@ impl DevaddrRange {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
 pub struct Eui {
     pub route_id: String,
     pub app_eui: hex_field::HexEui,
@@ -184,6 +261,59 @@ impl Eui {
             dev_eui,
         })
     }
+
+    /// True when either EUI is the zero wildcard.
+    pub fn is_wildcard(&self) -> bool {
+        self.app_eui.0 == 0 || self.dev_eui.0 == 0
+    }
+}
+
+/// Why `max_copies` is likely a mistake, `None` when it is within
+/// [`MAX_COPIES_LIMIT`].
+pub fn max_copies_warning(max_copies: u32) -> Option<String> {
+    (max_copies > MAX_COPIES_LIMIT).then(|| {
+        format!(
+            "max_copies {max_copies} is over the network maximum of {MAX_COPIES_LIMIT}, the extra copies only spend DC"
+        )
+    })
+}
+
+/// Reject adding a pair with the zero app_eui unless `wildcard_app_eui` says
+/// the wildcard is intended.
+pub fn check_wildcard_app_euis(
+    pairs: impl IntoIterator<Item = (hex_field::HexEui, hex_field::HexEui)>,
+    wildcard_app_eui: bool,
+) -> Result {
+    if wildcard_app_eui {
+        return Ok(());
+    }
+    match pairs.into_iter().find(|(app_eui, _)| app_eui.0 == 0) {
+        Some((app_eui, dev_eui)) => Err(anyhow!(
+            "app_eui {app_eui} of dev_eui {dev_eui} is the wildcard, pass --wildcard-app-eui if that is intended"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// What adding an EUI pair containing the zero wildcard will match, `None`
+/// when neither EUI is a wildcard.
+pub fn eui_wildcard_warning(
+    app_eui: hex_field::HexEui,
+    dev_eui: hex_field::HexEui,
+) -> Option<String> {
+    match (app_eui.0, dev_eui.0) {
+        (0, 0) => Some(
+            "app_eui and dev_eui are both wildcards, the route will match every join request on the network"
+                .to_string(),
+        ),
+        (0, _) => Some(format!(
+            "app_eui is a wildcard, the route will match joins from dev_eui {dev_eui} with any app_eui"
+        )),
+        (_, 0) => Some(format!(
+            "dev_eui is a wildcard, the route will match joins from every device with app_eui {app_eui}"
+        )),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
@@ -208,9 +338,14 @@ impl Skf {
             max_copies,
         })
     }
+
+    /// The `max_copies` that will be sent to the config service.
+    pub fn effective_max_copies(&self) -> u32 {
+        self.max_copies.unwrap_or(DEFAULT_SKF_MAX_COPIES)
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UpdateAction {
     #[serde(alias = "add")]
     Add,
@@ -218,7 +353,7 @@ pub enum UpdateAction {
     Remove,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
 pub struct SkfUpdate {
     pub devaddr: hex_field::HexDevAddr,
     pub session_key: String,
@@ -226,19 +361,224 @@ pub struct SkfUpdate {
     pub max_copies: Option<u32>,
 }
 
-impl From<SkfUpdate> for proto::RouteSkfUpdateV1 {
-    fn from(update: SkfUpdate) -> Self {
-        let action = match update.action {
+impl SkfUpdate {
+    /// Parse `<devaddr> <session_key> [max_copies]` lines into additions.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. `max_copies` is
+    /// used for lines that do not specify their own.
+    pub fn parse_add_lines(data: &str, max_copies: Option<u32>) -> Result<Vec<Self>> {
+        data.lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_num, line)| {
+                let fields: Vec<&str> = line
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|field| !field.is_empty())
+                    .collect();
+                let (devaddr, session_key, line_max_copies) = match fields[..] {
+                    [devaddr, session_key] => (devaddr, session_key, None),
+                    [devaddr, session_key, max_copies] => (devaddr, session_key, Some(max_copies)),
+                    _ => {
+                        return Err(anyhow!(
+                            "line {line_num}: expected `<devaddr> <session_key> [max_copies]`"
+                        ))
+                    }
+                };
+                let devaddr = hex_field::validate_devaddr(devaddr)
+                    .map_err(|err| anyhow!("line {line_num}: {err}"))?;
+                let max_copies = match line_max_copies {
+                    Some(val) => Some(
+                        val.parse()
+                            .map_err(|_| anyhow!("line {line_num}: invalid max_copies {val}"))?,
+                    ),
+                    None => max_copies,
+                };
+                Ok(Self {
+                    devaddr,
+                    session_key: session_key.to_string(),
+                    action: UpdateAction::Add,
+                    max_copies,
+                })
+            })
+            .collect()
+    }
+
+    /// Parse CSV with the header `devaddr,session_key,action,max_copies`.
+    ///
+    /// Blank rows are skipped and an empty `max_copies` uses the Route's.
+    /// Every invalid row is reported, by line number.
+    pub fn parse_csv(data: &str) -> Result<Vec<Self>> {
+        let rows = parse_skf_csv(data, &[&SKF_CSV_HEADER])?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Self {
+                devaddr: row.devaddr,
+                session_key: row.session_key,
+                action: row.action.expect("the header has an action column"),
+                max_copies: row.max_copies,
+            })
+            .collect())
+    }
+}
+
+const SKF_CSV_HEADER: [&str; 4] = ["devaddr", "session_key", "action", "max_copies"];
+
+/// A Session Key Filter as an LNS exports it, to compare against a Route.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct LnsSkf {
+    pub devaddr: hex_field::HexDevAddr,
+    pub session_key: String,
+    /// Not compared when missing
+    #[serde(default)]
+    pub max_copies: Option<u32>,
+}
+
+impl LnsSkf {
+    /// Parse CSV with the header `devaddr,session_key` or
+    /// `devaddr,session_key,max_copies`.
+    ///
+    /// Blank rows are skipped and every invalid row is reported, by line number.
+    pub fn parse_csv(data: &str) -> Result<Vec<Self>> {
+        let rows = parse_skf_csv(
+            data,
+            &[
+                &["devaddr", "session_key"],
+                &["devaddr", "session_key", "max_copies"],
+            ],
+        )?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Self {
+                devaddr: row.devaddr,
+                session_key: row.session_key,
+                max_copies: row.max_copies,
+            })
+            .collect())
+    }
+}
+
+/// A row of an SKF CSV, with the columns of the header it was read with.
+struct SkfCsvRow {
+    devaddr: hex_field::HexDevAddr,
+    session_key: String,
+    action: Option<UpdateAction>,
+    max_copies: Option<u32>,
+}
+
+/// The rows of SKF CSV whose header is one of `headers`. Spreadsheets quote
+/// fields and may start the file with a UTF-8 BOM, both are accepted.
+fn parse_skf_csv(data: &str, headers: &[&[&str]]) -> Result<Vec<SkfCsvRow>> {
+    let data = data.strip_prefix('\u{feff}').unwrap_or(data);
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(data.as_bytes());
+    let header: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    if !headers.iter().any(|expected| header == *expected) {
+        let expected: Vec<String> = headers
+            .iter()
+            .map(|expected| format!("`{}`", expected.join(",")))
+            .collect();
+        return Err(anyhow!(
+            "expected the header {}, found `{}`",
+            expected.join(" or "),
+            header.join(",")
+        ));
+    }
+
+    let mut rows = vec![];
+    let mut errors = vec![];
+    for record in reader.records() {
+        let record = record?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let line_num = record.position().map_or(0, |position| position.line());
+        match parse_skf_csv_row(&header, &record) {
+            Ok(row) => rows.push(row),
+            Err(err) => errors.push(format!("line {line_num}: {err}")),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+    Ok(rows)
+}
+
+fn parse_skf_csv_row(header: &[String], record: &csv::StringRecord) -> Result<SkfCsvRow> {
+    if record.len() != header.len() {
+        return Err(anyhow!(
+            "expected {} fields, found {}",
+            header.len(),
+            record.len()
+        ));
+    }
+    let field = |name: &str| {
+        header
+            .iter()
+            .position(|column| column == name)
+            .and_then(|idx| record.get(idx))
+    };
+    let session_key = field("session_key").unwrap_or_default();
+    if session_key.is_empty() {
+        return Err(anyhow!("missing session_key"));
+    }
+    let action = match field("action").map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("add") => Some(UpdateAction::Add),
+        Some("remove") => Some(UpdateAction::Remove),
+        Some(action) => return Err(anyhow!("invalid action {action}, expected add or remove")),
+    };
+    let max_copies = match field("max_copies").unwrap_or_default() {
+        "" => None,
+        val => Some(
+            val.parse()
+                .map_err(|_| anyhow!("invalid max_copies {val}"))?,
+        ),
+    };
+    Ok(SkfCsvRow {
+        devaddr: hex_field::validate_devaddr(field("devaddr").unwrap_or_default())?,
+        session_key: session_key.to_string(),
+        action,
+        max_copies,
+    })
+}
+
+impl From<UpdateAction> for proto::ActionV1 {
+    fn from(action: UpdateAction) -> Self {
+        match action {
             UpdateAction::Add => proto::ActionV1::Add,
             UpdateAction::Remove => proto::ActionV1::Remove,
         }
-        .into();
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct EuiUpdate {
+    pub app_eui: hex_field::HexEui,
+    pub dev_eui: hex_field::HexEui,
+    pub action: UpdateAction,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct DevaddrUpdate {
+    pub start_addr: hex_field::HexDevAddr,
+    pub end_addr: hex_field::HexDevAddr,
+    pub action: UpdateAction,
+}
+
+impl From<SkfUpdate> for proto::RouteSkfUpdateV1 {
+    fn from(update: SkfUpdate) -> Self {
+        let action = proto::ActionV1::from(update.action).into();
 
         Self {
             devaddr: update.devaddr.into(),
             session_key: update.session_key,
             action,
-            max_copies: update.max_copies.unwrap_or(1),
+            // additions are given the Route's max_copies before they are sent, this
+            // only fills in removes
+            max_copies: update.max_copies.unwrap_or(DEFAULT_SKF_MAX_COPIES),
         }
     }
 }
@@ -289,11 +629,12 @@ impl From<proto::SkfV1> for Skf {
 
 impl From<Skf> for proto::SkfV1 {
     fn from(filter: Skf) -> Self {
+        let max_copies = filter.effective_max_copies();
         Self {
             route_id: filter.route_id,
             devaddr: filter.devaddr.0 as u32,
             session_key: filter.session_key,
-            max_copies: filter.max_copies.unwrap_or(1),
+            max_copies,
         }
     }
 }
@@ -419,7 +760,45 @@ impl From<Eui> for proto::EuiPairV1 {
 
 #[cfg(test)]
 mod tests {
-    use crate::{hex_field, DevaddrRange, Eui};
+    use crate::{
+        check_wildcard_app_euis, hex_field, max_copies_warning, testing::fixtures, DevaddrRange,
+        Eui, SkfUpdate, UpdateAction, MAX_COPIES_LIMIT,
+    };
+
+    #[test]
+    fn wildcard_app_euis_need_the_flag() {
+        let pairs = [
+            (hex_field::eui(1), hex_field::eui(2)),
+            (hex_field::eui(0), hex_field::eui(3)),
+        ];
+        assert!(check_wildcard_app_euis(pairs[..1].to_vec(), false).is_ok());
+        let err = check_wildcard_app_euis(pairs, false).unwrap_err();
+        assert!(err.to_string().contains("of dev_eui 0000000000000003"));
+        assert!(check_wildcard_app_euis(pairs, true).is_ok());
+    }
+
+    #[test]
+    fn max_copies_over_the_limit() {
+        assert_eq!(None, max_copies_warning(MAX_COPIES_LIMIT));
+        assert!(max_copies_warning(MAX_COPIES_LIMIT + 1)
+            .unwrap()
+            .starts_with(&format!("max_copies {}", MAX_COPIES_LIMIT + 1)));
+    }
+
+    #[test]
+    fn org_roles() {
+        let (owner, delegate, other) = (
+            fixtures::keypair().public_key().clone(),
+            fixtures::keypair().public_key().clone(),
+            fixtures::keypair().public_key().clone(),
+        );
+        let mut org = fixtures::org(1, &owner).org;
+        org.delegate_keys.push(delegate.clone());
+
+        assert_eq!(vec!["owner", "payer"], org.roles(&owner));
+        assert_eq!(vec!["delegate"], org.roles(&delegate));
+        assert!(org.roles(&other).is_empty());
+    }
 
     #[test]
     fn deserialize_devaddr_range() {
@@ -448,4 +827,71 @@ mod tests {
             val
         );
     }
+
+    #[test]
+    fn parse_skf_add_lines() {
+        let data = "# provisioning export\n00000001 key-one\n\n00000002,key-two,3\n";
+        let updates = SkfUpdate::parse_add_lines(data, Some(2)).unwrap();
+        assert_eq!(
+            vec![
+                SkfUpdate {
+                    devaddr: hex_field::devaddr(1),
+                    session_key: "key-one".to_string(),
+                    action: UpdateAction::Add,
+                    max_copies: Some(2),
+                },
+                SkfUpdate {
+                    devaddr: hex_field::devaddr(2),
+                    session_key: "key-two".to_string(),
+                    action: UpdateAction::Add,
+                    max_copies: Some(3),
+                },
+            ],
+            updates
+        );
+
+        let err = SkfUpdate::parse_add_lines("00000001\n", None).unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn parse_skf_csv() {
+        let data = "devaddr,session_key,action,max_copies\n\
+                    00000001,key-one,add,3\n\
+                    \n\
+                    \"00000002\",\"key-two\",Remove,\n";
+        assert_eq!(
+            vec![
+                SkfUpdate {
+                    devaddr: hex_field::devaddr(1),
+                    session_key: "key-one".to_string(),
+                    action: UpdateAction::Add,
+                    max_copies: Some(3),
+                },
+                SkfUpdate {
+                    devaddr: hex_field::devaddr(2),
+                    session_key: "key-two".to_string(),
+                    action: UpdateAction::Remove,
+                    max_copies: None,
+                },
+            ],
+            SkfUpdate::parse_csv(data).unwrap()
+        );
+
+        let bom = format!("\u{feff}{data}");
+        assert_eq!(2, SkfUpdate::parse_csv(&bom).unwrap().len());
+
+        let err = SkfUpdate::parse_csv("devaddr,session_key,action\n").unwrap_err();
+        assert!(err.to_string().starts_with("expected the header"));
+
+        let data = "devaddr,session_key,action,max_copies\n\
+                    00000001,key-one,move,\n\
+                    00000002,key-two,add,3\n\
+                    00000003,key-three,add\n";
+        let err = SkfUpdate::parse_csv(data).unwrap_err().to_string();
+        let lines: Vec<_> = err.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("line 2: invalid action move"));
+        assert!(lines[1].starts_with("line 4: expected 4 fields"));
+    }
 }
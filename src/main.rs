@@ -1,25 +1,78 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use helium_config_service_cli::{
+    alias::Aliases,
+    client,
     cmds::{
-        self, admin, env, gateway, org,
-        route::{self, devaddrs, euis, skfs},
-        Cli, Commands, EnvCommands as Env, OrgCommands as Org, RouteCommands, RouteUpdateCommand,
+        self, admin, env, gateway, netid, org, report,
+        route::{self, devaddrs, euis, file, skfs},
+        version, Cli, Commands, EnvCommands as Env, OrgCommands as Org, RouteCommands,
+        RouteUpdateCommand,
     },
-    Msg, Result,
+    metadata,
+    output::{OutputSink, Outputs},
+    profile, recording, retry, telemetry, timestamp, warning, Msg, Result,
 };
+use std::ffi::OsString;
+use tracing::Instrument;
 
-#[tokio::main]
-async fn main() -> Result {
-    let cli = Cli::parse();
+/// Profiles set environment variables, which is only sound before the
+/// runtime starts its threads.
+fn main() -> Result {
+    let aliases = Aliases::load(std::env::var_os(cmds::ENV_ALIASES).map(Into::into));
+    let args = aliases.expand(std::env::args_os().collect(), &Cli::command());
+    profile::apply(
+        std::env::var_os(cmds::ENV_PROFILES).map(Into::into),
+        &args,
+        Cli::command(),
+    )?;
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(args, aliases))
+}
+
+async fn run(args: Vec<OsString>, aliases: Aliases) -> Result {
+    let cli = Cli::parse_from(&args);
 
     if cli.print_command {
         println!("{cli:#?}");
     }
 
-    let msg = handle_cli(cli).await?;
-    println!("{msg}");
+    metadata::set_headers(cli.header.clone())?;
+    client::set_timestamp_skew(cli.timestamp_skew)?;
+    timestamp::set_utc(cli.utc)?;
+    warning::set_as_errors(cli.warnings_as_errors)?;
+    aliases.warn_ignored()?;
+    retry::set_adaptive_chunking(cli.adaptive_chunking)?;
+    if let Some(dir) = &cli.record {
+        recording::record_to(dir)?;
+    }
+    if let Some(dir) = &cli.replay {
+        recording::replay_from(dir)?;
+    }
+    if let Some(endpoint) = &cli.otlp_endpoint {
+        telemetry::init(endpoint)?;
+    }
+
+    if let Some(mismatch) = cmds::keypair_network_warning(&cli.keypair, &cli.config_host) {
+        warning::warn(mismatch)?;
+    }
 
-    Ok(())
+    let mut outputs = Outputs::new(cli.quiet, cli.output_file.as_deref(), cli.syslog)?;
+    let span = telemetry::command_span(&args, Cli::command());
+    let result = handle_cli(cli).instrument(span.clone()).await;
+    if result.is_err() {
+        telemetry::record_error(&span);
+    }
+    drop(span);
+    telemetry::shutdown();
+    match result {
+        Ok(msg) => outputs.write(&msg),
+        Err(err) => {
+            outputs.write_failure(&err)?;
+            Err(err)
+        }
+    }
 }
 
 pub async fn handle_cli(cli: Cli) -> Result<Msg> {
@@ -28,18 +81,23 @@ pub async fn handle_cli(cli: Cli) -> Result<Msg> {
             Env::Init => env::env_init().await,
             Env::Info(args) => env::env_info(args),
             Env::GenerateKeypair(args) => env::generate_keypair(args),
+            Env::WalletInfo(args) => env::wallet_info(args).await,
         },
         Commands::Route { command } => match command {
             RouteCommands::List(args) => route::list_routes(args).await,
             RouteCommands::Get(args) => route::get_route(args).await,
+            RouteCommands::Summary(args) => route::route_summary(args).await,
             RouteCommands::New(args) => route::new_route(args).await,
             RouteCommands::Delete(args) => route::delete_route(args).await,
+            RouteCommands::ProbePropagation(args) => route::probe_propagation(args).await,
+            RouteCommands::Stream(args) => route::stream_routes(args).await,
             RouteCommands::Update { command } => match command {
                 RouteUpdateCommand::MaxCopies(args) => route::update_max_copies(args).await,
                 RouteUpdateCommand::Server(args) => route::update_server(args).await,
                 RouteUpdateCommand::Http(args) => route::update_http(args).await,
                 RouteUpdateCommand::AddGwmpRegion(args) => route::add_gwmp_region(args).await,
                 RouteUpdateCommand::RemoveGwmpRegion(args) => route::remove_gwmp_region(args).await,
+                RouteUpdateCommand::ClearProtocol(args) => route::clear_protocol(args).await,
                 RouteUpdateCommand::PacketRouter(args) => route::update_packet_router(args).await,
                 RouteUpdateCommand::IgnoreEmptySkf(args) => {
                     route::update_ignore_empty_skf(args).await
@@ -50,13 +108,21 @@ pub async fn handle_cli(cli: Cli) -> Result<Msg> {
                 cmds::EuiCommands::Add(args) => euis::add_eui(args).await,
                 cmds::EuiCommands::Remove(args) => euis::remove_eui(args).await,
                 cmds::EuiCommands::Clear(args) => euis::clear_euis(args).await,
+                cmds::EuiCommands::Update(args) => euis::update_euis_from_file(args).await,
+                cmds::EuiCommands::Copy(args) => euis::copy_euis(args).await,
             },
             RouteCommands::Devaddrs { command } => match command {
                 cmds::DevaddrCommands::List(args) => devaddrs::list_devaddrs(args).await,
                 cmds::DevaddrCommands::Add(args) => devaddrs::add_devaddr(args).await,
                 cmds::DevaddrCommands::Remove(args) => devaddrs::remove_devaddr(args).await,
+                cmds::DevaddrCommands::Replace(args) => devaddrs::replace_devaddr(args).await,
                 cmds::DevaddrCommands::SubnetMask(args) => devaddrs::subnet_mask(args).await,
+                cmds::DevaddrCommands::Export(args) => devaddrs::export_devaddrs(args).await,
                 cmds::DevaddrCommands::Clear(args) => devaddrs::clear_devaddrs(args).await,
+                cmds::DevaddrCommands::Copy(args) => devaddrs::copy_devaddrs(args).await,
+                cmds::DevaddrCommands::Update(args) => {
+                    devaddrs::update_devaddrs_from_file(args).await
+                }
             },
             RouteCommands::Activate(args) => route::activate_route(args).await,
             RouteCommands::Deactivate(args) => route::deactivate_route(args).await,
@@ -65,16 +131,50 @@ pub async fn handle_cli(cli: Cli) -> Result<Msg> {
                 cmds::SkfCommands::Get(args) => skfs::get_filters(args).await,
                 cmds::SkfCommands::Add(args) => skfs::add_filter(args).await,
                 cmds::SkfCommands::Remove(args) => skfs::remove_filter(args).await,
+                cmds::SkfCommands::SetMaxCopies(args) => skfs::set_max_copies(args).await,
                 cmds::SkfCommands::Clear(args) => skfs::clear_filters(args).await,
                 cmds::SkfCommands::Update(args) => skfs::update_filters_from_file(args).await,
+                cmds::SkfCommands::Export(args) => skfs::export_filters(args).await,
+                cmds::SkfCommands::Verify(args) => skfs::verify_filters(args).await,
+                cmds::SkfCommands::Move(args) => skfs::move_filters(args).await,
+                cmds::SkfCommands::Wizard(args) => skfs::filter_wizard(args).await,
+            },
+            RouteCommands::Schema => route::route_schema(),
+            RouteCommands::Fmt(args) => file::fmt(args),
+            RouteCommands::Backups { command } => match command {
+                cmds::RouteBackupCommands::List(args) => route::list_backups(args),
+                cmds::RouteBackupCommands::Show(args) => route::show_backup(args),
+                cmds::RouteBackupCommands::Restore(args) => route::restore_backup(args).await,
+            },
+            RouteCommands::History(args) => route::route_history(args).await,
+            RouteCommands::Show(args) => route::show_route_at(args).await,
+            RouteCommands::File { command } => match command {
+                cmds::RouteFileCommands::New(args) => file::new_route_file(args),
+                cmds::RouteFileCommands::Show(args) => file::show(args),
+                cmds::RouteFileCommands::SetMaxCopies(args) => file::set_max_copies(args),
+                cmds::RouteFileCommands::SetServer(args) => file::set_server(args),
+                cmds::RouteFileCommands::SetHttp(args) => file::set_http(args),
+                cmds::RouteFileCommands::SetPacketRouter(args) => file::set_packet_router(args),
+                cmds::RouteFileCommands::AddGwmpRegion(args) => file::add_gwmp_region(args),
+                cmds::RouteFileCommands::RemoveGwmpRegion(args) => file::remove_gwmp_region(args),
+                cmds::RouteFileCommands::AddEui(args) => file::add_eui(args),
+                cmds::RouteFileCommands::RemoveEui(args) => file::remove_eui(args),
+                cmds::RouteFileCommands::AddDevaddr(args) => file::add_devaddr(args),
+                cmds::RouteFileCommands::RemoveDevaddr(args) => file::remove_devaddr(args),
             },
         },
         Commands::Org { command } => match command {
             Org::List(args) => org::list_orgs(args).await,
             Org::Get(args) => org::get_org(args).await,
+            Org::Whoami(args) => org::whoami(args).await,
+            Org::Find(args) => org::find_org(args).await,
+            Org::Verify(args) => org::verify_org(args).await,
+            Org::NextFree(args) => org::next_free_devaddrs(args).await,
+            Org::Onboard(args) => org::onboard_org(args).await,
             Org::CreateHelium(args) => org::create_helium_org(args).await,
             Org::CreateRoaming(args) => org::create_roaming_org(args).await,
             Org::Enable(args) => org::enable_org(args).await,
+            Org::Transfer(args) => org::transfer_org(args).await,
             Org::Update { command } => match command {
                 cmds::OrgUpdateCommand::Owner(args) => org::update_owner(args).await,
                 cmds::OrgUpdateCommand::Payer(args) => org::update_payer(args).await,
@@ -92,14 +192,23 @@ pub async fn handle_cli(cli: Cli) -> Result<Msg> {
             },
         },
         Commands::SubnetMask(args) => cmds::subnet_mask(args),
+        Commands::Netid { command } => match command {
+            cmds::NetIdCommands::ListHelium => netid::list_helium(),
+            cmds::NetIdCommands::List(args) => netid::list_net_ids(args).await,
+        },
         Commands::Admin { command } => match command {
             cmds::AdminCommands::LoadRegion(args) => admin::load_region(args).await,
             cmds::AdminCommands::AddKey(args) => admin::add_key(args).await,
             cmds::AdminCommands::RemoveKey(args) => admin::remove_key(args).await,
+            cmds::AdminCommands::ConstraintReport(args) => admin::constraint_report(args).await,
         },
         Commands::Gateway { command } => match command {
             cmds::GatewayCommands::Location(args) => gateway::location(args).await,
             cmds::GatewayCommands::Info(args) => gateway::info(args).await,
         },
+        Commands::Report { command } => match command {
+            cmds::ReportCommands::Inventory(args) => report::inventory(args).await,
+        },
+        Commands::Version(args) => version::version(args).await,
     }
 }
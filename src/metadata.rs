@@ -0,0 +1,101 @@
+//! Identify the CLI in config service logs. Every request carries a
+//! `user-agent` naming the CLI and its version, and any `--header` metadata,
+//! so operators can attribute traffic and automation runs can be tagged.
+
+use crate::Result;
+use anyhow::anyhow;
+use std::sync::{Arc, OnceLock};
+use tonic::{
+    metadata::{AsciiMetadataKey, AsciiMetadataValue},
+    service::Interceptor,
+    Request, Status,
+};
+
+pub const USER_AGENT: &str = concat!("helium-config-service-cli/", env!("CARGO_PKG_VERSION"));
+
+pub type Header = (AsciiMetadataKey, AsciiMetadataValue);
+
+static HEADERS: OnceLock<Arc<Vec<Header>>> = OnceLock::new();
+
+/// Send `headers` with every request of clients created after this call.
+pub fn set_headers(headers: Vec<Header>) -> Result {
+    HEADERS
+        .set(Arc::new(headers))
+        .map_err(|_| anyhow!("request headers are already set"))
+}
+
+/// Parse a `key=value` header.
+pub fn parse_header(s: &str) -> Result<Header> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected `key=value`, got {s}"))?;
+    let key = AsciiMetadataKey::from_bytes(key.trim().as_bytes())
+        .map_err(|_| anyhow!("invalid header name {key:?}"))?;
+    if key.as_str() == "user-agent" || key.as_str().starts_with("grpc-") {
+        return Err(anyhow!("header {key} is reserved"));
+    }
+    let value = AsciiMetadataValue::try_from(value.trim())
+        .map_err(|_| anyhow!("invalid value for header {key}"))?;
+    Ok((key, value))
+}
+
+/// Adds the `--header` metadata to each request. The user agent is set on
+/// the channel, gRPC does not allow it as request metadata.
+#[derive(Clone, Default)]
+pub struct RequestMetadata {
+    headers: Arc<Vec<Header>>,
+}
+
+impl RequestMetadata {
+    /// The headers given to [`set_headers`].
+    pub fn current() -> Self {
+        Self {
+            headers: HEADERS.get().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl Interceptor for RequestMetadata {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        for (key, value) in self.headers.iter() {
+            request.metadata_mut().insert(key.clone(), value.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_header, RequestMetadata};
+    use std::sync::Arc;
+    use tonic::{service::Interceptor, Request};
+
+    #[test]
+    fn headers_are_parsed() {
+        let (key, value) = parse_header("X-Run-Id = nightly-42").unwrap();
+        assert_eq!("x-run-id", key.as_str());
+        assert_eq!("nightly-42", value.to_str().unwrap());
+
+        assert!(parse_header("x-run-id").is_err());
+        assert!(parse_header("bad key=1").is_err());
+        assert!(parse_header("user-agent=me").is_err());
+        assert!(parse_header("grpc-timeout=1S").is_err());
+    }
+
+    #[test]
+    fn headers_are_attached() {
+        let mut interceptor = RequestMetadata {
+            headers: Arc::new(vec![parse_header("x-run-id=nightly-42").unwrap()]),
+        };
+        let request = interceptor.call(Request::new(())).unwrap();
+        assert_eq!(
+            "nightly-42",
+            request
+                .metadata()
+                .get("x-run-id")
+                .unwrap()
+                .to_str()
+                .unwrap()
+        );
+    }
+}
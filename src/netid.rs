@@ -0,0 +1,78 @@
+use crate::{hex_field, subnet::DevaddrConstraint, NetId, Result};
+use anyhow::anyhow;
+
+/// Bits of the NetID that make up the devaddr NwkID, indexed by NetID type.
+const NWK_ID_BITS: [u32; 8] = [6, 6, 9, 11, 12, 13, 15, 17];
+
+/// The type of a NetID, from its 3 most significant bits.
+pub fn net_id_type(net_id: NetId) -> Result<u32> {
+    if net_id > 0xFF_FFFF {
+        return Err(anyhow!("net_id {net_id:X} is wider than 24 bits"));
+    }
+    Ok(net_id >> 21)
+}
+
+/// Every devaddr a NetID may assign, per the LoRaWAN Backend Interfaces
+/// devaddr layout.
+///
+/// A devaddr is a prefix of `type` 1 bits and a 0, the NwkID taken from the
+/// low bits of the NetID, then the NwkAddr.
+pub fn devaddr_range(net_id: NetId) -> Result<DevaddrConstraint> {
+    let net_type = net_id_type(net_id)?;
+    let nwk_id_bits = NWK_ID_BITS[net_type as usize];
+    let prefix_bits = net_type + 1;
+    let nwk_addr_bits = 32 - prefix_bits - nwk_id_bits;
+
+    let prefix = match net_type {
+        0 => 0,
+        _ => (u32::MAX << (32 - net_type)) as u64,
+    };
+    let nwk_id = (net_id & ((1 << nwk_id_bits) - 1)) as u64;
+    let start = prefix | (nwk_id << nwk_addr_bits);
+    let end = start | ((1u64 << nwk_addr_bits) - 1);
+
+    DevaddrConstraint::new(hex_field::devaddr(start), hex_field::devaddr(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{devaddr_range, net_id_type};
+    use crate::hex_field;
+
+    #[test]
+    fn helium_net_id_ranges() {
+        let range = |net_id| {
+            let range = devaddr_range(net_id).unwrap();
+            (range.start_addr, range.end_addr)
+        };
+        assert_eq!(
+            (
+                hex_field::devaddr(0x7800_0000),
+                hex_field::devaddr(0x79FF_FFFF)
+            ),
+            range(0x00_003C)
+        );
+        assert_eq!(
+            (
+                hex_field::devaddr(0xE05A_0000),
+                hex_field::devaddr(0xE05B_FFFF)
+            ),
+            range(0x60_002D)
+        );
+        assert_eq!(
+            (
+                hex_field::devaddr(0xFC01_4C00),
+                hex_field::devaddr(0xFC01_4FFF)
+            ),
+            range(0xC0_0053)
+        );
+    }
+
+    #[test]
+    fn net_id_types() {
+        assert_eq!(0, net_id_type(0x00_003C).unwrap());
+        assert_eq!(3, net_id_type(0x60_002D).unwrap());
+        assert_eq!(7, net_id_type(0xE0_0001).unwrap());
+        assert!(net_id_type(0x100_0000).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    eui_wildcard_warning, warning, DevaddrUpdate, EuiUpdate, Result, SkfUpdate, UpdateAction,
+};
+use anyhow::anyhow;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+/// A record from a file-driven bulk command.
+pub trait BulkUpdate: Clone + Debug + Eq + Hash {
+    type Key: Eq + Hash;
+
+    /// Updates sharing a key, but differing otherwise, conflict.
+    fn key(&self) -> Self::Key;
+
+    /// Problems worth reporting that do not prevent submission.
+    fn warnings(_updates: &[Self]) -> Vec<String> {
+        vec![]
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Normalized<T> {
+    pub updates: Vec<T>,
+    /// Exact duplicates removed from the input.
+    pub dropped: Vec<T>,
+    pub warnings: Vec<String>,
+}
+
+impl<T> Normalized<T> {
+    /// Human readable summary of what normalization dropped. Warnings are
+    /// written to stderr.
+    pub fn report(&self) -> Result<Vec<String>>
+    where
+        T: Debug,
+    {
+        for warning in &self.warnings {
+            warning::warn(warning)?;
+        }
+        Ok(self
+            .dropped
+            .iter()
+            .map(|update| format!("dropped duplicate {update:?}"))
+            .collect())
+    }
+}
+
+/// Remove exact duplicates, keeping the first occurrence and input order.
+///
+/// Hex fields are already normalized in case and width by parsing into `HexField`.
+/// Errors listing every conflict when two updates share a key but disagree.
+pub fn normalize<T: BulkUpdate>(updates: Vec<T>) -> Result<Normalized<T>> {
+    let mut seen = HashSet::new();
+    let mut by_key: HashMap<T::Key, T> = HashMap::new();
+    let mut kept = vec![];
+    let mut dropped = vec![];
+    let mut conflicts = vec![];
+
+    for update in updates {
+        if !seen.insert(update.clone()) {
+            dropped.push(update);
+            continue;
+        }
+        match by_key.get(&update.key()) {
+            Some(existing) => conflicts.push(format!("{existing:?} conflicts with {update:?}")),
+            None => {
+                by_key.insert(update.key(), update.clone());
+                kept.push(update);
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(anyhow!("conflicting updates:\n{}", conflicts.join("\n")));
+    }
+
+    let warnings = T::warnings(&kept);
+    Ok(Normalized {
+        updates: kept,
+        dropped,
+        warnings,
+    })
+}
+
+impl BulkUpdate for EuiUpdate {
+    type Key = (u64, u64);
+
+    fn key(&self) -> Self::Key {
+        (self.app_eui.0, self.dev_eui.0)
+    }
+
+    fn warnings(updates: &[Self]) -> Vec<String> {
+        updates
+            .iter()
+            .filter(|update| update.action == UpdateAction::Add)
+            .filter_map(|update| eui_wildcard_warning(update.app_eui, update.dev_eui))
+            .collect()
+    }
+}
+
+impl BulkUpdate for SkfUpdate {
+    type Key = (u64, String);
+
+    fn key(&self) -> Self::Key {
+        (self.devaddr.0, self.session_key.to_lowercase())
+    }
+}
+
+impl BulkUpdate for DevaddrUpdate {
+    type Key = (u64, u64);
+
+    fn key(&self) -> Self::Key {
+        (self.start_addr.0, self.end_addr.0)
+    }
+
+    fn warnings(updates: &[Self]) -> Vec<String> {
+        let mut added: Vec<&DevaddrUpdate> = updates
+            .iter()
+            .filter(|update| update.action == UpdateAction::Add)
+            .collect();
+        added.sort_by_key(|update| update.key());
+
+        added
+            .windows(2)
+            .filter(|pair| pair[1].start_addr <= pair[0].end_addr)
+            .map(|pair| {
+                format!(
+                    "overlapping ranges {} - {} and {} - {}",
+                    pair[0].start_addr, pair[0].end_addr, pair[1].start_addr, pair[1].end_addr
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+    use crate::{hex_field, DevaddrUpdate, EuiUpdate, UpdateAction};
+
+    fn eui(app_eui: u64, dev_eui: u64, action: UpdateAction) -> EuiUpdate {
+        EuiUpdate {
+            app_eui: hex_field::eui(app_eui),
+            dev_eui: hex_field::eui(dev_eui),
+            action,
+        }
+    }
+
+    fn devaddr(start: u64, end: u64, action: UpdateAction) -> DevaddrUpdate {
+        DevaddrUpdate {
+            start_addr: hex_field::devaddr(start),
+            end_addr: hex_field::devaddr(end),
+            action,
+        }
+    }
+
+    #[test]
+    fn drops_exact_duplicates() {
+        let parsed: Vec<EuiUpdate> = serde_json::from_str(
+            r#"[
+                {"app_eui": "00000000000000AB", "dev_eui": "0000000000000001", "action": "add"},
+                {"app_eui": "00000000000000ab", "dev_eui": "0000000000000001", "action": "Add"},
+                {"app_eui": "00000000000000AB", "dev_eui": "0000000000000002", "action": "add"}
+            ]"#,
+        )
+        .unwrap();
+
+        let normalized = normalize(parsed).unwrap();
+        assert_eq!(
+            vec![
+                eui(0xAB, 1, UpdateAction::Add),
+                eui(0xAB, 2, UpdateAction::Add)
+            ],
+            normalized.updates
+        );
+        assert_eq!(vec![eui(0xAB, 1, UpdateAction::Add)], normalized.dropped);
+    }
+
+    #[test]
+    fn conflicting_actions_error() {
+        let updates = vec![
+            eui(1, 1, UpdateAction::Add),
+            eui(1, 1, UpdateAction::Remove),
+        ];
+        assert!(normalize(updates).is_err());
+    }
+
+    #[test]
+    fn added_wildcard_euis_warn() {
+        let updates = vec![
+            eui(0, 1, UpdateAction::Add),
+            eui(0, 0, UpdateAction::Remove),
+            eui(1, 1, UpdateAction::Add),
+        ];
+        let normalized = normalize(updates).unwrap();
+        assert_eq!(
+            vec!["app_eui is a wildcard, the route will match joins from dev_eui 0000000000000001 with any app_eui".to_string()],
+            normalized.warnings
+        );
+    }
+
+    #[test]
+    fn overlapping_devaddr_ranges_warn() {
+        let updates = vec![
+            devaddr(0x10, 0x1F, UpdateAction::Add),
+            devaddr(0x18, 0x2F, UpdateAction::Add),
+            devaddr(0x30, 0x3F, UpdateAction::Add),
+        ];
+        let normalized = normalize(updates).unwrap();
+        assert_eq!(3, normalized.updates.len());
+        assert_eq!(
+            vec!["overlapping ranges 00000010 - 0000001F and 00000018 - 0000002F".to_string()],
+            normalized.warnings
+        );
+    }
+}
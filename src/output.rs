@@ -0,0 +1,232 @@
+//! Where the result of a command is written. Results go to stdout unless
+//! `--quiet`, and are also appended to `--output-file` and sent to syslog
+//! with `--syslog`, so the outcome of a long running command survives a
+//! closed terminal.
+
+use crate::{Msg, Result};
+use anyhow::{anyhow, Context};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+pub trait OutputSink {
+    fn write(&mut self, msg: &Msg) -> Result;
+}
+
+/// A closed stdout, e.g. piped into `head`, is not an error: the other sinks
+/// have already been written.
+pub struct Stdout;
+
+impl OutputSink for Stdout {
+    fn write(&mut self, msg: &Msg) -> Result {
+        match writeln!(io::stdout().lock(), "{msg}") {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            written => Ok(written?),
+        }
+    }
+}
+
+/// Appends each result to a file with the time it was written, synced to
+/// disk before the command exits.
+pub struct FileSink {
+    file: File,
+}
+
+impl FileSink {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("opening output file {}", path.display()))?;
+        Ok(Self { file })
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write(&mut self, msg: &Msg) -> Result {
+        let now = humantime::format_rfc3339_seconds(SystemTime::now());
+        writeln!(self.file, "[{now}] {msg}")?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// The local syslog daemon, or journald through its syslog socket.
+#[cfg(unix)]
+pub struct Syslog {
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+#[cfg(unix)]
+impl Syslog {
+    /// `/dev/log` on Linux, `/var/run/syslog` on macOS.
+    const SOCKETS: [&'static str; 2] = ["/dev/log", "/var/run/syslog"];
+    /// The `user` facility
+    const FACILITY: u8 = 1;
+    /// The RFC 3164 limit, longer messages are split
+    const MAX_MESSAGE: usize = 1024;
+
+    pub fn connect() -> Result<Self> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        Self::SOCKETS
+            .iter()
+            .find(|path| socket.connect(path).is_ok())
+            .ok_or_else(|| anyhow!("no syslog socket at {}", Self::SOCKETS.join(" or ")))?;
+        Ok(Self { socket })
+    }
+}
+
+#[cfg(unix)]
+impl OutputSink for Syslog {
+    fn write(&mut self, msg: &Msg) -> Result {
+        for line in syslog_lines(Self::FACILITY, msg, Self::MAX_MESSAGE) {
+            self.socket
+                .send(line.as_bytes())
+                .context("sending to syslog")?;
+        }
+        Ok(())
+    }
+}
+
+/// RFC 3164 messages without the optional timestamp and hostname, which the
+/// syslog daemon fills in. A result longer than `max_len` is split over
+/// several messages, at a line break where there is one.
+fn syslog_lines(facility: u8, msg: &Msg, max_len: usize) -> Vec<String> {
+    // err and info severities
+    let severity = match msg {
+        Msg::Error(_) => 3,
+        _ => 6,
+    };
+    let header = format!(
+        "<{}>helium-config-cli[{}]: ",
+        facility * 8 + severity,
+        std::process::id()
+    );
+    let room = max_len.saturating_sub(header.len()).max(1);
+    let body = msg.to_string();
+    let mut rest = body.as_str();
+    let mut lines = vec![];
+    while rest.len() > room {
+        let mut end = room;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(newline) = rest[..end].rfind('\n').filter(|newline| *newline > 0) {
+            end = newline + 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(1, char::len_utf8);
+        }
+        lines.push(format!("{header}{}", rest[..end].trim_end_matches('\n')));
+        rest = &rest[end..];
+    }
+    lines.push(format!("{header}{rest}"));
+    lines
+}
+
+/// Writes each result to every sink, attempting all of them before reporting
+/// the first failure.
+pub struct Outputs {
+    stdout: Option<Stdout>,
+    sinks: Vec<Box<dyn OutputSink>>,
+}
+
+impl Outputs {
+    pub fn new(quiet: bool, output_file: Option<&Path>, syslog: bool) -> Result<Self> {
+        let mut sinks: Vec<Box<dyn OutputSink>> = vec![];
+        if let Some(path) = output_file {
+            sinks.push(Box::new(FileSink::open(path)?));
+        }
+        if syslog {
+            #[cfg(unix)]
+            sinks.push(Box::new(Syslog::connect()?));
+            #[cfg(not(unix))]
+            return Err(anyhow!("--syslog is only supported on unix"));
+        }
+        Ok(Self {
+            stdout: (!quiet).then_some(Stdout),
+            sinks,
+        })
+    }
+
+    /// Record a command that failed in the file and syslog sinks, the error
+    /// itself is printed to stderr.
+    pub fn write_failure(&mut self, err: &anyhow::Error) -> Result {
+        let msg = Msg::Error(format!("{err:#}"));
+        let results: Vec<Result> = self.sinks.iter_mut().map(|sink| sink.write(&msg)).collect();
+        results.into_iter().collect()
+    }
+}
+
+/// The file and syslog sinks are written first, so the result is recorded
+/// even if writing to stdout fails.
+impl OutputSink for Outputs {
+    fn write(&mut self, msg: &Msg) -> Result {
+        let mut results: Vec<Result> = self.sinks.iter_mut().map(|sink| sink.write(msg)).collect();
+        if let Some(stdout) = self.stdout.as_mut() {
+            results.push(stdout.write(msg));
+        }
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{syslog_lines, FileSink, OutputSink};
+    use crate::Msg;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn file_sink_appends() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("results.log");
+        FileSink::open(&path)
+            .unwrap()
+            .write(&Msg::Success("first".to_string()))
+            .unwrap();
+        FileSink::open(&path)
+            .unwrap()
+            .write(&Msg::Error("second".to_string()))
+            .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = written.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].ends_with("] first"));
+        assert!(lines[1].ends_with("] \u{2717} second"));
+    }
+
+    #[test]
+    fn syslog_priorities() {
+        let pid = std::process::id();
+        assert_eq!(
+            vec![format!("<14>helium-config-cli[{pid}]: done")],
+            syslog_lines(1, &Msg::Success("done".to_string()), 1024)
+        );
+        assert!(syslog_lines(1, &Msg::Error("failed".to_string()), 1024)[0].starts_with("<11>"));
+    }
+
+    #[test]
+    fn syslog_splits_long_results() {
+        let header = format!("<14>helium-config-cli[{}]: ", std::process::id());
+        let max_len = header.len() + 10;
+        let lines = syslog_lines(
+            1,
+            &Msg::Success("first\nsecond line\né".to_string()),
+            max_len,
+        );
+        assert_eq!(
+            vec![
+                format!("{header}first"),
+                format!("{header}second lin"),
+                format!("{header}e\né"),
+            ],
+            lines
+        );
+        assert!(lines.iter().all(|line| line.len() <= max_len));
+    }
+}
@@ -0,0 +1,227 @@
+//! Profiles of per-OUI defaults, for operators managing Orgs with different
+//! settings. A profiles file maps profile names to the defaults of each OUI:
+//!
+//! ```json
+//! {
+//!   "default": {
+//!     "ouis": {
+//!       "1": {
+//!         "net_id": "00003C",
+//!         "max_copies": 3,
+//!         "route_template": "routes/customer-a.json",
+//!         "solana_url": "https://api.mainnet-beta.solana.com"
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! When a command's `--oui`, or `HELIUM_OUI`, matches an OUI of the selected
+//! profile, its defaults are applied through the environment variables the
+//! arguments already read, so flags and the environment take precedence.
+
+use crate::{
+    cmds::{ENV_MAX_COPIES, ENV_NET_ID, ENV_ROUTE_TEMPLATE, ENV_SOLANA_URL},
+    hex_field::HexNetID,
+    Oui, Result,
+};
+use anyhow::{anyhow, Context};
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Used when `HELIUM_PROFILES` is not set, relative to `$HOME`.
+const DEFAULT_PROFILES_FILE: &str = ".config/helium-config-cli/profiles.json";
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+static APPLIED: OnceLock<Applied> = OnceLock::new();
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(default)]
+    pub ouis: BTreeMap<Oui, OuiDefaults>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OuiDefaults {
+    pub net_id: Option<HexNetID>,
+    pub max_copies: Option<u32>,
+    /// Route file `route new` creates from
+    pub route_template: Option<PathBuf>,
+    pub solana_url: Option<String>,
+}
+
+impl OuiDefaults {
+    /// The defaults as the environment variables arguments read.
+    fn env(&self) -> Vec<(&'static str, String)> {
+        [
+            (ENV_NET_ID, self.net_id.map(|net_id| net_id.to_string())),
+            (ENV_MAX_COPIES, self.max_copies.map(|max| max.to_string())),
+            (
+                ENV_ROUTE_TEMPLATE,
+                self.route_template
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+            (ENV_SOLANA_URL, self.solana_url.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(var, value)| Some((var, value?)))
+        .collect()
+    }
+}
+
+/// What the selected profile contributed, shown by `env info`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Applied {
+    pub profile: String,
+    /// `None` when there is no profiles file
+    pub file: Option<PathBuf>,
+    /// The OUI of the command when it has defaults in the profile
+    pub oui: Option<Oui>,
+    /// Environment variables set from the profile
+    pub defaults: BTreeMap<&'static str, String>,
+}
+
+/// The profile applied to this invocation, once [`apply`] has run.
+pub fn applied() -> Option<&'static Applied> {
+    APPLIED.get()
+}
+
+/// Set the defaults of the selected profile for the OUI in `args`. `args` that
+/// do not parse are left for the real parse to report.
+///
+/// Modifying the environment is only sound while the process has a single
+/// thread, so this runs before the tokio runtime is built.
+pub fn apply(path: Option<PathBuf>, args: &[OsString], command: clap::Command) -> Result {
+    let Ok(matches) = command.try_get_matches_from(args) else {
+        return Ok(());
+    };
+    let name = matches
+        .get_one::<String>("profile")
+        .map_or(DEFAULT_PROFILE, String::as_str);
+    let (file, profile) = load(path, name)?;
+    let mut applied = Applied {
+        profile: name.to_string(),
+        file,
+        oui: None,
+        defaults: BTreeMap::new(),
+    };
+    if let Some((oui, defaults)) =
+        matched_oui(&matches).and_then(|oui| Some((oui, profile.ouis.get(&oui)?)))
+    {
+        applied.oui = Some(oui);
+        applied.defaults = unset_defaults(defaults, |var| std::env::var_os(var).is_some());
+        for (var, value) in &applied.defaults {
+            std::env::set_var(var, value);
+        }
+    }
+    APPLIED
+        .set(applied)
+        .map_err(|_| anyhow!("profile is already applied"))
+}
+
+/// The profile `name` from `path`, or the default profiles file when it
+/// exists. Only the default profile may be missing.
+fn load(path: Option<PathBuf>, name: &str) -> Result<(Option<PathBuf>, Profile)> {
+    let path = match path {
+        Some(path) => Some(path),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(DEFAULT_PROFILES_FILE))
+            .filter(|path| path.exists()),
+    };
+    let mut profiles = match &path {
+        Some(path) => read(path)?,
+        None => BTreeMap::new(),
+    };
+    match profiles.remove(name) {
+        Some(profile) => Ok((path, profile)),
+        None if name == DEFAULT_PROFILE => Ok((path, Profile::default())),
+        None => Err(anyhow!(
+            "no profile {name} in {}",
+            path.map_or("profiles".to_string(), |path| path.display().to_string())
+        )),
+    }
+}
+
+fn read(path: &Path) -> Result<BTreeMap<String, Profile>> {
+    let data =
+        std::fs::read_to_string(path).context(format!("reading profiles {}", path.display()))?;
+    serde_json::from_str(&data).context(format!("in profiles {}", path.display()))
+}
+
+/// The `--oui` of the subcommand that will run.
+fn matched_oui(matches: &ArgMatches) -> Option<Oui> {
+    match matches.subcommand() {
+        Some((_, matches)) => matched_oui(matches),
+        None => matches.try_get_one::<Oui>("oui").ok().flatten().copied(),
+    }
+}
+
+fn unset_defaults(
+    defaults: &OuiDefaults,
+    is_set: impl Fn(&str) -> bool,
+) -> BTreeMap<&'static str, String> {
+    defaults
+        .env()
+        .into_iter()
+        .filter(|(var, _)| !is_set(var))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matched_oui, read, unset_defaults};
+    use crate::{
+        cmds::{Cli, ENV_MAX_COPIES, ENV_NET_ID, ENV_SOLANA_URL},
+        hex_field,
+    };
+    use clap::CommandFactory;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn oui_defaults() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("profiles.json");
+        std::fs::write(
+            &path,
+            r#"{"acme": {"ouis": {"7": {"net_id": "00003C", "max_copies": 3, "solana_url": "http://rpc"}}}}"#,
+        )
+        .unwrap();
+        let profiles = read(&path).unwrap();
+        let defaults = &profiles["acme"].ouis[&7];
+        assert_eq!(Some(hex_field::net_id(0x3C)), defaults.net_id);
+
+        let unset = unset_defaults(defaults, |var| var == ENV_SOLANA_URL);
+        assert_eq!(
+            vec![(ENV_MAX_COPIES, "3"), (ENV_NET_ID, "00003C")],
+            unset
+                .iter()
+                .map(|(var, value)| (*var, value.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        std::fs::write(&path, r#"{"acme": {"ouis": {"7": {"copies": 3}}}}"#).unwrap();
+        assert!(read(&path).is_err());
+    }
+
+    #[test]
+    fn oui_of_the_subcommand() {
+        let matches = Cli::command()
+            .try_get_matches_from(["cli", "route", "list", "--oui", "7"])
+            .unwrap();
+        assert_eq!(Some(7), matched_oui(&matches));
+        let matches = Cli::command()
+            .try_get_matches_from(["cli", "env", "init"])
+            .unwrap();
+        assert_eq!(None, matched_oui(&matches));
+    }
+}
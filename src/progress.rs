@@ -0,0 +1,122 @@
+use serde::Serialize;
+use std::{fmt::Display, time::Duration};
+use tokio::sync::mpsc;
+
+/// Progress of a long running operation, for callers embedding the clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// `done` of `total` items of a bulk operation have been sent
+    Chunk {
+        operation: &'static str,
+        done: usize,
+        total: usize,
+    },
+    /// The config service is rate limiting, the next attempt waits for `delay`
+    RateLimited { delay: Duration, error: String },
+    /// `--adaptive-chunking` changed the items sent per request
+    ChunkResized { from: usize, to: usize },
+}
+
+impl Display for ProgressEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Chunk {
+                operation,
+                done,
+                total,
+            } => write!(f, "{operation}: {done}/{total}"),
+            Self::RateLimited { delay, error } => {
+                write!(f, "rate limited, retrying in {delay:?}: {error}")
+            }
+            Self::ChunkResized { from, to } => write!(f, "chunk size {from} -> {to}"),
+        }
+    }
+}
+
+/// Receives [`ProgressEvent`]s instead of them being printed.
+pub trait ProgressSink: Send + Sync {
+    fn event(&self, event: ProgressEvent);
+}
+
+/// Writes events to stderr, the default for the CLI.
+pub struct StderrProgress;
+
+impl ProgressSink for StderrProgress {
+    fn event(&self, event: ProgressEvent) {
+        eprintln!("{event}");
+    }
+}
+
+/// Drops every event.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn event(&self, _event: ProgressEvent) {}
+}
+
+/// Events are dropped once the receiver is closed.
+impl ProgressSink for mpsc::UnboundedSender<ProgressEvent> {
+    fn event(&self, event: ProgressEvent) {
+        let _ = self.send(event);
+    }
+}
+
+impl<F: Fn(ProgressEvent) + Send + Sync> ProgressSink for F {
+    fn event(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProgressEvent, ProgressSink};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tokio::sync::mpsc;
+
+    fn chunk(done: usize) -> ProgressEvent {
+        ProgressEvent::Chunk {
+            operation: "copy euis",
+            done,
+            total: 10,
+        }
+    }
+
+    #[test]
+    fn sinks_receive_events() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let callback = {
+            let seen = seen.clone();
+            move |event: ProgressEvent| seen.lock().unwrap().push(event)
+        };
+        callback.event(chunk(5));
+        assert_eq!(vec![chunk(5)], *seen.lock().unwrap());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tx.event(chunk(10));
+        assert_eq!(Ok(chunk(10)), rx.try_recv());
+        drop(rx);
+        // a closed receiver does not fail the operation
+        tx.event(chunk(10));
+    }
+
+    #[test]
+    fn events_display_and_serialize() {
+        assert_eq!("copy euis: 5/10", chunk(5).to_string());
+        let limited = ProgressEvent::RateLimited {
+            delay: Duration::from_millis(250),
+            error: "slow down".to_string(),
+        };
+        assert_eq!(
+            "rate limited, retrying in 250ms: slow down",
+            limited.to_string()
+        );
+        assert_eq!(
+            r#"{"event":"chunk","operation":"copy euis","done":5,"total":10}"#,
+            serde_json::to_string(&chunk(5)).unwrap()
+        );
+    }
+}
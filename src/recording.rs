@@ -0,0 +1,541 @@
+//! Record config service exchanges to disk and replay them later, so scripts
+//! wrapping the CLI can be tested without a config service.
+//!
+//! Every client dials through a [`Transport`]. When recording, each request
+//! and its response are written to their own file once the response ends,
+//! or is dropped, e.g. a stream that is never closed. Responses are passed on
+//! as they arrive, so streaming methods work while recording. When replaying,
+//! nothing is dialed and responses are served from those files, matched by
+//! method and request contents.
+//!
+//! Signatures, signers, and timestamps are cleared from recorded requests.
+//! They change every run, and replayed requests are matched without them.
+//! Responses are kept whole so their signatures still verify.
+
+use crate::{metadata::USER_AGENT, telemetry, warning, Result};
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use helium_proto::{
+    services::iot_config::{
+        AdminAddKeyReqV1, AdminLoadRegionReqV1, AdminRemoveKeyReqV1, GatewayInfoReqV1,
+        GatewayLocationReqV1, OrgCreateHeliumReqV1, OrgCreateRoamerReqV1, OrgEnableReqV1,
+        OrgUpdateReqV1, RouteCreateReqV1, RouteDeleteReqV1, RouteGetDevaddrRangesReqV1,
+        RouteGetEuisReqV1, RouteGetReqV1, RouteListReqV1, RouteSkfGetReqV1, RouteSkfListReqV1,
+        RouteSkfUpdateReqV1, RouteStreamReqV1, RouteUpdateDevaddrRangesReqV1, RouteUpdateEuisReqV1,
+        RouteUpdateReqV1,
+    },
+    Message,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    task::{Context as TaskContext, Poll},
+};
+use tonic::{
+    body::BoxBody,
+    codegen::{
+        http::{self, HeaderMap, HeaderName, HeaderValue},
+        Body, BoxFuture, Bytes, Service, StdError,
+    },
+    transport::{Channel, Endpoint},
+    Status,
+};
+use tracing::Instrument;
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+enum Mode {
+    Record(Arc<Recorder>),
+    Replay(Arc<Replay>),
+}
+
+/// Record every exchange of clients created after this call into `dir`.
+pub fn record_to(dir: &Path) -> Result {
+    std::fs::create_dir_all(dir).context(format!("creating {}", dir.display()))?;
+    let recorder = Recorder {
+        dir: dir.to_path_buf(),
+        next: AtomicUsize::new(0),
+    };
+    set_mode(Mode::Record(Arc::new(recorder)))
+}
+
+/// Serve clients created after this call from the exchanges recorded in `dir`.
+pub fn replay_from(dir: &Path) -> Result {
+    set_mode(Mode::Replay(Arc::new(Replay::load(dir)?)))
+}
+
+fn set_mode(mode: Mode) -> Result {
+    MODE.set(mode)
+        .map_err(|_| anyhow!("recording or replaying is already set up"))
+}
+
+/// The channel clients send requests over.
+#[derive(Clone)]
+pub enum Transport {
+    Live(Channel),
+    Record(Channel, Arc<Recorder>),
+    Replay(Arc<Replay>),
+}
+
+impl Transport {
+    /// Dial `host`, unless replaying.
+    pub async fn connect(host: &str) -> Result<Self> {
+        match MODE.get() {
+            Some(Mode::Replay(replay)) => Ok(Self::Replay(replay.clone())),
+            Some(Mode::Record(recorder)) => Ok(Self::Record(dial(host).await?, recorder.clone())),
+            None => Ok(Self::Live(dial(host).await?)),
+        }
+    }
+}
+
+async fn dial(host: &str) -> Result<Channel> {
+    Ok(Endpoint::from_shared(host.to_owned())?
+        .user_agent(USER_AGENT)?
+        .connect()
+        .await?)
+}
+
+impl Service<http::Request<BoxBody>> for Transport {
+    type Response = http::Response<BoxBody>;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        match self {
+            Self::Live(channel) | Self::Record(channel, _) => {
+                channel.poll_ready(cx).map_err(Into::into)
+            }
+            Self::Replay(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn call(&mut self, mut request: http::Request<BoxBody>) -> Self::Future {
+        let span = telemetry::rpc_span(request.uri().path());
+        telemetry::inject(&span, request.headers_mut());
+        // The channel polled ready is the one that must send the request
+        let clone = self.clone();
+        let response: Self::Future = match std::mem::replace(self, clone) {
+            Self::Live(mut channel) => {
+                let response = channel.call(request);
+                Box::pin(async move {
+                    let response = response.await?;
+                    Ok(response.map(|body| {
+                        body.map_err(|err| Status::from_error(Box::new(err)))
+                            .boxed_unsync()
+                    }))
+                })
+            }
+            Self::Record(channel, recorder) => Box::pin(recorder.exchange(channel, request)),
+            Self::Replay(replay) => Box::pin(async move { replay.respond(request).await }),
+        };
+        Box::pin(response.instrument(span))
+    }
+}
+
+/// One request and the response it got, as stored on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    path: String,
+    /// Base64 protobuf request messages with volatile fields cleared
+    request: Vec<String>,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Base64 grpc framed response body
+    body: String,
+    trailers: Vec<(String, String)>,
+}
+
+pub struct Recorder {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl Recorder {
+    async fn exchange(
+        self: Arc<Self>,
+        mut channel: Channel,
+        request: http::Request<BoxBody>,
+    ) -> Result<http::Response<BoxBody>, StdError> {
+        let (parts, body) = request.into_parts();
+        let (request_body, _) = collect(body).await?;
+        let path = parts.uri.path().to_string();
+        let request_messages = redact_frames(&path, &request_body)?;
+
+        // numbered when sent, so identical requests replay in the same order
+        let seq = self.next.fetch_add(1, Ordering::SeqCst);
+        let request = http::Request::from_parts(parts, RecordedBody::new(request_body, None));
+        let response = channel.call(request).await?;
+        let (parts, body) = response.into_parts();
+
+        let exchange = Exchange {
+            path,
+            request: request_messages,
+            status: parts.status.as_u16(),
+            headers: header_pairs(&parts.headers),
+            body: String::new(),
+            trailers: vec![],
+        };
+        let body = TeeBody {
+            inner: body
+                .map_err(|err| Status::from_error(Box::new(err)))
+                .boxed_unsync(),
+            recorder: self,
+            seq,
+            exchange: Some(exchange),
+            data: vec![],
+        };
+        Ok(http::Response::from_parts(parts, body.boxed_unsync()))
+    }
+
+    fn save(&self, seq: usize, exchange: &Exchange) -> Result {
+        let method = exchange
+            .path
+            .trim_start_matches('/')
+            .replace(['/', '.'], "-");
+        let path = self.dir.join(format!("{seq:05}-{method}.json"));
+        std::fs::write(&path, serde_json::to_string_pretty(exchange)?)
+            .context(format!("writing {}", path.display()))
+    }
+}
+
+type ReplayKey = (String, Vec<String>);
+
+pub struct Replay {
+    /// Identical requests are answered in the order they were recorded
+    exchanges: Mutex<HashMap<ReplayKey, VecDeque<Exchange>>>,
+}
+
+impl Replay {
+    fn load(dir: &Path) -> Result<Self> {
+        let mut paths = std::fs::read_dir(dir)
+            .context(format!("reading recordings in {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        paths.sort();
+
+        let mut exchanges: HashMap<ReplayKey, VecDeque<Exchange>> = HashMap::new();
+        for path in paths {
+            let data = std::fs::read_to_string(&path)?;
+            let exchange: Exchange =
+                serde_json::from_str(&data).context(format!("parsing {}", path.display()))?;
+            exchanges
+                .entry((exchange.path.clone(), exchange.request.clone()))
+                .or_default()
+                .push_back(exchange);
+        }
+        Ok(Self {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    async fn respond(
+        &self,
+        request: http::Request<BoxBody>,
+    ) -> Result<http::Response<BoxBody>, StdError> {
+        let (parts, body) = request.into_parts();
+        let (request_body, _) = collect(body).await?;
+        let path = parts.uri.path().to_string();
+        let key = (path.clone(), redact_frames(&path, &request_body)?);
+
+        let exchange = self
+            .exchanges
+            .lock()
+            .expect("replay lock poisoned")
+            .get_mut(&key)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| anyhow!("no recorded response left for {path} with this request"))?;
+
+        let mut response = http::Response::builder().status(exchange.status);
+        for (name, value) in &exchange.headers {
+            response = response.header(name, value);
+        }
+        let trailers = (!exchange.trailers.is_empty())
+            .then(|| header_map(&exchange.trailers))
+            .transpose()?;
+        let body = RecordedBody::new(STANDARD.decode(&exchange.body)?.into(), trailers);
+        Ok(response.body(body)?)
+    }
+}
+
+/// A response body passed on frame by frame while a copy is kept, which is
+/// saved with the trailers or when the body is dropped before them.
+struct TeeBody {
+    inner: BoxBody,
+    recorder: Arc<Recorder>,
+    seq: usize,
+    /// Taken once saved
+    exchange: Option<Exchange>,
+    data: Vec<u8>,
+}
+
+impl TeeBody {
+    fn save(&mut self, trailers: Option<&HeaderMap>) -> Result {
+        let Some(mut exchange) = self.exchange.take() else {
+            return Ok(());
+        };
+        exchange.body = STANDARD.encode(&self.data);
+        exchange.trailers = trailers.map(header_pairs).unwrap_or_default();
+        self.recorder.save(self.seq, &exchange)
+    }
+}
+
+impl Body for TeeBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Bytes, Status>>> {
+        let polled = Pin::new(&mut self.inner).poll_data(cx);
+        if let Poll::Ready(Some(Ok(data))) = &polled {
+            self.data.extend_from_slice(data);
+        }
+        polled
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Status>> {
+        let polled = Pin::new(&mut self.inner).poll_trailers(cx);
+        if let Poll::Ready(Ok(trailers)) = &polled {
+            if let Err(err) = self.save(trailers.as_ref()) {
+                return Poll::Ready(Err(Status::internal(format!(
+                    "recording exchange: {err:#}"
+                ))));
+            }
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+impl Drop for TeeBody {
+    fn drop(&mut self) {
+        if let Err(err) = self.save(None) {
+            // a drop cannot fail the command, so with --warnings-as-errors the
+            // warning is printed as the error it would have been
+            if let Err(err) = warning::warn(format!("could not record exchange: {err:#}")) {
+                eprintln!("Error: {err:#}");
+            }
+        }
+    }
+}
+
+/// A body that is already in memory.
+struct RecordedBody {
+    data: Option<Bytes>,
+    trailers: Option<HeaderMap>,
+}
+
+impl RecordedBody {
+    fn new(data: Bytes, trailers: Option<HeaderMap>) -> BoxBody {
+        Self {
+            data: Some(data),
+            trailers,
+        }
+        .boxed_unsync()
+    }
+}
+
+impl Body for RecordedBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        _cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Bytes, Status>>> {
+        Poll::Ready(self.data.take().map(Ok))
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        _cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Status>> {
+        Poll::Ready(Ok(self.trailers.take()))
+    }
+}
+
+async fn collect<B>(mut body: B) -> Result<(Bytes, Option<HeaderMap>), StdError>
+where
+    B: Body<Data = Bytes> + Unpin,
+    B::Error: Into<StdError>,
+{
+    let mut data = vec![];
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk.map_err(Into::into)?);
+    }
+    let trailers = body.trailers().await.map_err(Into::into)?;
+    Ok((data.into(), trailers))
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn header_map(pairs: &[(String, String)]) -> Result<HeaderMap> {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            Ok((
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            ))
+        })
+        .collect()
+}
+
+/// Split a grpc body into its messages and clear the fields of each that
+/// change between runs.
+fn redact_frames(path: &str, body: &[u8]) -> Result<Vec<String>> {
+    let mut messages = vec![];
+    let mut rest = body;
+    while !rest.is_empty() {
+        if rest.len() < 5 {
+            return Err(anyhow!("truncated grpc frame in {path} request"));
+        }
+        let len = u32::from_be_bytes(rest[1..5].try_into()?) as usize;
+        let message = rest
+            .get(5..5 + len)
+            .ok_or_else(|| anyhow!("truncated grpc message in {path} request"))?;
+        messages.push(STANDARD.encode(redact(path, message)?));
+        rest = &rest[5 + len..];
+    }
+    Ok(messages)
+}
+
+macro_rules! clear {
+    ($message:expr, $msg_type:ty, $( $field:ident ),+ ) => {{
+        let mut msg = <$msg_type>::decode($message)?;
+        $(msg.$field = Default::default();)+
+        msg.encode_to_vec()
+    }};
+}
+
+/// Requests without a signature, or of methods the CLI does not call, are
+/// kept as sent.
+fn redact(path: &str, message: &[u8]) -> Result<Vec<u8>> {
+    let method = path.trim_start_matches("/helium.iot_config.");
+    Ok(match method {
+        "org/create_helium" => clear!(message, OrgCreateHeliumReqV1, timestamp, signer, signature),
+        "org/create_roamer" => clear!(message, OrgCreateRoamerReqV1, timestamp, signer, signature),
+        "org/enable" => clear!(message, OrgEnableReqV1, timestamp, signer, signature),
+        "org/update" => clear!(message, OrgUpdateReqV1, timestamp, signer, signature),
+        "route/list" => clear!(message, RouteListReqV1, timestamp, signer, signature),
+        "route/get" => clear!(message, RouteGetReqV1, timestamp, signer, signature),
+        "route/stream" => clear!(
+            message,
+            RouteStreamReqV1,
+            timestamp,
+            signer,
+            signature,
+            since
+        ),
+        "route/create" => clear!(message, RouteCreateReqV1, timestamp, signer, signature),
+        "route/update" => clear!(message, RouteUpdateReqV1, timestamp, signer, signature),
+        "route/delete" => clear!(message, RouteDeleteReqV1, timestamp, signer, signature),
+        "route/get_euis" => clear!(message, RouteGetEuisReqV1, timestamp, signer, signature),
+        "route/update_euis" => clear!(message, RouteUpdateEuisReqV1, timestamp, signer, signature),
+        "route/get_devaddr_ranges" => {
+            clear!(
+                message,
+                RouteGetDevaddrRangesReqV1,
+                timestamp,
+                signer,
+                signature
+            )
+        }
+        "route/update_devaddr_ranges" => {
+            clear!(
+                message,
+                RouteUpdateDevaddrRangesReqV1,
+                timestamp,
+                signer,
+                signature
+            )
+        }
+        "route/list_skfs" => clear!(message, RouteSkfListReqV1, timestamp, signer, signature),
+        "route/get_skfs" => clear!(message, RouteSkfGetReqV1, timestamp, signer, signature),
+        "route/update_skfs" => clear!(message, RouteSkfUpdateReqV1, timestamp, signer, signature),
+        "gateway/location" => clear!(message, GatewayLocationReqV1, signer, signature),
+        "gateway/info" => clear!(message, GatewayInfoReqV1, signer, signature),
+        "admin/add_key" => clear!(message, AdminAddKeyReqV1, signer, signature),
+        "admin/remove_key" => clear!(message, AdminRemoveKeyReqV1, signer, signature),
+        "admin/load_region" => clear!(message, AdminLoadRegionReqV1, signer, signature),
+        _ => message.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redact_frames, STANDARD};
+    use base64::Engine;
+    use helium_proto::{
+        services::iot_config::{RouteGetReqV1, RouteStreamReqV1},
+        Message,
+    };
+
+    fn frame(message: &impl Message) -> Vec<u8> {
+        let encoded = message.encode_to_vec();
+        let mut frame = vec![0];
+        frame.extend((encoded.len() as u32).to_be_bytes());
+        frame.extend(encoded);
+        frame
+    }
+
+    #[test]
+    fn volatile_fields_are_redacted() {
+        let request = |timestamp, signature: &[u8]| RouteGetReqV1 {
+            id: "route-id".to_string(),
+            timestamp,
+            signer: vec![1; 33],
+            signature: signature.to_vec(),
+        };
+        let body = [frame(&request(1, &[2; 64])), frame(&request(3, &[4; 64]))].concat();
+
+        let messages = redact_frames("/helium.iot_config.route/get", &body).unwrap();
+        let expected = STANDARD.encode(
+            RouteGetReqV1 {
+                id: "route-id".to_string(),
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        );
+        assert_eq!(vec![expected.clone(), expected], messages);
+
+        assert!(redact_frames("/helium.iot_config.route/get", &body[..body.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn stream_requests_are_redacted() {
+        let request = |timestamp, since| RouteStreamReqV1 {
+            timestamp,
+            signer: vec![1; 33],
+            signature: vec![2; 64],
+            since,
+        };
+        let path = "/helium.iot_config.route/stream";
+        let recorded = redact_frames(path, &frame(&request(1, 0))).unwrap();
+        let replayed = redact_frames(path, &frame(&request(5, 4))).unwrap();
+        assert_eq!(recorded, replayed);
+        assert_eq!(
+            vec![STANDARD.encode(RouteStreamReqV1::default().encode_to_vec())],
+            recorded
+        );
+    }
+}
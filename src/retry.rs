@@ -0,0 +1,372 @@
+use crate::{
+    progress::{ProgressEvent, ProgressSink, StderrProgress},
+    Result,
+};
+use anyhow::anyhow;
+use rand::Rng;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use tonic::{Code, Status};
+use tracing::Span;
+
+/// Requests allowed in a burst before the bucket starts pacing.
+const BUCKET_CAPACITY: f64 = 8.0;
+/// Sustained requests per second once the burst is spent.
+const BUCKET_REFILL_PER_SEC: f64 = 20.0;
+const BACKOFF_BASE: Duration = Duration::from_millis(250);
+const BACKOFF_MAX: Duration = Duration::from_secs(10);
+const MAX_RETRIES: u32 = 6;
+/// Smallest chunk `--adaptive-chunking` shrinks to.
+const MIN_CHUNK_SIZE: usize = 10;
+/// Chunks slower than this shrink before one times out.
+const SLOW_CHUNK: Duration = Duration::from_secs(5);
+/// Fast chunks in a row before the chunk size grows back.
+const GROW_AFTER: u32 = 3;
+
+static ADAPTIVE_CHUNKING: OnceLock<bool> = OnceLock::new();
+
+/// Size bulk chunks by how the config service is coping, set once from
+/// `--adaptive-chunking`.
+pub fn set_adaptive_chunking(adaptive: bool) -> Result {
+    ADAPTIVE_CHUNKING
+        .set(adaptive)
+        .map_err(|_| anyhow!("adaptive chunking is already set"))
+}
+
+/// True when the config service is asking us to slow down.
+///
+/// tonic maps an HTTP 429 without a grpc-status to `Unavailable`, so the
+/// message is checked for the status code as well.
+pub fn is_rate_limited(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<Status>() {
+        Some(status) => match status.code() {
+            Code::ResourceExhausted => true,
+            Code::Unavailable => status.message().contains("429"),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// True when a request ran out of time before the config service answered.
+///
+/// tonic reports its own request timeout as `Cancelled`.
+pub fn is_timed_out(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<Status>() {
+        Some(status) => match status.code() {
+            Code::DeadlineExceeded => true,
+            Code::Cancelled => status.message().contains("Timeout expired"),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// Jittered exponential delay for the `attempt`th retry, starting at 0.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let exp = BACKOFF_BASE.saturating_mul(2u32.saturating_pow(attempt));
+    let capped = exp.min(BACKOFF_MAX);
+    capped.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// Token bucket shared by every clone, so concurrent workers pace together
+/// and all pause when any one of them is throttled.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    progress: Arc<dyn ProgressSink>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: BUCKET_CAPACITY,
+                refilled_at: Instant::now(),
+                paused_until: None,
+            })),
+            progress: Arc::new(StderrProgress),
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Report retries to `progress` instead of stderr.
+    pub fn with_progress(self, progress: Arc<dyn ProgressSink>) -> Self {
+        Self { progress, ..self }
+    }
+
+    /// Wait until a request may be sent.
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token, or return how long to wait before trying again.
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock");
+        let now = Instant::now();
+        if let Some(paused_until) = bucket.paused_until {
+            if paused_until > now {
+                return Some(paused_until - now);
+            }
+            bucket.paused_until = None;
+        }
+
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * BUCKET_REFILL_PER_SEC).min(BUCKET_CAPACITY);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / BUCKET_REFILL_PER_SEC,
+        ))
+    }
+
+    /// Hold every worker for `delay` and empty the bucket.
+    fn throttle(&self, delay: Duration) {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock");
+        let until = Instant::now() + delay;
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(until, |current| current.max(until)),
+        );
+        bucket.tokens = 0.0;
+    }
+
+    /// Run `op`, retrying with backoff while the config service is rate limiting.
+    ///
+    /// Any other error is returned immediately.
+    pub async fn retry<T, F, Fut>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.acquire().await;
+            match op().await {
+                Err(err) if attempt < MAX_RETRIES && is_rate_limited(&err) => {
+                    let delay = backoff_delay(attempt);
+                    self.progress.event(ProgressEvent::RateLimited {
+                        delay,
+                        error: err.to_string(),
+                    });
+                    self.throttle(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Splits the items of a bulk operation into the chunks sent per request.
+///
+/// Chunks are `max` items, unless adaptive. Then a chunk that times out is
+/// sent again at half the size, down to [`MIN_CHUNK_SIZE`], slow chunks
+/// shrink the next one, and a run of fast chunks grows it back toward `max`.
+pub struct Chunker<'a, T> {
+    items: &'a [T],
+    done: usize,
+    size: usize,
+    max: usize,
+    adaptive: bool,
+    fast: u32,
+    progress: Arc<dyn ProgressSink>,
+    /// Open from creation until the pipeline is dropped
+    span: Span,
+}
+
+impl<'a, T> Chunker<'a, T> {
+    /// Adaptive when `--adaptive-chunking` is set.
+    pub fn new(items: &'a [T], max: usize) -> Self {
+        Self {
+            items,
+            done: 0,
+            size: max,
+            max,
+            adaptive: ADAPTIVE_CHUNKING.get().copied().unwrap_or_default(),
+            fast: 0,
+            progress: Arc::new(StderrProgress),
+            span: tracing::info_span!("bulk", items = items.len(), max_chunk = max),
+        }
+    }
+
+    pub fn adaptive(self, adaptive: bool) -> Self {
+        Self { adaptive, ..self }
+    }
+
+    /// Report chunk size changes to `progress` instead of stderr.
+    pub fn with_progress(self, progress: Arc<dyn ProgressSink>) -> Self {
+        Self { progress, ..self }
+    }
+
+    /// The chunk to send next, `None` once every item is done.
+    pub fn next_chunk(&self) -> Option<&'a [T]> {
+        let items = self.items;
+        let end = items.len().min(self.done + self.size);
+        (self.done < end).then(|| &items[self.done..end])
+    }
+
+    /// The span of the pipeline, to instrument each chunk's requests with so
+    /// they are traced within it.
+    pub fn span(&self) -> Span {
+        self.span.clone()
+    }
+
+    /// Items in the chunks sent so far.
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    /// Record the outcome of sending [`Chunker::next_chunk`], which took
+    /// `latency`. A timed out chunk is kept to be sent again when it can
+    /// still shrink, any other error is returned.
+    pub fn record<R>(&mut self, result: Result<R>, latency: Duration) -> Result {
+        let (offset, size) = (self.done, self.size);
+        let latency_ms = latency.as_millis() as u64;
+        match result {
+            Ok(_) => {
+                self.span
+                    .in_scope(|| tracing::info!(offset, size, latency_ms, "chunk sent"));
+                self.done = self.items.len().min(self.done + self.size);
+                if !self.adaptive {
+                    return Ok(());
+                }
+                if latency > SLOW_CHUNK {
+                    self.fast = 0;
+                    self.resize(self.size * 3 / 4);
+                } else {
+                    self.fast += 1;
+                    if self.fast >= GROW_AFTER {
+                        self.fast = 0;
+                        self.resize(self.size + self.size / 2);
+                    }
+                }
+                Ok(())
+            }
+            Err(err) if self.adaptive && is_timed_out(&err) && self.size > self.min() => {
+                self.span
+                    .in_scope(|| tracing::warn!(offset, size, latency_ms, "chunk timed out"));
+                self.fast = 0;
+                self.resize(self.size / 2);
+                Ok(())
+            }
+            Err(err) => {
+                self.span.in_scope(
+                    || tracing::error!(offset, size, latency_ms, error = %err, "chunk failed"),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    fn min(&self) -> usize {
+        MIN_CHUNK_SIZE.min(self.max)
+    }
+
+    fn resize(&mut self, size: usize) {
+        let size = size.clamp(self.min(), self.max);
+        if size != self.size {
+            self.progress.event(ProgressEvent::ChunkResized {
+                from: self.size,
+                to: size,
+            });
+            self.size = size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backoff_delay, is_rate_limited, is_timed_out, Chunker, BACKOFF_MAX};
+    use crate::progress::NoProgress;
+    use std::{sync::Arc, time::Duration};
+    use tonic::{Code, Status};
+
+    #[test]
+    fn rate_limited_statuses() {
+        let err = |status: Status| anyhow::Error::from(status);
+        assert!(is_rate_limited(&err(Status::resource_exhausted(
+            "slow down"
+        ))));
+        assert!(is_rate_limited(&err(Status::new(
+            Code::Unavailable,
+            "grpc-status header missing, mapped from HTTP status code 429"
+        ))));
+        assert!(!is_rate_limited(&err(Status::unavailable(
+            "connection reset"
+        ))));
+        assert!(!is_rate_limited(&err(Status::permission_denied("no"))));
+        assert!(!is_rate_limited(&anyhow::anyhow!("not a status")));
+    }
+
+    #[test]
+    fn backoff_grows_and_caps() {
+        assert!(backoff_delay(0) <= Duration::from_millis(250));
+        assert!(backoff_delay(3) >= Duration::from_secs(1));
+        assert!(backoff_delay(30) <= BACKOFF_MAX);
+    }
+
+    #[test]
+    fn adaptive_chunks() {
+        let items: Vec<u32> = (0..250).collect();
+        let timeout = || Err(anyhow::Error::from(Status::deadline_exceeded("too slow")));
+        let fast = Duration::from_millis(100);
+
+        let mut chunks = Chunker::new(&items, 100).adaptive(false);
+        assert!(chunks.record::<()>(timeout(), fast).is_err());
+        assert_eq!(100, chunks.next_chunk().unwrap().len());
+
+        let mut chunks = Chunker::new(&items, 100)
+            .adaptive(true)
+            .with_progress(Arc::new(NoProgress));
+        for size in [50, 25, 12, 10] {
+            chunks.record::<()>(timeout(), fast).unwrap();
+            assert_eq!(size, chunks.next_chunk().unwrap().len());
+        }
+        // nothing smaller to retry with
+        assert!(chunks.record::<()>(timeout(), fast).is_err());
+        assert_eq!(0, chunks.done());
+
+        for _ in 0..3 {
+            chunks.record(Ok(()), fast).unwrap();
+        }
+        assert_eq!(30, chunks.done());
+        assert_eq!(15, chunks.next_chunk().unwrap().len());
+        chunks.record(Ok(()), Duration::from_secs(6)).unwrap();
+        assert_eq!(11, chunks.next_chunk().unwrap().len());
+        assert!(chunks
+            .record::<()>(Err(anyhow::anyhow!("denied")), fast)
+            .is_err());
+
+        while chunks.next_chunk().is_some() {
+            chunks.record(Ok(()), fast).unwrap();
+        }
+        assert_eq!(250, chunks.done());
+    }
+
+    #[test]
+    fn timed_out_statuses() {
+        let err = |status: Status| anyhow::Error::from(status);
+        assert!(is_timed_out(&err(Status::deadline_exceeded("deadline"))));
+        assert!(is_timed_out(&err(Status::cancelled("Timeout expired"))));
+        assert!(!is_timed_out(&err(Status::cancelled("by client"))));
+        assert!(!is_timed_out(&err(Status::unavailable("down"))));
+    }
+}
@@ -1,23 +1,38 @@
 use crate::{
     hex_field,
-    server::{GwmpMap, Http, Server},
-    Oui, Result,
+    schema::{self, Versioned},
+    server::{GwmpMap, Http, Protocol, Server},
+    DevaddrRange, Eui, Oui, PrettyJson, Result, Skf, UpdateAction,
 };
-use helium_proto::services::iot_config::RouteV1 as ProtoRoute;
+use anyhow::{anyhow, Context};
+use helium_proto::services::iot_config::{
+    route_stream_res_v1, ActionV1, RouteStreamResV1, RouteV1 as ProtoRoute,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Route {
     pub id: String,
     pub net_id: hex_field::HexNetID,
     pub oui: Oui,
     pub server: Server,
     pub max_copies: u32,
+    // Defaults let route files written before these fields existed load
+    // with the same values the config service assigns a new Route.
+    #[serde(default = "default_active")]
     pub active: bool,
+    #[serde(default)]
     pub locked: bool,
+    #[serde(default)]
     pub ignore_empty_skf: bool,
 }
 
+fn default_active() -> bool {
+    true
+}
+
 impl Route {
     pub fn new(net_id: hex_field::HexNetID, oui: Oui, max_copies: u32) -> Self {
         Self {
@@ -47,6 +62,293 @@ impl Route {
     pub fn set_ignore_empty_skf(&mut self, ignore: bool) {
         self.ignore_empty_skf = ignore;
     }
+
+    fn auth_header_mut(&mut self) -> Option<&mut String> {
+        match &mut self.server.protocol {
+            Some(Protocol::Http(http)) if !http.auth_header.is_empty() => {
+                Some(&mut http.auth_header)
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace a set http `auth_header` with [`REDACTED`].
+    pub fn redact(&mut self) {
+        if let Some(auth_header) = self.auth_header_mut() {
+            *auth_header = REDACTED.to_string();
+        }
+    }
+
+    /// A copy to print or save, see [`Route::redact`].
+    pub fn redacted(&self) -> Self {
+        let mut route = self.clone();
+        route.redact();
+        route
+    }
+
+    /// Put back the `auth_header` of `current` where this Route's is
+    /// [`REDACTED`], e.g. when restoring a backup.
+    pub fn unredact(&mut self, current: &Route) -> Result {
+        if !self.is_redacted() {
+            return Ok(());
+        }
+        let Some(Protocol::Http(current_http)) = &current.server.protocol else {
+            return Err(anyhow!(
+                "route {} has a redacted auth_header and the current route has none to keep, \
+                 set it with --auth-header-file",
+                self.id
+            ));
+        };
+        if let Some(auth_header) = self.auth_header_mut() {
+            *auth_header = current_http.auth_header.clone();
+        }
+        Ok(())
+    }
+
+    /// Refuse to send a Route whose `auth_header` is still [`REDACTED`].
+    pub fn ensure_unredacted(&self) -> Result {
+        if self.is_redacted() {
+            return Err(anyhow!(
+                "route {} has a redacted auth_header, set it with --auth-header-file",
+                self.id
+            ));
+        }
+        Ok(())
+    }
+
+    fn is_redacted(&self) -> bool {
+        matches!(&self.server.protocol, Some(Protocol::Http(http)) if http.auth_header == REDACTED)
+    }
+}
+
+/// Stands in for a Route's http `auth_header` wherever Routes are printed or
+/// saved by the CLI, so the secret only goes to the config service. Routes
+/// pushed with it keep the `auth_header` already on the config service.
+pub const REDACTED: &str = "<redacted>";
+
+/// A Route prepared offline, along with the EUIs and Devaddr Ranges it should
+/// carry once created.
+///
+/// The output of `route get` is a valid route file without members, with
+/// its `auth_header` [`REDACTED`].
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq, Eq)]
+pub struct RouteFile {
+    #[serde(flatten)]
+    pub route: Route,
+    #[serde(default)]
+    pub devaddr_ranges: Vec<DevaddrRange>,
+    #[serde(default)]
+    pub euis: Vec<Eui>,
+}
+
+/// The fields of a [`RouteFile`] unflattened, so [`strict`](crate::strict)
+/// parsing sees the ones it does not know.
+#[derive(Deserialize)]
+struct RouteFileFields {
+    id: String,
+    net_id: hex_field::HexNetID,
+    oui: Oui,
+    server: Server,
+    max_copies: u32,
+    #[serde(default = "default_active")]
+    active: bool,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    ignore_empty_skf: bool,
+    #[serde(default)]
+    devaddr_ranges: Vec<DevaddrRange>,
+    #[serde(default)]
+    euis: Vec<Eui>,
+}
+
+impl<'de> Deserialize<'de> for RouteFile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = RouteFileFields::deserialize(deserializer)?;
+        Ok(Self {
+            route: Route {
+                id: fields.id,
+                net_id: fields.net_id,
+                oui: fields.oui,
+                server: fields.server,
+                max_copies: fields.max_copies,
+                active: fields.active,
+                locked: fields.locked,
+                ignore_empty_skf: fields.ignore_empty_skf,
+            },
+            devaddr_ranges: fields.devaddr_ranges,
+            euis: fields.euis,
+        })
+    }
+}
+
+impl RouteFile {
+    pub fn new(route: Route) -> Self {
+        Self {
+            route,
+            devaddr_ranges: vec![],
+            euis: vec![],
+        }
+    }
+
+    /// With `strict`, fields the route file format does not know about are an error.
+    pub fn read(path: &Path, strict: bool) -> Result<Self> {
+        let data =
+            fs::read_to_string(path).context(format!("reading route file {}", path.display()))?;
+        let file: Self = schema::from_str(&data, "route file", strict)
+            .context(format!("parsing route file {}", path.display()))?;
+        file.route
+            .server
+            .validate()
+            .context(format!("invalid route file {}", path.display()))?;
+        Ok(file)
+    }
+
+    /// Written canonically, see [`RouteFile::canonical_json`].
+    pub fn write(&self, path: &Path) -> Result {
+        fs::write(path, self.canonical_json()?)
+            .context(format!("writing route file {}", path.display()))
+    }
+
+    /// Sort members by address so files of the same Route are identical no
+    /// matter the order the config service returned them in.
+    pub fn canonicalize(&mut self) {
+        self.devaddr_ranges
+            .sort_by_key(|range| (range.start_addr.0, range.end_addr.0));
+        self.euis.sort_by_key(|eui| (eui.app_eui.0, eui.dev_eui.0));
+    }
+
+    /// Pretty json with the `schema_version`, sorted members, and a trailing
+    /// newline. Field order and upper case hex come from serialization.
+    pub fn canonical_json(&self) -> Result<String> {
+        let mut file = self.clone();
+        file.canonicalize();
+        Ok(format!("{}\n", Versioned::new(file).pretty_json()?))
+    }
+
+    /// Returns false if the pair was already present.
+    pub fn add_eui(&mut self, app_eui: hex_field::HexEui, dev_eui: hex_field::HexEui) -> bool {
+        let eui = Eui {
+            route_id: self.route.id.clone(),
+            app_eui,
+            dev_eui,
+        };
+        if self.euis.contains(&eui) {
+            return false;
+        }
+        self.euis.push(eui);
+        true
+    }
+
+    /// Returns false if the pair was not present.
+    pub fn remove_eui(&mut self, app_eui: hex_field::HexEui, dev_eui: hex_field::HexEui) -> bool {
+        let before = self.euis.len();
+        self.euis
+            .retain(|eui| !(eui.app_eui == app_eui && eui.dev_eui == dev_eui));
+        self.euis.len() != before
+    }
+
+    /// Returns false if the range was already present.
+    pub fn add_devaddr_range(
+        &mut self,
+        start_addr: hex_field::HexDevAddr,
+        end_addr: hex_field::HexDevAddr,
+    ) -> Result<bool> {
+        let range = DevaddrRange::new(self.route.id.clone(), start_addr, end_addr)?;
+        if self.devaddr_ranges.contains(&range) {
+            return Ok(false);
+        }
+        self.devaddr_ranges.push(range);
+        Ok(true)
+    }
+
+    /// Point the Route and its members at `route_id`, such as the ID the
+    /// config service assigned when the Route was created.
+    pub fn assign_id(&mut self, route_id: &str) {
+        self.route.id = route_id.to_string();
+        for eui in &mut self.euis {
+            eui.route_id = route_id.to_string();
+        }
+        for range in &mut self.devaddr_ranges {
+            range.route_id = route_id.to_string();
+        }
+    }
+
+    /// Returns false if the range was not present.
+    pub fn remove_devaddr_range(
+        &mut self,
+        start_addr: hex_field::HexDevAddr,
+        end_addr: hex_field::HexDevAddr,
+    ) -> bool {
+        let before = self.devaddr_ranges.len();
+        self.devaddr_ranges
+            .retain(|range| !(range.start_addr == start_addr && range.end_addr == end_addr));
+        self.devaddr_ranges.len() != before
+    }
+}
+
+/// Route IDs are UUIDs in the canonical hyphenated form.
+///
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`
+pub fn validate_route_id(s: &str) -> Result<String> {
+    const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+
+    let groups: Vec<&str> = s.split('-').collect();
+    let well_formed = groups.len() == GROUPS.len()
+        && groups
+            .iter()
+            .zip(GROUPS)
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()));
+
+    if !well_formed {
+        return Err(anyhow!(
+            "could not parse {s} into route_id, expected a 36 character UUID"
+        ));
+    }
+    Ok(s.to_lowercase())
+}
+
+/// Route IDs from `routes` that look like a mistyped or truncated `route_id`.
+///
+/// Prefix matches are returned first, followed by IDs within a small edit distance.
+pub fn suggest_route_ids<'a>(route_id: &str, routes: &'a [Route]) -> Vec<&'a str> {
+    const MAX_DISTANCE: usize = 4;
+
+    let route_id = route_id.to_lowercase();
+    let mut prefixed = vec![];
+    let mut similar = vec![];
+
+    for route in routes {
+        let id = route.id.as_str();
+        if id.starts_with(&route_id) || route_id.starts_with(id) {
+            prefixed.push(id);
+        } else {
+            let distance = levenshtein(&route_id, id);
+            if distance <= MAX_DISTANCE {
+                similar.push((distance, id));
+            }
+        }
+    }
+
+    similar.sort();
+    prefixed.extend(similar.into_iter().map(|(_, id)| id));
+    prefixed
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
 }
 
 impl From<ProtoRoute> for Route {
@@ -79,8 +381,67 @@ impl From<Route> for ProtoRoute {
     }
 }
 
+/// A change sent on the Route stream packet routers subscribe to.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct RouteChange {
+    pub action: UpdateAction,
+    /// Unix milliseconds
+    #[serde(serialize_with = "crate::timestamp::serialize_millis")]
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub item: RouteChangeItem,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RouteChangeItem {
+    Route(Route),
+    Eui(Eui),
+    DevaddrRange(DevaddrRange),
+    Skf(Skf),
+}
+
+impl RouteChangeItem {
+    /// The Route this change is to, or is a member of.
+    pub fn route_id(&self) -> &str {
+        match self {
+            Self::Route(route) => &route.id,
+            Self::Eui(eui) => &eui.route_id,
+            Self::DevaddrRange(range) => &range.route_id,
+            Self::Skf(skf) => &skf.route_id,
+        }
+    }
+}
+
+impl TryFrom<RouteStreamResV1> for RouteChange {
+    type Error = anyhow::Error;
+
+    fn try_from(res: RouteStreamResV1) -> Result<Self> {
+        let action = match ActionV1::from_i32(res.action) {
+            Some(ActionV1::Add) => UpdateAction::Add,
+            Some(ActionV1::Remove) => UpdateAction::Remove,
+            None => return Err(anyhow!("unsupported route stream action {}", res.action)),
+        };
+        let item = match res.data {
+            Some(route_stream_res_v1::Data::Route(route)) => RouteChangeItem::Route(route.into()),
+            Some(route_stream_res_v1::Data::EuiPair(eui)) => RouteChangeItem::Eui(eui.into()),
+            Some(route_stream_res_v1::Data::DevaddrRange(range)) => {
+                RouteChangeItem::DevaddrRange(range.into())
+            }
+            Some(route_stream_res_v1::Data::Skf(skf)) => RouteChangeItem::Skf(skf.into()),
+            None => return Err(anyhow!("route stream change without data")),
+        };
+        Ok(Self {
+            action,
+            timestamp: res.timestamp,
+            item,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{suggest_route_ids, validate_route_id, RouteChange, RouteFile, REDACTED};
     use crate::{hex_field, server::Server, Route};
     use helium_proto::services::iot_config::{
         server_v1::Protocol, ProtocolPacketRouterV1, RouteV1, ServerV1,
@@ -116,4 +477,213 @@ mod tests {
         assert_eq!(route, Route::from(v1.clone()));
         assert_eq!(v1, RouteV1::from(route));
     }
+
+    #[test]
+    fn route_id_validation() {
+        let id = "2AB3A45A-1D0C-4D5E-9F8A-1B2C3D4E5F60";
+        assert_eq!(id.to_lowercase(), validate_route_id(id).unwrap());
+
+        // truncated
+        assert!(validate_route_id("2ab3a45a-1d0c-4d5e-9f8a").is_err());
+        // not hex
+        assert!(validate_route_id("2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5fzz").is_err());
+        // no hyphens
+        assert!(validate_route_id("2ab3a45a1d0c4d5e9f8a1b2c3d4e5f60").is_err());
+    }
+
+    #[test]
+    fn route_id_suggestions() {
+        let route = |id: &str| Route {
+            id: id.into(),
+            ..Route::new(hex_field::net_id(1), 1, 1)
+        };
+        let routes = vec![
+            route("2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f60"),
+            route("2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f61"),
+            route("99999999-9999-4999-9999-999999999999"),
+        ];
+
+        // one character off
+        assert_eq!(
+            vec![
+                "2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f61",
+                "2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f60"
+            ],
+            suggest_route_ids("2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f71", &routes)
+        );
+        // truncated
+        assert_eq!(
+            vec![
+                "2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f60",
+                "2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f61"
+            ],
+            suggest_route_ids("2ab3a45a-1d0c", &routes)
+        );
+        assert!(suggest_route_ids("00000000-0000-4000-0000-000000000000", &routes).is_empty());
+    }
+
+    #[test]
+    fn route_file_members() {
+        let json = r#"{
+            "id": "",
+            "net_id": "000024",
+            "oui": 1,
+            "server": {"host": "", "port": 0, "protocol": {"type": "packet_router"}},
+            "max_copies": 5,
+            "active": true,
+            "locked": false,
+            "ignore_empty_skf": false
+        }"#;
+        // `route get` output parses without members
+        let mut file: RouteFile = serde_json::from_str(json).unwrap();
+        assert!(file.euis.is_empty() && file.devaddr_ranges.is_empty());
+
+        let eui = hex_field::eui(1);
+        assert!(file.add_eui(eui, eui));
+        assert!(!file.add_eui(eui, eui));
+        assert!(file.remove_eui(eui, eui));
+        assert!(!file.remove_eui(eui, eui));
+
+        let (start, end) = (hex_field::devaddr(1), hex_field::devaddr(10));
+        assert!(file.add_devaddr_range(start, end).unwrap());
+        assert!(!file.add_devaddr_range(start, end).unwrap());
+        assert!(file.add_devaddr_range(end, start).is_err());
+        assert!(file.remove_devaddr_range(start, end));
+
+        assert!(file.add_eui(eui, eui));
+        assert!(file.add_devaddr_range(start, end).unwrap());
+        file.assign_id("created-id");
+        assert_eq!("created-id", file.route.id);
+        assert_eq!("created-id", file.euis[0].route_id);
+        assert_eq!("created-id", file.devaddr_ranges[0].route_id);
+
+        let round_trip: RouteFile =
+            serde_json::from_str(&serde_json::to_string(&file).unwrap()).unwrap();
+        assert_eq!(file, round_trip);
+    }
+
+    #[test]
+    fn route_files_are_canonical() {
+        let json = r#"{
+            "id": "route", "net_id": "00003c", "oui": 1, "max_copies": 5,
+            "server": {"host": "", "port": 0, "protocol": {"type": "packet_router"}},
+            "euis": [
+                {"route_id": "route", "app_eui": "00000000000000ff", "dev_eui": "0000000000000001"},
+                {"route_id": "route", "app_eui": "0000000000000001", "dev_eui": "0000000000000002"},
+                {"route_id": "route", "app_eui": "0000000000000001", "dev_eui": "0000000000000001"}
+            ],
+            "devaddr_ranges": [
+                {"route_id": "route", "start_addr": "48000010", "end_addr": "4800001f"},
+                {"route_id": "route", "start_addr": "48000000", "end_addr": "4800000f"}
+            ]
+        }"#;
+        let file: RouteFile = serde_json::from_str(json).unwrap();
+        let canonical = file.canonical_json().unwrap();
+        assert!(canonical.starts_with("{\n  \"schema_version\": 1,"));
+        assert!(canonical.ends_with("}\n"));
+        assert!(canonical.contains("\"net_id\": \"00003C\""));
+
+        let sorted: RouteFile = serde_json::from_str(&canonical).unwrap();
+        let euis: Vec<_> = sorted
+            .euis
+            .iter()
+            .map(|eui| (eui.app_eui.0, eui.dev_eui.0))
+            .collect();
+        assert_eq!(vec![(1, 1), (1, 2), (0xff, 1)], euis);
+        assert_eq!(0x4800_0000, sorted.devaddr_ranges[0].start_addr.0);
+        // canonicalizing is idempotent
+        assert_eq!(canonical, sorted.canonical_json().unwrap());
+    }
+
+    #[test]
+    fn route_defaults_for_older_files() {
+        let json = r#"{
+            "id": "route_id",
+            "net_id": "000024",
+            "oui": 1,
+            "server": {"host": "", "port": 0, "protocol": {"type": "packet_router"}},
+            "max_copies": 5
+        }"#;
+        let route: Route = serde_json::from_str(json).unwrap();
+        assert!(route.active);
+        assert!(!route.locked);
+        assert!(!route.ignore_empty_skf);
+
+        let route = Route {
+            active: false,
+            locked: true,
+            ignore_empty_skf: true,
+            ..route
+        };
+        let round_trip: Route =
+            serde_json::from_str(&serde_json::to_string(&route).unwrap()).unwrap();
+        assert_eq!(route, round_trip);
+        assert_eq!(route, Route::from(RouteV1::from(route.clone())));
+    }
+
+    #[test]
+    fn route_stream_changes() {
+        use helium_proto::services::iot_config::{
+            route_stream_res_v1, ActionV1, EuiPairV1, RouteStreamResV1,
+        };
+
+        let res = RouteStreamResV1 {
+            action: ActionV1::Remove as i32,
+            timestamp: 1_700_000_000_000,
+            data: Some(route_stream_res_v1::Data::EuiPair(EuiPairV1 {
+                route_id: "route".to_string(),
+                app_eui: 1,
+                dev_eui: 2,
+            })),
+            ..Default::default()
+        };
+        let change = RouteChange::try_from(res).unwrap();
+        let mut json = serde_json::to_value(change).unwrap();
+        let timestamp = json.as_object_mut().unwrap().remove("timestamp").unwrap();
+        assert_eq!(1_700_000_000_000u64, timestamp["epoch"]);
+        assert!(timestamp["time"].as_str().unwrap().starts_with("2023-11-1"));
+        assert_eq!(
+            serde_json::json!({
+                "action": "Remove",
+                "type": "eui",
+                "data": {
+                    "route_id": "route",
+                    "app_eui": "0000000000000001",
+                    "dev_eui": "0000000000000002",
+                },
+            }),
+            json
+        );
+        assert!(RouteChange::try_from(RouteStreamResV1::default()).is_err());
+    }
+
+    #[test]
+    fn route_auth_header_redaction() {
+        use crate::server::{Http, Protocol as ServerProtocol};
+
+        let http = |auth_header: &str| Route {
+            server: Server {
+                protocol: Some(ServerProtocol::Http(Http {
+                    auth_header: auth_header.to_string(),
+                    ..Http::default()
+                })),
+                ..Server::default()
+            },
+            ..Route::new(hex_field::net_id(1), 1, 1)
+        };
+        let route = http("secret");
+        let mut redacted = route.redacted();
+        assert_eq!(http(REDACTED), redacted);
+        assert!(redacted.ensure_unredacted().is_err());
+        assert!(!serde_json::to_string(&redacted).unwrap().contains("secret"));
+
+        redacted.unredact(&route).unwrap();
+        assert_eq!(route, redacted);
+        assert!(route.ensure_unredacted().is_ok());
+        // an empty auth_header is not a secret
+        assert_eq!(http(""), http("").redacted());
+        assert!(http(REDACTED)
+            .unredact(&Route::new(hex_field::net_id(1), 1, 1))
+            .is_err());
+    }
 }
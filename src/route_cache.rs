@@ -0,0 +1,194 @@
+use crate::{api::RouteApi, route::Route, shared_file, warning, Oui, PrettyJson, Result};
+use anyhow::Context;
+use helium_crypto::Keypair;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Used when no cache directory is given, relative to `$HOME`.
+const DEFAULT_CACHE_DIR: &str = ".cache/helium-config-cli";
+
+/// How long a cached Route list is used before it is fetched again.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Route lists per OUI kept on disk for lookups that would otherwise list
+/// every Route of an OUI.
+///
+/// The config service has no ETag or version for a Route list, so entries
+/// expire by age. The cache only saves listing Routes, so failing to write it
+/// is reported on stderr and does not fail the command.
+pub struct RouteCache {
+    dir: Option<PathBuf>,
+    max_age: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedRoutes {
+    /// Unix seconds
+    fetched_at: u64,
+    routes: Vec<Route>,
+}
+
+impl RouteCache {
+    /// A `disabled` cache never reads or writes, every lookup lists Routes.
+    /// Without `dir` or `$HOME` the cache is disabled.
+    pub fn new(dir: Option<PathBuf>, max_age: Duration, disabled: bool) -> Self {
+        let dir = dir.or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(DEFAULT_CACHE_DIR))
+        });
+        Self {
+            dir: dir.filter(|_| !disabled),
+            max_age,
+        }
+    }
+
+    fn path(&self, oui: Oui) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("routes-{oui}.json")))
+    }
+
+    /// The cached Routes for `oui`, `None` when missing or older than the max age.
+    pub fn read(&self, oui: Oui) -> Result<Option<Vec<Route>>> {
+        let Some(path) = self.path(oui).filter(|path| path.exists()) else {
+            return Ok(None);
+        };
+        let data = fs::read_to_string(&path).context(format!("reading {}", path.display()))?;
+        // an unreadable cache is refetched rather than failing the lookup
+        let Ok(cached) = serde_json::from_str::<CachedRoutes>(&data) else {
+            return Ok(None);
+        };
+        let age = unix_secs()?.saturating_sub(cached.fetched_at);
+        if age > self.max_age.as_secs() {
+            return Ok(None);
+        }
+        Ok(Some(cached.routes))
+    }
+
+    /// Replace the cached Routes for `oui`, best effort: a failed write is a
+    /// warning, so it only fails the command with `--warnings-as-errors`.
+    pub fn write(&self, oui: Oui, routes: &[Route]) -> Result {
+        match self.try_write(oui, routes) {
+            Ok(()) => Ok(()),
+            Err(err) => warning::warn(format!("could not cache the routes of OUI {oui}: {err:#}")),
+        }
+    }
+
+    fn try_write(&self, oui: Oui, routes: &[Route]) -> Result {
+        let (Some(dir), Some(path)) = (&self.dir, self.path(oui)) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir).context(format!("creating cache directory {}", dir.display()))?;
+        let cached = CachedRoutes {
+            fetched_at: unix_secs()?,
+            routes: routes.iter().map(Route::redacted).collect(),
+        };
+        shared_file::write(&path, cached.pretty_json()?)
+    }
+
+    /// Routes for `oui` from the cache, listing and caching them when stale.
+    pub async fn routes(
+        &self,
+        client: &mut impl RouteApi,
+        oui: Oui,
+        keypair: &Keypair,
+    ) -> Result<Vec<Route>> {
+        if let Some(routes) = self.read(oui)? {
+            return Ok(routes);
+        }
+        self.refresh(client, oui, keypair).await
+    }
+
+    /// List the Routes for `oui` and replace the cached list.
+    pub async fn refresh(
+        &self,
+        client: &mut impl RouteApi,
+        oui: Oui,
+        keypair: &Keypair,
+    ) -> Result<Vec<Route>> {
+        let routes = client.list(oui, keypair).await?.routes;
+        self.write(oui, &routes)?;
+        Ok(routes)
+    }
+}
+
+fn unix_secs() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RouteCache;
+    use crate::{
+        api::RouteApi,
+        route::Route,
+        testing::{fixtures, FakeRouteApi},
+    };
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn cached_routes_refresh_on_miss() {
+        let dir = TempDir::new().unwrap();
+        let cache = RouteCache::new(
+            Some(dir.path().to_path_buf()),
+            Duration::from_secs(60),
+            false,
+        );
+        let keypair = fixtures::keypair();
+        let mut api = FakeRouteApi::with_routes([fixtures::route(1)]);
+
+        assert!(cache.read(1).unwrap().is_none());
+        assert_eq!(1, cache.routes(&mut api, 1, &keypair).await.unwrap().len());
+        assert!(dir.path().join("routes-1.json").exists());
+
+        // served from the cache while fresh
+        let created = api
+            .create_route(fixtures::route(1), &keypair)
+            .await
+            .unwrap();
+        assert_eq!(1, cache.routes(&mut api, 1, &keypair).await.unwrap().len());
+
+        let refreshed = cache.refresh(&mut api, 1, &keypair).await.unwrap();
+        assert!(refreshed.iter().any(|route: &Route| route.id == created.id));
+        assert_eq!(2, cache.read(1).unwrap().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn stale_or_disabled_cache_is_not_read() {
+        let dir = TempDir::new().unwrap();
+        let keypair = fixtures::keypair();
+        let mut api = FakeRouteApi::with_routes([fixtures::route(1)]);
+
+        let stale = RouteCache::new(Some(dir.path().to_path_buf()), Duration::ZERO, false);
+        stale.routes(&mut api, 1, &keypair).await.unwrap();
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(stale.read(1).unwrap().is_none());
+
+        let disabled = RouteCache::new(
+            Some(dir.path().to_path_buf()),
+            Duration::from_secs(60),
+            true,
+        );
+        assert!(disabled.read(1).unwrap().is_none());
+        std::fs::remove_file(dir.path().join("routes-1.json")).unwrap();
+        disabled.routes(&mut api, 1, &keypair).await.unwrap();
+        assert!(!dir.path().join("routes-1.json").exists());
+    }
+
+    #[tokio::test]
+    async fn unwritable_cache_is_skipped() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.child("not-a-dir");
+        std::fs::write(&file, "").unwrap();
+        let cache = RouteCache::new(Some(file), Duration::from_secs(60), false);
+        let keypair = fixtures::keypair();
+        let mut api = FakeRouteApi::with_routes([fixtures::route(1)]);
+
+        assert_eq!(1, cache.routes(&mut api, 1, &keypair).await.unwrap().len());
+        assert!(cache.read(1).unwrap().is_none());
+    }
+}
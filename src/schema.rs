@@ -0,0 +1,147 @@
+//! Versions of the documents the CLI writes to be read back later: route
+//! files, Route backups, and Session Key Filter exports. Each carries a
+//! `schema_version`, so documents from an older CLI are migrated as they are
+//! read, and ones from a newer CLI are refused rather than misread.
+
+use crate::{cmds::version::CLI_VERSION, strict, Result};
+use anyhow::anyhow;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+/// The version of documents written by this CLI.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades a document from the version at its index to the next one.
+type Migration = fn(&mut Map<String, Value>) -> Result;
+
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [from_unversioned];
+
+/// A document with the `schema_version` it is written in as its first field.
+#[derive(Debug, Serialize)]
+pub struct Versioned<T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    document: T,
+}
+
+impl<T: Serialize> Versioned<T> {
+    pub fn new(document: T) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            document,
+        }
+    }
+}
+
+/// Parse a document of any version as the current layout, see
+/// [`strict::from_str`] for `strict`.
+pub fn from_str<T: DeserializeOwned>(data: &str, kind: &str, strict: bool) -> Result<T> {
+    strict::from_value(migrate(serde_json::from_str(data)?, kind)?, strict)
+}
+
+/// Parse records of any version, given as a json array or one per line as
+/// exports write them. Each record is migrated on its own, see [`from_str`].
+pub fn records_from_str<T: DeserializeOwned>(
+    data: &str,
+    kind: &str,
+    strict: bool,
+) -> Result<Vec<T>> {
+    let records: Vec<Value> = if data.trim_start().starts_with('[') {
+        serde_json::from_str(data)?
+    } else {
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()?
+    };
+    records
+        .into_iter()
+        .map(|record| strict::from_value(migrate(record, kind)?, strict))
+        .collect()
+}
+
+/// `document` migrated to [`SCHEMA_VERSION`], with its `schema_version`
+/// removed so it parses as the current layout. `kind` names the document in
+/// errors.
+pub fn migrate(mut document: Value, kind: &str) -> Result<Value> {
+    let Value::Object(fields) = &mut document else {
+        return Err(anyhow!("{kind} is not a json object"));
+    };
+    let version = match fields.remove("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| anyhow!("{kind} schema_version {version} is not a number"))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "{kind} has schema_version {version}, helium-config-cli {CLI_VERSION} reads up to \
+             {SCHEMA_VERSION}, upgrade helium-config-cli to read it"
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(fields)?;
+    }
+    Ok(document)
+}
+
+/// Documents from before `schema_version` was written have the layout of
+/// version 1.
+fn from_unversioned(_fields: &mut Map<String, Value>) -> Result {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate, records_from_str, Versioned, SCHEMA_VERSION};
+    use crate::SkfUpdate;
+    use serde_json::json;
+
+    #[test]
+    fn documents_are_versioned() {
+        let written = serde_json::to_string(&Versioned::new(json!({"id": "route"}))).unwrap();
+        assert_eq!(
+            format!(r#"{{"schema_version":{SCHEMA_VERSION},"id":"route"}}"#),
+            written
+        );
+
+        let read = migrate(serde_json::from_str(&written).unwrap(), "route file").unwrap();
+        assert_eq!(json!({"id": "route"}), read);
+        // written before schema_version
+        assert_eq!(
+            json!({"id": "route"}),
+            migrate(json!({"id": "route"}), "route file").unwrap()
+        );
+
+        let newer = json!({"schema_version": SCHEMA_VERSION + 1, "id": "route"});
+        let err = migrate(newer, "route file").unwrap_err().to_string();
+        assert!(err.starts_with(&format!(
+            "route file has schema_version {}",
+            SCHEMA_VERSION + 1
+        )));
+        assert!(err.ends_with("upgrade helium-config-cli to read it"));
+        assert!(migrate(json!({"schema_version": "1"}), "backup").is_err());
+        assert!(migrate(json!([]), "backup").is_err());
+    }
+
+    #[test]
+    fn records_are_migrated() {
+        let record = |version: &str| {
+            format!(
+                r#"{{{version}"devaddr":"48000800","session_key":"00112233445566778899aabbccddeeff","action":"add","max_copies":null}}"#
+            )
+        };
+        let (old, current) = (record(""), record(r#""schema_version":1,"#));
+        let array: Vec<SkfUpdate> =
+            records_from_str(&format!("[{old},{current}]"), "update", true).unwrap();
+        let lines: Vec<SkfUpdate> =
+            records_from_str(&format!("{old}\n\n{current}\n"), "update", true).unwrap();
+        assert_eq!(2, array.len());
+        assert_eq!(array, lines);
+        assert_eq!(array[0], array[1]);
+
+        let newer = record(&format!(r#""schema_version":{},"#, SCHEMA_VERSION + 1));
+        assert!(records_from_str::<SkfUpdate>(&newer, "update", false).is_err());
+    }
+}
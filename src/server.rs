@@ -1,7 +1,8 @@
-use crate::{region::Region, Result};
+use crate::{hex_field, region::Region, Result};
 use anyhow::anyhow;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, str::FromStr};
 
 pub mod proto {
     pub use helium_proto::services::iot_config::{
@@ -13,7 +14,53 @@ pub mod proto {
 pub type Port = u32;
 pub type GwmpMap = BTreeMap<Region, Port>;
 
-#[derive(Serialize, Clone, Debug, Deserialize, PartialEq, Eq)]
+/// Ports are carried as u32 by the config service but must be valid UDP/TCP ports.
+pub fn check_port(port: Port) -> Result {
+    if (1..=u16::MAX as Port).contains(&port) {
+        return Ok(());
+    }
+    Err(anyhow!("port {port} is not between 1 and 65535"))
+}
+
+/// The receiver NSID of an HTTP roaming route is the NetID of the home
+/// network, normalized to the 6 uppercase hex chars the service expects.
+pub fn validate_receiver_nsid(nsid: &str) -> Result<String> {
+    // HexNetID reads `*` as the zero wildcard, which is never a home network
+    if nsid.trim() == "*" {
+        return Err(anyhow!(
+            "receiver_nsid must be the home network NetID, not the wildcard"
+        ));
+    }
+    let net_id = hex_field::HexNetID::from_str(nsid)
+        .map_err(|err| anyhow!("receiver_nsid must be the home network NetID, {err}"))?;
+    Ok(net_id.to_string())
+}
+
+/// A region an added GWMP mapping would move to a different port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GwmpConflict {
+    pub region: Region,
+    pub old_port: Port,
+    pub new_port: Port,
+}
+
+impl std::fmt::Display for GwmpConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "region {:?} already maps to port {} (new port {})",
+            self.region, self.old_port, self.new_port
+        )
+    }
+}
+
+pub fn parse_port(s: &str) -> Result<Port> {
+    let port = s.parse()?;
+    check_port(port)?;
+    Ok(port)
+}
+
+#[derive(Serialize, Clone, Debug, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Server {
     pub host: String,
     pub port: Port,
@@ -53,9 +100,20 @@ impl Server {
         }
         Err(anyhow!("server has no protocol to update"))
     }
+
+    /// A Server that has not been given a host yet may keep port 0.
+    pub fn validate(&self) -> Result {
+        if !(self.host.is_empty() && self.port == 0) {
+            check_port(self.port).map_err(|err| anyhow!("server {err}"))?;
+        }
+        match &self.protocol {
+            Some(protocol) => protocol.validate(),
+            None => Ok(()),
+        }
+    }
 }
 
-#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Protocol {
     Gwmp(Gwmp),
@@ -72,6 +130,14 @@ impl Protocol {
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::Gwmp(_) => "GWMP",
+            Protocol::Http(_) => "HTTP",
+            Protocol::PacketRouter => "PACKET_ROUTER",
+        }
+    }
+
     pub fn default_gwmp() -> Self {
         Protocol::Gwmp(Gwmp::default())
     }
@@ -120,6 +186,24 @@ impl Protocol {
         }
     }
 
+    /// Regions in `map` that are already mapped to a different port.
+    /// `gwmp_add_mapping` silently overwrites them.
+    pub fn gwmp_conflicts(&self, map: &GwmpMap) -> Vec<GwmpConflict> {
+        let Protocol::Gwmp(Gwmp { mapping }) = self else {
+            return vec![];
+        };
+        map.iter()
+            .filter_map(|(region, new_port)| match mapping.get(region) {
+                Some(old_port) if old_port != new_port => Some(GwmpConflict {
+                    region: region.clone(),
+                    old_port: *old_port,
+                    new_port: *new_port,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn gwmp_remove_mapping(&mut self, region: &Region) -> Result {
         match self {
             Protocol::Gwmp(Gwmp { ref mut mapping }) => {
@@ -131,6 +215,32 @@ impl Protocol {
         }
     }
 
+    pub fn validate(&self) -> Result {
+        match self {
+            Protocol::Gwmp(Gwmp { mapping }) => {
+                for (region, port) in mapping {
+                    check_port(*port).map_err(|err| anyhow!("gwmp {region:?} {err}"))?;
+                }
+                Ok(())
+            }
+            Protocol::Http(Http {
+                path,
+                receiver_nsid,
+                ..
+            }) => {
+                if !path.starts_with('/') {
+                    return Err(anyhow!("http path {path:?} must start with '/'"));
+                }
+                if !receiver_nsid.is_empty() {
+                    validate_receiver_nsid(receiver_nsid)?;
+                }
+                Ok(())
+            }
+            // Packet router has no fields of its own
+            Protocol::PacketRouter => Ok(()),
+        }
+    }
+
     fn http_update(&mut self, http: Http) -> Result {
         match self {
             Protocol::Http(_) => {
@@ -143,12 +253,12 @@ impl Protocol {
     }
 }
 
-#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Debug, Deserialize, JsonSchema, Clone, PartialEq, Eq, Default)]
 pub struct Gwmp {
     pub mapping: GwmpMap,
 }
 
-#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Debug, Deserialize, JsonSchema, Clone, PartialEq, Eq, Default)]
 pub struct Http {
     pub flow_type: FlowType,
     pub dedupe_timeout: u32,
@@ -157,7 +267,9 @@ pub struct Http {
     pub receiver_nsid: String,
 }
 
-#[derive(clap::ValueEnum, Clone, Serialize, Debug, Deserialize, PartialEq, Eq, Default)]
+#[derive(
+    clap::ValueEnum, Clone, Serialize, Debug, Deserialize, JsonSchema, PartialEq, Eq, Default,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum FlowType {
     #[default]
@@ -265,7 +377,7 @@ impl From<proto::Protocol> for Protocol {
 mod tests {
     /// Ensure all the keys and values are snake_cased.
     /// Serialize regions as lowercase with underscores in the right places.
-    use super::{Gwmp, Protocol, Server};
+    use super::{proto, validate_receiver_nsid, Gwmp, GwmpConflict, Protocol, Server};
     use crate::{
         region::Region,
         server::{FlowType, Http},
@@ -380,4 +492,71 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn server_validation() {
+        assert!(Server::default().validate().is_ok());
+
+        let server = |port, protocol| Server {
+            host: "example.com".into(),
+            port,
+            protocol: Some(protocol),
+        };
+        assert!(server(1337, Protocol::PacketRouter).validate().is_ok());
+        assert!(server(0, Protocol::PacketRouter).validate().is_err());
+        assert!(server(700_000, Protocol::PacketRouter).validate().is_err());
+
+        let gwmp = |port| Protocol::make_gwmp(Region::Us915, port).unwrap();
+        assert!(server(1337, gwmp(1700)).validate().is_ok());
+        assert!(server(1337, gwmp(65_536)).validate().is_err());
+
+        let http = |path: &str| Protocol::make_http(250, path.into(), None, None);
+        assert!(server(1337, http("/uplink")).validate().is_ok());
+        assert!(server(1337, http("")).validate().is_err());
+        assert!(server(1337, http("uplink")).validate().is_err());
+
+        let roaming = |nsid: &str| Protocol::make_http(250, "/".into(), None, Some(nsid.into()));
+        assert!(server(1337, roaming("C00053")).validate().is_ok());
+        assert!(server(1337, roaming("C0005")).validate().is_err());
+        assert!(server(1337, roaming("home-net")).validate().is_err());
+    }
+
+    #[test]
+    fn receiver_nsid() {
+        assert_eq!("C00053", validate_receiver_nsid("0xc00053").unwrap());
+        assert!(validate_receiver_nsid("").is_err());
+        assert!(validate_receiver_nsid("*").is_err());
+        assert!(validate_receiver_nsid("C000531").is_err());
+
+        let http = Protocol::make_http(250, "/".into(), None, Some("600013".into()));
+        let proto::Protocol::HttpRoaming(roaming) = proto::Protocol::from(http.clone()) else {
+            panic!("expected http roaming");
+        };
+        assert_eq!("600013", roaming.receiver_nsid);
+        assert_eq!(http, Protocol::from(proto::Protocol::HttpRoaming(roaming)));
+    }
+
+    #[test]
+    fn gwmp_conflicts() {
+        let gwmp = Protocol::make_gwmp(Region::Us915, 1700).unwrap();
+        let same = Protocol::make_gwmp_mapping(Region::Us915, 1700);
+        let other_region = Protocol::make_gwmp_mapping(Region::Eu868, 1701);
+        assert!(gwmp.gwmp_conflicts(&same).is_empty());
+        assert!(gwmp.gwmp_conflicts(&other_region).is_empty());
+
+        let moved = Protocol::make_gwmp_mapping(Region::Us915, 1701);
+        assert_eq!(
+            vec![GwmpConflict {
+                region: Region::Us915,
+                old_port: 1700,
+                new_port: 1701,
+            }],
+            gwmp.gwmp_conflicts(&moved)
+        );
+        assert_eq!(
+            "region Us915 already maps to port 1700 (new port 1701)",
+            gwmp.gwmp_conflicts(&moved)[0].to_string()
+        );
+        assert!(Protocol::PacketRouter.gwmp_conflicts(&moved).is_empty());
+    }
 }
@@ -0,0 +1,89 @@
+//! Files shared by concurrent CLI invocations, e.g. parallel CI jobs using
+//! one route cache or backup directory.
+//!
+//! Writers hold an advisory lock on a `<path>.lock` file beside the target,
+//! and replace the target by renaming a fully written temporary file, so
+//! readers never see a truncated file and read-modify-writes don't race.
+
+use crate::Result;
+use anyhow::{anyhow, Context};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// An exclusive advisory lock, released when dropped.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Block until no other invocation holds the lock for `path`. `path`
+    /// itself need not exist, but its parent directory must.
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let lock_path = sibling(path, |name| format!("{name}.lock"))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .context(format!("opening lock file {}", lock_path.display()))?;
+        file.lock()
+            .context(format!("locking {}", lock_path.display()))?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Replace `path` with `contents` under its lock.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result {
+    let _lock = FileLock::acquire(path)?;
+    write_atomic(path, contents)
+}
+
+/// Replace `path` with `contents` without ever exposing a partial file. The
+/// caller holds the lock when other invocations may write `path`.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result {
+    let tmp_path = sibling(path, |name| format!(".{name}.{}.tmp", std::process::id()))?;
+    let written = File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    if let Err(err) = written.and_then(|_| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err).context(format!("writing {}", path.display()));
+    }
+    Ok(())
+}
+
+fn sibling(path: &Path, name: impl FnOnce(&str) -> String) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file path", path.display()))?;
+    Ok(path.with_file_name(name(&file_name.to_string_lossy())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_atomic, FileLock};
+    use temp_dir::TempDir;
+
+    #[test]
+    fn writers_holding_the_lock_are_serialized() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("last_seen");
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _lock = FileLock::acquire(&path).unwrap();
+                    let count: u32 =
+                        std::fs::read_to_string(&path).map_or(0, |count| count.parse().unwrap());
+                    write_atomic(&path, (count + 1).to_string()).unwrap();
+                });
+            }
+        });
+        assert_eq!("8", std::fs::read_to_string(&path).unwrap());
+        // only the target and its lock file are left behind
+        assert_eq!(2, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+}
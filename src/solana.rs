@@ -0,0 +1,889 @@
+use crate::Result;
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const HNT_MINT: &str = "hntyVP6YFm1Hg25TN9WGLqM12b8TQmcknKrdu1oxWux";
+pub const IOT_MINT: &str = "iotEVVZLEywoTn1QdwNPddxPWszn3zFhEot3MfL9fns";
+pub const DC_MINT: &str = "dcuc8Amr83Wz27ZkQ2K9NS6r8zRpf1J6cvArEBDZDmm";
+
+pub const HELIUM_SUB_DAOS: &str = "hdaoVTCqhfHHo75XdAMxBKdUqvq1i5bF23sisBqVgGR";
+pub const HELIUM_ENTITY_MANAGER: &str = "hemjuPXBpNvggtaUnN1MwT3wrdhttKEfosTcc2P9Pg8";
+pub const IOT_ROUTING_MANAGER: &str = "irtjLnjCMmyowq2m3KWqpuFB3M9gdNA9A5BvH6jkcWd";
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// Accounts per `getMultipleAccounts` request, the RPC limit.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Anchor account discriminators, the first 8 bytes of
+/// sha256("account:<name>").
+const KEY_TO_ASSET_DISCRIMINATOR: [u8; 8] = [234, 40, 244, 64, 154, 171, 29, 177];
+const IOT_HOTSPOT_INFO_DISCRIMINATOR: [u8; 8] = [121, 208, 53, 245, 199, 166, 246, 79];
+const ORGANIZATION_DISCRIMINATOR: [u8; 8] = [243, 189, 126, 191, 59, 72, 255, 68];
+const ORGANIZATION_DELEGATE_DISCRIMINATOR: [u8; 8] = [213, 168, 210, 141, 101, 218, 243, 122];
+const DEVADDR_CONSTRAINT_DISCRIMINATOR: [u8; 8] = [56, 230, 2, 10, 210, 45, 39, 129];
+const NET_ID_DISCRIMINATOR: [u8; 8] = [216, 60, 142, 65, 74, 32, 44, 191];
+
+/// A Solana keypair file, as written by `solana-keygen`.
+pub struct SolanaKeypair {
+    /// Raw ed25519 public key
+    pub pubkey: [u8; 32],
+}
+
+impl SolanaKeypair {
+    /// Solana keypair files are a json array of 64 bytes, secret then public.
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .context(format!("reading solana keypair {}", path.display()))?;
+        let bytes: Vec<u8> = serde_json::from_str(&data)
+            .context(format!("parsing solana keypair {}", path.display()))?;
+        if bytes.len() != 64 {
+            return Err(anyhow!(
+                "solana keypair {} has {} bytes, expected 64",
+                path.display(),
+                bytes.len()
+            ));
+        }
+        let mut pubkey = [0; 32];
+        pubkey.copy_from_slice(&bytes[32..]);
+        Ok(Self { pubkey })
+    }
+
+    /// `path`, or the Solana CLI's default keypair `~/.config/solana/id.json`.
+    pub fn path_or_default(path: Option<PathBuf>) -> Result<PathBuf> {
+        match path {
+            Some(path) => Ok(path),
+            None => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".config/solana/id.json"))
+                .ok_or_else(|| anyhow!("HOME is not set, provide --solana-keypair")),
+        }
+    }
+
+    /// The keypair at [`SolanaKeypair::path_or_default`].
+    pub fn read_or_default(path: Option<PathBuf>) -> Result<Self> {
+        Self::read(&Self::path_or_default(path)?)
+    }
+
+    /// Base58 address used by Solana
+    pub fn address(&self) -> String {
+        bs58::encode(self.pubkey).into_string()
+    }
+
+    /// True when `key` is the same ed25519 key, regardless of Helium network.
+    pub fn matches(&self, key: &helium_crypto::PublicKey) -> bool {
+        key.to_vec().get(1..) == Some(&self.pubkey[..])
+    }
+}
+
+/// Just enough of the Solana json rpc to report balances.
+pub struct SolanaRpc {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl SolanaRpc {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let mut response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .send()
+            .await
+            .context(format!("calling {method} on {}", self.url))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(err) = response.get("error") {
+            return Err(anyhow!("{method} failed: {err}"));
+        }
+        Ok(response["result"].take())
+    }
+
+    /// SOL held by `address`
+    pub async fn sol_balance(&self, address: &str) -> Result<f64> {
+        let result = self.call("getBalance", json!([address])).await?;
+        let lamports = result["value"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("unexpected getBalance result: {result}"))?;
+        Ok(lamports as f64 / LAMPORTS_PER_SOL)
+    }
+
+    /// Balance of `mint` tokens across every token account owned by `address`
+    pub async fn token_balance(&self, address: &str, mint: &str) -> Result<f64> {
+        let result = self
+            .call(
+                "getTokenAccountsByOwner",
+                json!([address, {"mint": mint}, {"encoding": "jsonParsed"}]),
+            )
+            .await?;
+        Ok(sum_token_amounts(&result))
+    }
+}
+
+/// Accounts are read at the addresses their programs derive for them, not by
+/// scanning a program's accounts, which public RPC nodes refuse or rate limit.
+impl SolanaRpc {
+    /// The compressed NFT asset of a Hotspot, from the `KeyToAssetV0` account
+    /// holding its entity key.
+    pub async fn hotspot_asset(&self, hotspot: &helium_crypto::PublicKey) -> Result<String> {
+        let address = key_to_asset_address(&entity_key(hotspot)?)?;
+        let data = self
+            .account_data(&address.to_string())
+            .await
+            .context(format!("{hotspot} has not been onboarded"))?;
+        let account: KeyToAssetV0 =
+            anchor_account("KeyToAssetV0", KEY_TO_ASSET_DISCRIMINATOR, &data)?;
+        Ok(bs58::encode(account.asset).into_string())
+    }
+
+    /// The `IotHotspotInfoV0` account of an onboarded Hotspot.
+    pub async fn iot_hotspot_info(
+        &self,
+        hotspot: &helium_crypto::PublicKey,
+    ) -> Result<IotHotspotInfo> {
+        let address = iot_info_address(&entity_key(hotspot)?)?.to_string();
+        let data = self
+            .account_data(&address)
+            .await
+            .context(format!("{hotspot} is not onboarded to the IoT network"))?;
+        IotHotspotInfo::decode(address, &data)
+    }
+
+    /// The `OrganizationV0` account of `org` with its delegates, devaddr
+    /// constraints, and NetID. Delegates and constraints are looked up from
+    /// the config service's, so ones only on chain are not found.
+    pub async fn iot_organization(&self, org: &crate::OrgResponse) -> Result<ChainOrg> {
+        let oui = org.org.oui;
+        let organization = organization_address(oui)?;
+        let address = organization.to_string();
+        let data = self
+            .account_data(&address)
+            .await
+            .context(format!("OUI {oui} has no organization account on chain"))?;
+        let OrganizationAccount {
+            net_id_address,
+            authority,
+            approved,
+            ..
+        } = decode_organization(&data)?;
+
+        let delegate_addresses = org
+            .org
+            .delegate_keys
+            .iter()
+            .filter_map(solana_address)
+            .map(|delegate| {
+                let delegate = Pubkey::from_str(&delegate)?;
+                pda(
+                    IOT_ROUTING_MANAGER,
+                    &[
+                        b"organization_delegate",
+                        organization.as_ref(),
+                        delegate.as_ref(),
+                    ],
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let delegates = self
+            .multiple_accounts(&delegate_addresses)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(|data| {
+                let account: OrganizationDelegateV0 = anchor_account(
+                    "OrganizationDelegateV0",
+                    ORGANIZATION_DELEGATE_DISCRIMINATOR,
+                    &data,
+                )?;
+                Ok(bs58::encode(account.delegate).into_string())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let constraint_addresses = org
+            .devaddr_constraints
+            .iter()
+            .map(|constraint| {
+                pda(
+                    IOT_ROUTING_MANAGER,
+                    &[
+                        b"devaddr_constraint",
+                        organization.as_ref(),
+                        &constraint.start_addr.0.to_le_bytes(),
+                    ],
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let devaddr_constraints = self
+            .multiple_accounts(&constraint_addresses)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(|data| {
+                let account: DevaddrConstraintV0 = anchor_account(
+                    "DevaddrConstraintV0",
+                    DEVADDR_CONSTRAINT_DISCRIMINATOR,
+                    &data,
+                )?;
+                Ok((account.start_addr, account.end_addr))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let net_id_data = self.account_data(&net_id_address).await?;
+        let net_id = decode_net_id(net_id_address, &net_id_data)?.net_id;
+
+        Ok(ChainOrg {
+            address,
+            authority,
+            net_id,
+            approved,
+            delegates,
+            devaddr_constraints,
+        })
+    }
+
+    /// The `NetIdV0` accounts of `net_ids` that exist, or only those
+    /// `authority` may allocate devaddrs from.
+    pub async fn iot_net_ids(
+        &self,
+        net_ids: &[u64],
+        authority: Option<&str>,
+    ) -> Result<Vec<ChainNetId>> {
+        let routing_manager = routing_manager_address()?;
+        let addresses = net_ids
+            .iter()
+            .map(|net_id| {
+                pda(
+                    IOT_ROUTING_MANAGER,
+                    &[b"net_id", routing_manager.as_ref(), &net_id.to_le_bytes()],
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut chain_net_ids = vec![];
+        for (address, data) in addresses
+            .iter()
+            .zip(self.multiple_accounts(&addresses).await?)
+        {
+            let Some(data) = data else {
+                continue;
+            };
+            let net_id = decode_net_id(address.to_string(), &data)?;
+            if authority.map_or(true, |authority| authority == net_id.authority) {
+                chain_net_ids.push(net_id);
+            }
+        }
+        chain_net_ids.sort_by_key(|net_id| net_id.net_id);
+        Ok(chain_net_ids)
+    }
+
+    /// The escrow key and approval of the `OrganizationV0` accounts of
+    /// `ouis`, by OUI. OUIs without an account are left out.
+    pub async fn iot_organization_escrows(&self, ouis: &[u64]) -> Result<BTreeMap<u64, OrgEscrow>> {
+        let addresses = ouis
+            .iter()
+            .map(|oui| organization_address(*oui))
+            .collect::<Result<Vec<_>>>()?;
+        self.multiple_accounts(&addresses)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(|data| {
+                let account = decode_organization(&data)?;
+                Ok((
+                    account.oui,
+                    OrgEscrow {
+                        escrow_key: account.escrow_key,
+                        approved: account.approved,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Data of each of `addresses`, `None` for accounts that do not exist.
+    async fn multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+            let result = self
+                .call(
+                    "getMultipleAccounts",
+                    json!([addresses, {"encoding": "base64"}]),
+                )
+                .await?;
+            let values = result["value"]
+                .as_array()
+                .filter(|values| values.len() == chunk.len())
+                .ok_or_else(|| anyhow!("unexpected getMultipleAccounts result: {result}"))?;
+            for value in values {
+                let data = match value["data"][0].as_str() {
+                    Some(data) => Some(STANDARD.decode(data)?),
+                    None => None,
+                };
+                accounts.push(data);
+            }
+        }
+        Ok(accounts)
+    }
+
+    async fn account_data(&self, address: &str) -> Result<Vec<u8>> {
+        let result = self
+            .call("getAccountInfo", json!([address, {"encoding": "base64"}]))
+            .await?;
+        let data = result["value"]["data"][0]
+            .as_str()
+            .ok_or_else(|| anyhow!("account {address} not found"))?;
+        Ok(STANDARD.decode(data)?)
+    }
+
+    /// Unix time of the most recent transaction involving `address`.
+    pub async fn last_transaction_time(&self, address: &str) -> Result<Option<i64>> {
+        let result = self
+            .call("getSignaturesForAddress", json!([address, {"limit": 1}]))
+            .await?;
+        Ok(result[0]["blockTime"].as_i64())
+    }
+}
+
+/// A Solana account address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pubkey([u8; 32]);
+
+impl FromStr for Pubkey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = bs58::decode(s)
+            .into_vec()
+            .context(format!("{s} is not a solana address"))?;
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| anyhow!("{s} is not a solana address, expected 32 bytes"))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl Display for Pubkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+impl AsRef<[u8]> for Pubkey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// The address `program` derives from `seeds`, as Solana's
+/// `find_program_address` does: the first bump seed from 255 down whose
+/// address is off the ed25519 curve, so no private key can sign for it.
+fn pda(program: &str, seeds: &[&[u8]]) -> Result<Pubkey> {
+    let program = Pubkey::from_str(program)?;
+    (0..=u8::MAX)
+        .rev()
+        .map(|bump| {
+            let mut hasher = Sha256::new();
+            for seed in seeds {
+                hasher.update(seed);
+            }
+            hasher.update([bump]);
+            hasher.update(program);
+            hasher.update(b"ProgramDerivedAddress");
+            hasher.finalize().into()
+        })
+        .find(|address: &[u8; 32]| CompressedEdwardsY(*address).decompress().is_none())
+        .map(Pubkey)
+        .ok_or_else(|| anyhow!("no program address of {program} for the seeds"))
+}
+
+fn hash(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Hotspot entity keys are the b58 decoded address, version and checksum
+/// included.
+fn entity_key(hotspot: &helium_crypto::PublicKey) -> Result<Vec<u8>> {
+    Ok(bs58::decode(hotspot.to_string()).into_vec()?)
+}
+
+fn dao_address(mint: &str) -> Result<Pubkey> {
+    let mint = Pubkey::from_str(mint)?;
+    pda(HELIUM_SUB_DAOS, &[b"dao", mint.as_ref()])
+}
+
+fn iot_sub_dao_address() -> Result<Pubkey> {
+    let mint = Pubkey::from_str(IOT_MINT)?;
+    pda(HELIUM_SUB_DAOS, &[b"sub_dao", mint.as_ref()])
+}
+
+fn key_to_asset_address(entity_key: &[u8]) -> Result<Pubkey> {
+    let dao = dao_address(HNT_MINT)?;
+    pda(
+        HELIUM_ENTITY_MANAGER,
+        &[b"key_to_asset", dao.as_ref(), hash(entity_key).as_ref()],
+    )
+}
+
+fn iot_info_address(entity_key: &[u8]) -> Result<Pubkey> {
+    let sub_dao = iot_sub_dao_address()?;
+    let config = pda(
+        HELIUM_ENTITY_MANAGER,
+        &[b"rewardable_entity_config", sub_dao.as_ref(), b"IOT"],
+    )?;
+    pda(
+        HELIUM_ENTITY_MANAGER,
+        &[b"iot_info", config.as_ref(), hash(entity_key).as_ref()],
+    )
+}
+
+fn routing_manager_address() -> Result<Pubkey> {
+    let sub_dao = iot_sub_dao_address()?;
+    pda(IOT_ROUTING_MANAGER, &[b"routing_manager", sub_dao.as_ref()])
+}
+
+fn organization_address(oui: u64) -> Result<Pubkey> {
+    let routing_manager = routing_manager_address()?;
+    pda(
+        IOT_ROUTING_MANAGER,
+        &[
+            b"organization",
+            routing_manager.as_ref(),
+            &oui.to_le_bytes(),
+        ],
+    )
+}
+
+/// An Anchor account's fields after its discriminator. Accounts are read
+/// up to the last field a struct declares, later fields are ignored.
+fn anchor_account<T: BorshDeserialize>(
+    name: &str,
+    discriminator: [u8; 8],
+    data: &[u8],
+) -> Result<T> {
+    match data.split_first_chunk::<8>() {
+        Some((found, mut fields)) if *found == discriminator => {
+            T::deserialize(&mut fields).context(format!("decoding {name} account"))
+        }
+        _ => Err(anyhow!("not a {name} account")),
+    }
+}
+
+#[derive(BorshDeserialize)]
+struct KeyToAssetV0 {
+    _dao: [u8; 32],
+    asset: [u8; 32],
+}
+
+#[derive(BorshDeserialize)]
+struct OrganizationV0 {
+    _routing_manager: [u8; 32],
+    net_id: [u8; 32],
+    authority: [u8; 32],
+    oui: u64,
+    escrow_key: String,
+    approved: bool,
+}
+
+#[derive(BorshDeserialize)]
+struct OrganizationDelegateV0 {
+    _organization: [u8; 32],
+    delegate: [u8; 32],
+}
+
+#[derive(BorshDeserialize)]
+struct DevaddrConstraintV0 {
+    _routing_manager: [u8; 32],
+    _net_id: [u8; 32],
+    _organization: [u8; 32],
+    start_addr: u64,
+    end_addr: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct NetIdV0 {
+    _routing_manager: [u8; 32],
+    id: u64,
+    authority: [u8; 32],
+    current_addr_offset: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct IotHotspotInfoV0 {
+    _asset: [u8; 32],
+    _bump_seed: u8,
+    location: Option<u64>,
+    elevation: Option<i32>,
+    gain: Option<i32>,
+    is_full_hotspot: bool,
+    num_location_asserts: u16,
+    is_active: bool,
+}
+
+struct OrganizationAccount {
+    net_id_address: String,
+    authority: String,
+    oui: u64,
+    escrow_key: String,
+    approved: bool,
+}
+
+fn decode_organization(data: &[u8]) -> Result<OrganizationAccount> {
+    let account: OrganizationV0 =
+        anchor_account("OrganizationV0", ORGANIZATION_DISCRIMINATOR, data)?;
+    Ok(OrganizationAccount {
+        net_id_address: bs58::encode(account.net_id).into_string(),
+        authority: bs58::encode(account.authority).into_string(),
+        oui: account.oui,
+        escrow_key: account.escrow_key,
+        approved: account.approved,
+    })
+}
+
+fn decode_net_id(address: String, data: &[u8]) -> Result<ChainNetId> {
+    let account: NetIdV0 = anchor_account("NetIdV0", NET_ID_DISCRIMINATOR, data)
+        .context(format!("reading {address}"))?;
+    Ok(ChainNetId {
+        net_id: account.id,
+        authority: bs58::encode(account.authority).into_string(),
+        current_addr_offset: account.current_addr_offset,
+        address,
+    })
+}
+
+/// The Solana address of an ed25519 Helium key.
+pub fn solana_address(key: &helium_crypto::PublicKey) -> Option<String> {
+    match key.key_type() {
+        helium_crypto::KeyType::Ed25519 => Some(bs58::encode(&key.to_vec()[1..]).into_string()),
+        _ => None,
+    }
+}
+
+/// How an Org's data credits are escrowed on chain, for billing.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OrgEscrow {
+    pub escrow_key: String,
+    pub approved: bool,
+}
+
+/// A NetID as the iot routing manager program records it on chain.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ChainNetId {
+    pub address: String,
+    pub net_id: u64,
+    /// Solana address allowed to allocate devaddrs from the NetID
+    pub authority: String,
+    /// Offset into the NetID's devaddr range of the next devaddr to allocate
+    pub current_addr_offset: u64,
+}
+
+/// An Org as the iot routing manager program records it on chain.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ChainOrg {
+    pub address: String,
+    /// Solana address of the owner
+    pub authority: String,
+    pub net_id: u64,
+    pub approved: bool,
+    /// Solana addresses of the delegate keys
+    pub delegates: Vec<String>,
+    /// `(start_addr, end_addr)`, end exclusive
+    pub devaddr_constraints: Vec<(u64, u64)>,
+}
+
+impl ChainOrg {
+    /// Every way the config service's record of the Org differs from this one.
+    pub fn divergences(&self, org: &crate::OrgResponse) -> Vec<String> {
+        let mut divergences = vec![];
+        let owner = solana_address(&org.org.owner).unwrap_or_default();
+        if owner != self.authority {
+            divergences.push(format!(
+                "owner: config service {} ({owner}), chain {}",
+                org.org.owner, self.authority
+            ));
+        }
+
+        let service_delegates: BTreeSet<_> = org
+            .org
+            .delegate_keys
+            .iter()
+            .filter_map(solana_address)
+            .collect();
+        let chain_delegates: BTreeSet<_> = self.delegates.iter().cloned().collect();
+        for delegate in service_delegates.difference(&chain_delegates) {
+            divergences.push(format!("delegate {delegate} is only in the config service"));
+        }
+        for delegate in chain_delegates.difference(&service_delegates) {
+            divergences.push(format!("delegate {delegate} is only on chain"));
+        }
+
+        if u64::from(org.net_id) != self.net_id {
+            divergences.push(format!(
+                "net_id: config service {}, chain {}",
+                org.net_id,
+                crate::hex_field::net_id(self.net_id)
+            ));
+        }
+
+        let service_constraints: BTreeSet<_> = org
+            .devaddr_constraints
+            .iter()
+            .map(|constraint| (constraint.start_addr.0, constraint.end_addr.0))
+            .collect();
+        let chain_constraints: BTreeSet<_> = self
+            .devaddr_constraints
+            .iter()
+            .map(|&(start, end)| (start, end.saturating_sub(1)))
+            .collect();
+        let range = |(start, end): &(u64, u64)| {
+            format!(
+                "{} - {}",
+                crate::hex_field::devaddr(*start),
+                crate::hex_field::devaddr(*end)
+            )
+        };
+        for constraint in service_constraints.difference(&chain_constraints) {
+            divergences.push(format!(
+                "devaddr constraint {} is only in the config service",
+                range(constraint)
+            ));
+        }
+        for constraint in chain_constraints.difference(&service_constraints) {
+            divergences.push(format!(
+                "devaddr constraint {} is only on chain",
+                range(constraint)
+            ));
+        }
+
+        if self.approved == org.org.locked {
+            divergences.push(format!(
+                "approved on chain is {} but the config service has the Org {}",
+                self.approved,
+                if org.org.locked { "locked" } else { "unlocked" }
+            ));
+        }
+        divergences
+    }
+}
+
+/// What a Hotspot asserted when onboarding to the IoT network or updating
+/// its location.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct IotHotspotInfo {
+    pub info_address: String,
+    /// H3 index, as hex
+    pub location: Option<String>,
+    pub elevation: Option<i32>,
+    /// Antenna gain in dBi * 10
+    pub gain: Option<i32>,
+    pub is_full_hotspot: bool,
+    pub num_location_asserts: u16,
+    pub is_active: bool,
+}
+
+impl IotHotspotInfo {
+    fn decode(info_address: String, data: &[u8]) -> Result<Self> {
+        let account: IotHotspotInfoV0 =
+            anchor_account("IotHotspotInfoV0", IOT_HOTSPOT_INFO_DISCRIMINATOR, data)
+                .context(format!("reading {info_address}"))?;
+        Ok(Self {
+            info_address,
+            location: account.location.map(|location| format!("{location:x}")),
+            elevation: account.elevation,
+            gain: account.gain,
+            is_full_hotspot: account.is_full_hotspot,
+            num_location_asserts: account.num_location_asserts,
+            is_active: account.is_active,
+        })
+    }
+}
+
+fn sum_token_amounts(result: &Value) -> f64 {
+    result["value"]
+        .as_array()
+        .map(|accounts| {
+            accounts
+                .iter()
+                .filter_map(|account| {
+                    account["account"]["data"]["parsed"]["info"]["tokenAmount"]["uiAmountString"]
+                        .as_str()
+                        .and_then(|amount| amount.parse::<f64>().ok())
+                })
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_net_id, decode_organization, pda, solana_address, sum_token_amounts, ChainOrg,
+        IotHotspotInfo, Pubkey, SolanaKeypair, HELIUM_SUB_DAOS, HNT_MINT,
+        IOT_HOTSPOT_INFO_DISCRIMINATOR, NET_ID_DISCRIMINATOR, ORGANIZATION_DISCRIMINATOR,
+    };
+    use crate::testing::fixtures;
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use serde_json::json;
+    use std::str::FromStr;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn read_keypair_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("id.json");
+        let bytes: Vec<u8> = (0..64).collect();
+        std::fs::write(&path, serde_json::to_string(&bytes).unwrap()).unwrap();
+
+        let keypair = SolanaKeypair::read(&path).unwrap();
+        assert_eq!(&bytes[32..], &keypair.pubkey[..]);
+        assert_eq!(bs58::encode(&bytes[32..]).into_string(), keypair.address());
+
+        std::fs::write(&path, "[1, 2, 3]").unwrap();
+        assert!(SolanaKeypair::read(&path).is_err());
+    }
+
+    #[test]
+    fn program_addresses_are_off_curve() {
+        let mint = Pubkey::from_str(HNT_MINT).unwrap();
+        let dao = pda(HELIUM_SUB_DAOS, &[b"dao", mint.as_ref()]).unwrap();
+        assert!(CompressedEdwardsY(dao.0).decompress().is_none());
+        assert_eq!(dao, pda(HELIUM_SUB_DAOS, &[b"dao", mint.as_ref()]).unwrap());
+        assert_eq!(dao, Pubkey::from_str(&dao.to_string()).unwrap());
+        assert!(Pubkey::from_str("not-b58").is_err());
+        assert!(Pubkey::from_str("11").is_err());
+    }
+
+    #[test]
+    fn token_amounts_are_summed() {
+        let account = |amount: &str| json!({"account": {"data": {"parsed": {"info": {"tokenAmount": {"uiAmountString": amount}}}}}});
+        let result = json!({"value": [account("1.5"), account("2")]});
+        assert_eq!(3.5, sum_token_amounts(&result));
+        assert_eq!(0.0, sum_token_amounts(&json!({"value": []})));
+    }
+
+    #[test]
+    fn decode_iot_hotspot_info() {
+        let mut data = IOT_HOTSPOT_INFO_DISCRIMINATOR.to_vec();
+        data.extend([7; 32]); // asset
+        data.push(255); // bump_seed
+        data.push(1);
+        data.extend(0x8c2ab38f1ee1bffu64.to_le_bytes());
+        data.push(0); // no elevation
+        data.push(1);
+        data.extend(12i32.to_le_bytes());
+        data.push(1);
+        data.extend(3u16.to_le_bytes());
+        data.push(1);
+        data.extend(0u64.to_le_bytes()); // dc_onboarding_fee_paid
+
+        let info = IotHotspotInfo::decode("info".to_string(), &data).unwrap();
+        assert_eq!(
+            IotHotspotInfo {
+                info_address: "info".to_string(),
+                location: Some("8c2ab38f1ee1bff".to_string()),
+                elevation: None,
+                gain: Some(12),
+                is_full_hotspot: true,
+                num_location_asserts: 3,
+                is_active: true,
+            },
+            info
+        );
+
+        assert!(IotHotspotInfo::decode("info".to_string(), &data[..20]).is_err());
+        data[0] = 0;
+        assert!(IotHotspotInfo::decode("info".to_string(), &data).is_err());
+    }
+
+    #[test]
+    fn decode_organization_account() {
+        let mut data = ORGANIZATION_DISCRIMINATOR.to_vec();
+        data.extend([1; 32]); // routing_manager
+        data.extend([2; 32]); // net_id
+        data.extend([3; 32]); // authority
+        data.extend(4u64.to_le_bytes());
+        data.extend(3u32.to_le_bytes());
+        data.extend(b"OUI");
+        data.push(1); // approved
+        data.push(255); // bump_seed
+
+        let account = decode_organization(&data).unwrap();
+        assert_eq!(bs58::encode([2; 32]).into_string(), account.net_id_address);
+        assert_eq!(bs58::encode([3; 32]).into_string(), account.authority);
+        assert_eq!(4, account.oui);
+        assert_eq!("OUI", account.escrow_key);
+        assert!(account.approved);
+
+        data[0] = 0;
+        assert!(decode_organization(&data).is_err());
+    }
+
+    #[test]
+    fn decode_net_id_account() {
+        let mut data = NET_ID_DISCRIMINATOR.to_vec();
+        data.extend([1; 32]); // routing_manager
+        data.extend(0x3Cu64.to_le_bytes());
+        data.extend([3; 32]); // authority
+        data.extend(512u64.to_le_bytes());
+        data.push(255); // bump_seed
+
+        let net_id = decode_net_id("net_id".to_string(), &data).unwrap();
+        assert_eq!(0x3C, net_id.net_id);
+        assert_eq!(bs58::encode([3; 32]).into_string(), net_id.authority);
+        assert_eq!(512, net_id.current_addr_offset);
+
+        data[0] = 0;
+        assert!(decode_net_id("net_id".to_string(), &data).is_err());
+    }
+
+    #[test]
+    fn chain_org_divergences() {
+        let owner = fixtures::keypair().public_key().clone();
+        let delegate = fixtures::keypair().public_key().clone();
+        let mut org = fixtures::org(4, &owner);
+        let mut chain = ChainOrg {
+            address: "org".to_string(),
+            authority: solana_address(&owner).unwrap(),
+            net_id: 0xC00053,
+            approved: true,
+            delegates: vec![],
+            devaddr_constraints: vec![(0x6000_0000, 0x6000_0008)],
+        };
+        assert!(chain.divergences(&org).is_empty());
+
+        org.org.delegate_keys.push(delegate);
+        org.org.locked = true;
+        chain.net_id = 0x00003C;
+        chain.devaddr_constraints = vec![(0x6000_0000, 0x6000_0007)];
+        assert_eq!(
+            vec![
+                format!(
+                    "delegate {} is only in the config service",
+                    solana_address(&org.org.delegate_keys[0]).unwrap()
+                ),
+                "net_id: config service C00053, chain 00003C".to_string(),
+                "devaddr constraint 60000000 - 60000007 is only in the config service".to_string(),
+                "devaddr constraint 60000000 - 60000006 is only on chain".to_string(),
+                "approved on chain is true but the config service has the Org locked".to_string(),
+            ],
+            chain.divergences(&org)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::Result;
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Parse `data` as json, rejecting fields that `T` does not know about when
+/// `strict` is set.
+///
+/// Fields are reported as serde ignores them while parsing. Content serde
+/// buffers first, a `#[serde(flatten)]` struct or the fields of an internally
+/// tagged enum, is parsed without being seen, so strict types deserialize
+/// without flattening.
+pub fn from_str<T: DeserializeOwned>(data: &str, strict: bool) -> Result<T> {
+    if !strict {
+        return Ok(serde_json::from_str(data)?);
+    }
+    from_value(serde_json::from_str(data)?, strict)
+}
+
+/// [`from_str`] for json that is already parsed.
+pub fn from_value<T: DeserializeOwned>(input: Value, strict: bool) -> Result<T> {
+    if !strict {
+        return Ok(serde_json::from_value(input)?);
+    }
+    let mut unknown = vec![];
+    let parsed = serde_ignored::deserialize(input, |path| unknown.push(path.to_string()))?;
+    if !unknown.is_empty() {
+        return Err(anyhow!("unknown fields: {}", unknown.join(", ")));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_str;
+    use crate::SkfUpdate;
+
+    #[test]
+    fn strict_rejects_unknown_fields() {
+        let data = r#"[{
+            "devaddr": "00000001",
+            "session_key": "key",
+            "action": "add",
+            "max_copys": 3
+        }]"#;
+
+        let lenient: Vec<SkfUpdate> = from_str(data, false).unwrap();
+        assert_eq!(None, lenient[0].max_copies);
+
+        let err = from_str::<Vec<SkfUpdate>>(data, true).unwrap_err();
+        assert_eq!("unknown fields: 0.max_copys", err.to_string());
+    }
+
+    #[test]
+    fn strict_accepts_flattened_fields() {
+        let data = r#"{
+            "id": "",
+            "net_id": "000024",
+            "oui": 1,
+            "server": {"host": "", "port": 0, "protocol": {"type": "packet_router"}},
+            "max_copies": 5,
+            "active": true,
+            "locked": false,
+            "ignore_empty_skf": false,
+            "euis": []
+        }"#;
+        assert!(from_str::<crate::route::RouteFile>(data, true).is_ok());
+
+        let typo = data.replace("\"active\"", "\"activ\": true, \"active\"");
+        let err = from_str::<crate::route::RouteFile>(&typo, true).unwrap_err();
+        assert_eq!("unknown fields: activ", err.to_string());
+
+        let typo = data.replace("\"port\"", "\"prot\": 1, \"port\"");
+        let err = from_str::<crate::route::RouteFile>(&typo, true).unwrap_err();
+        assert_eq!("unknown fields: server.prot", err.to_string());
+    }
+}
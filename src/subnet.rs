@@ -1,6 +1,6 @@
 use anyhow::anyhow;
 use ipnet;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use std::net;
 
 use crate::{
@@ -8,18 +8,55 @@ use crate::{
     DevaddrRange, Result,
 };
 
-#[derive(Debug, Serialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct DevaddrSubnet {
     range: DevaddrConstraint,
     pub subnets: Vec<String>,
 }
 
+/// Accepts either a bare range or the `{range, subnets}` shape constraints
+/// are printed in. Subnets are derived from the range and ignored on input.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "ConstraintInput")]
 pub struct DevaddrConstraint {
     pub start_addr: hex_field::HexDevAddr,
     pub end_addr: hex_field::HexDevAddr,
 }
 
+#[derive(Deserialize)]
+struct RangeInput {
+    start_addr: hex_field::HexDevAddr,
+    end_addr: hex_field::HexDevAddr,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConstraintInput {
+    Subnet { range: RangeInput },
+    Range(RangeInput),
+}
+
+impl TryFrom<ConstraintInput> for DevaddrConstraint {
+    type Error = anyhow::Error;
+
+    fn try_from(input: ConstraintInput) -> Result<Self> {
+        let (ConstraintInput::Subnet { range } | ConstraintInput::Range(range)) = input;
+        DevaddrConstraint::new(range.start_addr, range.end_addr)
+    }
+}
+
+/// Serialize constraints as [`DevaddrSubnet`]s.
+pub fn serialize_with_subnets<S: Serializer>(
+    constraints: &[DevaddrConstraint],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        constraints
+            .iter()
+            .map(|constraint| constraint.clone().to_subnet()),
+    )
+}
+
 /// Convenience to get subnet masks from an existing DevaddrRange.
 ///
 /// The range is inclusive. (start..=end)
@@ -50,6 +87,48 @@ impl DevaddrConstraint {
         })
     }
 
+    /// Parse a subnet in the `<devaddr>/<prefix>` form constraints are printed in.
+    pub fn from_cidr(s: &str) -> Result<Self> {
+        let (addr, prefix) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow!("expected <devaddr>/<prefix>, got {s}"))?;
+        let addr = hex_field::strip_hex_prefix(addr);
+        let addr = u32::from_str_radix(addr, 16)
+            .map_err(|_| anyhow!("devaddr {addr} is not 8 hex characters"))?;
+        let net = ipnet::Ipv4Net::new(net::Ipv4Addr::from(addr), prefix.parse()?)?;
+        if net.network() != net.addr() {
+            return Err(anyhow!(
+                "{s} is not aligned to its prefix, did you mean {}/{}?",
+                HexDevAddr::from(net.network()),
+                net.prefix_len()
+            ));
+        }
+        Self::new(net.network().into(), net.broadcast().into())
+    }
+
+    /// Every devaddr starting with the hex `prefix`, e.g. `4800` is
+    /// 48000000 through 4800FFFF.
+    pub fn from_prefix(prefix: &str) -> Result<Self> {
+        let prefix = hex_field::strip_hex_prefix(prefix);
+        if prefix.is_empty() || prefix.len() > 8 {
+            return Err(anyhow!(
+                "devaddr prefix {prefix} is not 1 to 8 hex characters"
+            ));
+        }
+        let value = u64::from_str_radix(prefix, 16)
+            .map_err(|_| anyhow!("devaddr prefix {prefix} is not hex"))?;
+        let free_bits = 4 * (8 - prefix.len() as u32);
+        let start = value << free_bits;
+        Self::new(
+            hex_field::devaddr(start),
+            hex_field::devaddr(start | ((1 << free_bits) - 1)),
+        )
+    }
+
+    pub fn contains(&self, devaddr: HexDevAddr) -> bool {
+        self.start_addr <= devaddr && devaddr <= self.end_addr
+    }
+
     pub fn to_subnet(self) -> DevaddrSubnet {
         let start = net::Ipv4Addr::from(self.start_addr.0 as u32);
         let end = net::Ipv4Addr::from(self.end_addr.0 as u32);
@@ -100,6 +179,56 @@ impl HexDevAddr {
             end_addr: end,
         }
     }
+
+    /// The `count` devaddrs starting at this one, erroring instead of
+    /// running past the last devaddr.
+    pub fn checked_range(self, count: u32) -> Result<DevaddrConstraint> {
+        if count == 0 {
+            return Err(anyhow!("devaddr count must be at least 1"));
+        }
+        let end = self.0 + (count - 1) as u64;
+        if end > u32::MAX as u64 {
+            return Err(anyhow!(
+                "{count} devaddrs starting at {self} run past FFFFFFFF"
+            ));
+        }
+        Ok(self.to_range(count))
+    }
+}
+
+/// The first block of `count` devaddrs inside `constraints` that overlaps
+/// none of the `used` ranges. Blocks start on a multiple of `count` rounded
+/// up to a power of two, so a power of two `count` is a single subnet.
+pub fn next_free_block(
+    constraints: &[DevaddrConstraint],
+    used: &[DevaddrConstraint],
+    count: u32,
+) -> Option<DevaddrConstraint> {
+    if count == 0 {
+        return None;
+    }
+    let size = count as u64;
+    let align = size.next_power_of_two();
+    let align_up = |addr: u64| addr.div_ceil(align) * align;
+
+    let mut constraints = constraints.to_vec();
+    constraints.sort_by_key(|constraint| constraint.start_addr.0);
+    for constraint in constraints {
+        let mut start = align_up(constraint.start_addr.0);
+        while start + size - 1 <= constraint.end_addr.0 {
+            let end = start + size - 1;
+            let overlap_end = used
+                .iter()
+                .filter(|range| range.start_addr.0 <= end && start <= range.end_addr.0)
+                .map(|range| range.end_addr.0)
+                .max();
+            match overlap_end {
+                None => return Some(HexDevAddr::from(start).to_range(count)),
+                Some(overlap_end) => start = align_up(overlap_end + 1),
+            }
+        }
+    }
+    None
 }
 
 impl From<net::Ipv4Addr> for HexDevAddr {
@@ -111,7 +240,7 @@ impl From<net::Ipv4Addr> for HexDevAddr {
 
 #[cfg(test)]
 mod tests {
-    use super::DevaddrSubnet;
+    use super::{next_free_block, DevaddrSubnet};
     use crate::{hex_field, subnet::DevaddrConstraint};
     use pretty_assertions::assert_eq;
 
@@ -138,6 +267,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn counted_ranges() {
+        let range = hex_field::devaddr(0x4800_0000).checked_range(1024).unwrap();
+        assert_eq!(hex_field::devaddr(0x4800_03FF), range.end_addr);
+        assert_eq!(vec!["48000000/22".to_string()], range.to_subnet().subnets);
+
+        assert!(hex_field::devaddr(0x4800_0000).checked_range(0).is_err());
+        assert!(hex_field::devaddr(0xFFFF_FFFF).checked_range(1).is_ok());
+        assert!(hex_field::devaddr(0xFFFF_FFFF).checked_range(2).is_err());
+    }
+
+    #[test]
+    fn next_free_blocks() {
+        let range = |start, end| {
+            DevaddrConstraint::new(hex_field::devaddr(start), hex_field::devaddr(end)).unwrap()
+        };
+        let constraints = [range(0x4800_0000, 0x4800_0FFF)];
+
+        let free = next_free_block(&constraints, &[], 1024).unwrap();
+        assert_eq!(range(0x4800_0000, 0x4800_03FF), free);
+
+        // a partially used block is skipped to the next aligned one
+        let used = [range(0x4800_0000, 0x4800_0009)];
+        let free = next_free_block(&constraints, &used, 1024).unwrap();
+        assert_eq!(range(0x4800_0400, 0x4800_07FF), free);
+        let free = next_free_block(&constraints, &used, 8).unwrap();
+        assert_eq!(range(0x4800_0010, 0x4800_0017), free);
+
+        let used = [range(0x4800_0000, 0x4800_0BFF)];
+        assert!(next_free_block(&constraints, &used, 2048).is_none());
+        assert!(next_free_block(&constraints, &[], 0).is_none());
+    }
+
     #[test]
     fn subnet_mapping() {
         let start = hex_field::devaddr(0x11_22_33_44);
@@ -190,4 +352,64 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn constraint_input_shapes() {
+        let expected = DevaddrConstraint::new(
+            hex_field::devaddr(0x48_00_00_00),
+            hex_field::devaddr(0x48_00_03_ff),
+        )
+        .unwrap();
+
+        let bare = r#"{"start_addr": "48000000", "end_addr": "480003FF"}"#;
+        assert_eq!(expected, serde_json::from_str(bare).unwrap());
+
+        let printed = serde_json::to_string(&expected.clone().to_subnet()).unwrap();
+        assert_eq!(
+            r#"{"range":{"start_addr":"48000000","end_addr":"480003FF"},"subnets":["48000000/22"]}"#,
+            printed
+        );
+        let parsed: DevaddrConstraint = serde_json::from_str(&printed).unwrap();
+        assert_eq!(expected, parsed);
+
+        let backwards = r#"{"start_addr": "480003FF", "end_addr": "48000000"}"#;
+        assert!(serde_json::from_str::<DevaddrConstraint>(backwards).is_err());
+    }
+
+    #[test]
+    fn constraint_from_cidr() {
+        let constraint = DevaddrConstraint::from_cidr("48000800/29").unwrap();
+        assert_eq!(hex_field::devaddr(0x48_00_08_00), constraint.start_addr);
+        assert_eq!(hex_field::devaddr(0x48_00_08_07), constraint.end_addr);
+        assert_eq!(vec!["48000800/29"], constraint.to_subnet().subnets);
+        assert_eq!(
+            constraint,
+            DevaddrConstraint::from_cidr("0x48000800/29").unwrap()
+        );
+
+        assert!(DevaddrConstraint::from_cidr("48000804/29").is_err());
+        assert!(DevaddrConstraint::from_cidr("48000800").is_err());
+        assert!(DevaddrConstraint::from_cidr("48000800/33").is_err());
+        assert!(DevaddrConstraint::from_cidr("4800zz00/29").is_err());
+    }
+
+    #[test]
+    fn constraint_from_prefix() {
+        let constraint = DevaddrConstraint::from_prefix("4800").unwrap();
+        assert_eq!(hex_field::devaddr(0x48_00_00_00), constraint.start_addr);
+        assert_eq!(hex_field::devaddr(0x48_00_FF_FF), constraint.end_addr);
+        assert!(constraint.contains(hex_field::devaddr(0x48_00_12_34)));
+        assert!(!constraint.contains(hex_field::devaddr(0x48_01_00_00)));
+        assert_eq!(
+            constraint,
+            DevaddrConstraint::from_prefix("0x4800").unwrap()
+        );
+
+        let whole = DevaddrConstraint::from_prefix("48001234").unwrap();
+        assert_eq!(whole.start_addr, whole.end_addr);
+
+        assert!(DevaddrConstraint::from_prefix("").is_err());
+        assert!(DevaddrConstraint::from_prefix("480012345").is_err());
+        assert!(DevaddrConstraint::from_prefix("zz").is_err());
+    }
 }
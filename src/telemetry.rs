@@ -0,0 +1,192 @@
+//! Traces of CLI-driven changes. Each command is a span with its OUI and
+//! Route id, config service requests and bulk pipelines are spans within it.
+//!
+//! Spans are always created and cost nothing without a subscriber. With the
+//! `otel` feature and `--otlp-endpoint` they are exported over OTLP, and each
+//! request carries a W3C `traceparent` so the config service's spans join the
+//! CLI's trace.
+
+use crate::{Oui, Result};
+use clap::ArgMatches;
+use std::ffi::OsString;
+use tonic::codegen::http::HeaderMap;
+use tracing::{field::Empty, Span};
+
+/// The `service.name` of exported spans.
+pub const SERVICE_NAME: &str = "helium-config-cli";
+
+/// Export spans to the OTLP collector at `endpoint` until [`shutdown`].
+#[cfg(feature = "otel")]
+pub fn init(endpoint: &str) -> Result {
+    use opentelemetry::{global, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace, Resource};
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)])),
+        )
+        .install_batch(runtime::Tokio)?;
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init(_endpoint: &str) -> Result {
+    Err(anyhow::anyhow!(
+        "--otlp-endpoint needs helium-config-cli built with the otel feature"
+    ))
+}
+
+/// Export the spans still batched, before the command exits.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Add the `traceparent` of `span` to the headers of a request.
+#[cfg(feature = "otel")]
+pub fn inject(span: &Span, headers: &mut HeaderMap) {
+    use opentelemetry::{global, propagation::Injector};
+    use tonic::codegen::http::{HeaderName, HeaderValue};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct Headers<'a>(&'a mut HeaderMap);
+
+    impl Injector for Headers<'_> {
+        fn set(&mut self, key: &str, value: String) {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(key.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                self.0.insert(name, value);
+            }
+        }
+    }
+
+    let context = span.context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut Headers(headers))
+    });
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn inject(_span: &Span, _headers: &mut HeaderMap) {}
+
+/// The span of the command `args` run, named for its subcommands, e.g.
+/// `route skfs add`. `args` that do not parse are left for the real parse to
+/// report.
+pub fn command_span(args: &[OsString], command: clap::Command) -> Span {
+    let Ok(matches) = command.try_get_matches_from(args) else {
+        return Span::none();
+    };
+    let attributes = CommandAttributes::from_matches(&matches);
+    let span = tracing::info_span!(
+        "command",
+        otel.name = %attributes.name,
+        otel.status_code = Empty,
+        oui = Empty,
+        route_id = Empty,
+    );
+    if let Some(oui) = attributes.oui {
+        span.record("oui", oui);
+    }
+    if let Some(route_id) = &attributes.route_id {
+        span.record("route_id", route_id.as_str());
+    }
+    span
+}
+
+/// Mark the command's span as failed.
+pub fn record_error(span: &Span) {
+    span.record("otel.status_code", "ERROR");
+}
+
+/// The span of a request to the config service at `path`, e.g.
+/// `/helium.iot_config.route/update_euis`.
+pub fn rpc_span(path: &str) -> Span {
+    let (service, method) = rpc_name(path);
+    tracing::info_span!(
+        "rpc",
+        otel.name = %format!("{service}/{method}"),
+        otel.kind = "client",
+        rpc.system = "grpc",
+        rpc.service = service,
+        rpc.method = method,
+    )
+}
+
+fn rpc_name(path: &str) -> (&str, &str) {
+    path.trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or((path, ""))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct CommandAttributes {
+    name: String,
+    oui: Option<Oui>,
+    route_id: Option<String>,
+}
+
+impl CommandAttributes {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let mut names = vec![];
+        let mut matches = matches;
+        while let Some((name, sub)) = matches.subcommand() {
+            names.push(name);
+            matches = sub;
+        }
+        Self {
+            name: names.join(" "),
+            oui: matches.try_get_one::<Oui>("oui").ok().flatten().copied(),
+            route_id: matches
+                .try_get_one::<String>("route_id")
+                .ok()
+                .flatten()
+                .cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rpc_name, CommandAttributes};
+    use crate::{cmds::Cli, testing::fixtures};
+    use clap::CommandFactory;
+
+    #[test]
+    fn command_attributes() {
+        let matches = Cli::command()
+            .try_get_matches_from(["cli", "route", "skfs", "ls", "-r", fixtures::ROUTE_ID])
+            .unwrap();
+        assert_eq!(
+            CommandAttributes {
+                name: "route skfs list".to_string(),
+                oui: None,
+                route_id: Some(fixtures::ROUTE_ID.to_string()),
+            },
+            CommandAttributes::from_matches(&matches)
+        );
+        let matches = Cli::command()
+            .try_get_matches_from(["cli", "route", "list", "--oui", "7"])
+            .unwrap();
+        assert_eq!(Some(7), CommandAttributes::from_matches(&matches).oui);
+
+        assert_eq!(
+            ("helium.iot_config.route", "update_euis"),
+            rpc_name("/helium.iot_config.route/update_euis")
+        );
+    }
+}
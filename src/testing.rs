@@ -0,0 +1,286 @@
+//! In-memory fakes and fixtures for testing command logic without a config
+//! service. Enabled by the `testing` feature.
+
+use crate::{
+    api::{OrgApi, RouteApi},
+    hex_field,
+    route::Route,
+    subnet::DevaddrConstraint,
+    Org, OrgList, OrgResponse, Oui, Result, RouteList,
+};
+use anyhow::anyhow;
+use helium_crypto::{KeyTag, KeyType, Keypair, Network, PublicKey};
+use rand::{rngs::OsRng, Rng};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Routes and Orgs kept in memory. The fakes made from one storage share it,
+/// as the config service's Route and Org services share its database.
+#[derive(Debug, Default, Clone)]
+pub struct FakeStorage {
+    routes: Arc<Mutex<BTreeMap<String, Route>>>,
+    orgs: Arc<Mutex<BTreeMap<Oui, OrgResponse>>>,
+}
+
+impl FakeStorage {
+    pub fn with_routes(self, routes: impl IntoIterator<Item = Route>) -> Self {
+        self.lock_routes()
+            .extend(routes.into_iter().map(|route| (route.id.clone(), route)));
+        self
+    }
+
+    pub fn with_orgs(self, orgs: impl IntoIterator<Item = OrgResponse>) -> Self {
+        self.lock_orgs()
+            .extend(orgs.into_iter().map(|org| (org.org.oui, org)));
+        self
+    }
+
+    pub fn route_api(&self) -> FakeRouteApi {
+        FakeRouteApi {
+            storage: self.clone(),
+        }
+    }
+
+    pub fn org_api(&self) -> FakeOrgApi {
+        FakeOrgApi {
+            storage: self.clone(),
+        }
+    }
+
+    fn lock_routes(&self) -> MutexGuard<'_, BTreeMap<String, Route>> {
+        self.routes.lock().expect("fake route storage lock")
+    }
+
+    fn lock_orgs(&self) -> MutexGuard<'_, BTreeMap<Oui, OrgResponse>> {
+        self.orgs.lock().expect("fake org storage lock")
+    }
+}
+
+/// Routes in a [`FakeStorage`]. Created Routes get a random ID, like the
+/// config service assigns.
+#[derive(Debug, Default, Clone)]
+pub struct FakeRouteApi {
+    storage: FakeStorage,
+}
+
+impl FakeRouteApi {
+    /// A fake with its own storage holding `routes`.
+    pub fn with_routes(routes: impl IntoIterator<Item = Route>) -> Self {
+        FakeStorage::default().with_routes(routes).route_api()
+    }
+}
+
+impl RouteApi for FakeRouteApi {
+    async fn list(&mut self, oui: Oui, _keypair: &Keypair) -> Result<RouteList> {
+        Ok(RouteList {
+            routes: self
+                .storage
+                .lock_routes()
+                .values()
+                .filter(|route| route.oui == oui)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    async fn get(&mut self, id: &str, _keypair: &Keypair) -> Result<Route> {
+        self.storage
+            .lock_routes()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| anyhow!("route {id} not found"))
+    }
+
+    async fn create_route(&mut self, route: Route, _keypair: &Keypair) -> Result<Route> {
+        let route = Route {
+            id: random_route_id(),
+            ..route
+        };
+        self.storage
+            .lock_routes()
+            .insert(route.id.clone(), route.clone());
+        Ok(route)
+    }
+
+    async fn push(&mut self, route: Route, _keypair: &Keypair) -> Result<Route> {
+        let mut routes = self.storage.lock_routes();
+        let Some(stored) = routes.get_mut(&route.id) else {
+            return Err(anyhow!("route {} not found", route.id));
+        };
+        *stored = route.clone();
+        Ok(route)
+    }
+
+    async fn delete(&mut self, id: &str, _keypair: &Keypair) -> Result<Route> {
+        self.storage
+            .lock_routes()
+            .remove(id)
+            .ok_or_else(|| anyhow!("route {id} not found"))
+    }
+}
+
+/// Orgs in a [`FakeStorage`].
+#[derive(Debug, Default, Clone)]
+pub struct FakeOrgApi {
+    storage: FakeStorage,
+}
+
+impl OrgApi for FakeOrgApi {
+    async fn list(&mut self) -> Result<OrgList> {
+        Ok(OrgList {
+            orgs: self
+                .storage
+                .lock_orgs()
+                .values()
+                .map(|res| res.org.clone())
+                .collect(),
+        })
+    }
+
+    async fn get(&mut self, oui: Oui) -> Result<OrgResponse> {
+        self.storage
+            .lock_orgs()
+            .get(&oui)
+            .cloned()
+            .ok_or_else(|| anyhow!("org {oui} not found"))
+    }
+}
+
+fn random_route_id() -> String {
+    let bytes: [u8; 16] = OsRng.gen();
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Sample data shaped like config service responses and CLI input files.
+pub mod fixtures {
+    use super::*;
+
+    pub const ROUTE_ID: &str = "2ab3a45a-1d0c-4d5e-9f8a-1b2c3d4e5f60";
+
+    /// A session key filter update file, as read by `route skfs update`.
+    pub const SKF_UPDATES_JSON: &str = r#"[
+    {"devaddr": "00000001", "session_key": "key-one", "action": "add", "max_copies": 3},
+    {"devaddr": "00000002", "session_key": "key-two", "action": "remove", "max_copies": null}
+]"#;
+
+    pub fn keypair() -> Keypair {
+        Keypair::generate(
+            KeyTag {
+                network: Network::MainNet,
+                key_type: KeyType::Ed25519,
+            },
+            &mut OsRng,
+        )
+    }
+
+    /// A packet router Route with ID [`ROUTE_ID`].
+    pub fn route(oui: Oui) -> Route {
+        Route {
+            id: ROUTE_ID.to_string(),
+            ..Route::new(hex_field::net_id(0xC00053), oui, 5)
+        }
+    }
+
+    /// An unlocked Org owned and paid for by `owner`, with one constraint.
+    pub fn org(oui: Oui, owner: &PublicKey) -> OrgResponse {
+        OrgResponse {
+            org: Org {
+                oui,
+                owner: owner.clone(),
+                payer: owner.clone(),
+                delegate_keys: vec![],
+                locked: false,
+            },
+            net_id: hex_field::net_id(0xC00053),
+            devaddr_constraints: vec![DevaddrConstraint::new(
+                hex_field::devaddr(0x6000_0000),
+                hex_field::devaddr(0x6000_0007),
+            )
+            .expect("valid fixture constraint")],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fixtures, FakeRouteApi, FakeStorage};
+    use crate::{
+        api::{OrgApi, RouteApi},
+        route::validate_route_id,
+        SkfUpdate,
+    };
+
+    #[tokio::test]
+    async fn fake_route_api() {
+        let keypair = fixtures::keypair();
+        let mut api = FakeRouteApi::with_routes([fixtures::route(1)]);
+
+        let created = api
+            .create_route(fixtures::route(2), &keypair)
+            .await
+            .unwrap();
+        assert!(validate_route_id(&created.id).is_ok());
+        assert_ne!(fixtures::ROUTE_ID, created.id);
+        assert_eq!(1, api.list(2, &keypair).await.unwrap().routes.len());
+
+        let mut route = api.get(fixtures::ROUTE_ID, &keypair).await.unwrap();
+        route.max_copies = 9;
+        api.push(route, &keypair).await.unwrap();
+        assert_eq!(
+            9,
+            api.get(fixtures::ROUTE_ID, &keypair)
+                .await
+                .unwrap()
+                .max_copies
+        );
+
+        api.delete(fixtures::ROUTE_ID, &keypair).await.unwrap();
+        assert!(api.get(fixtures::ROUTE_ID, &keypair).await.is_err());
+        assert!(api.push(fixtures::route(1), &keypair).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fake_org_api() {
+        let owner = fixtures::keypair().public_key().clone();
+        let storage = FakeStorage::default().with_orgs([fixtures::org(1, &owner)]);
+        let mut api = storage.org_api();
+        assert!(api.list().await.unwrap().orgs[0].is_managed_by(&owner));
+        assert_eq!(1, api.get(1).await.unwrap().devaddr_constraints.len());
+        assert!(api.get(2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fakes_share_storage() {
+        let keypair = fixtures::keypair();
+        let storage = FakeStorage::default();
+        let created = storage
+            .route_api()
+            .create_route(fixtures::route(1), &keypair)
+            .await
+            .unwrap();
+        assert_eq!(
+            created,
+            storage
+                .route_api()
+                .get(&created.id, &keypair)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn skf_fixture_parses() {
+        let updates: Vec<SkfUpdate> = serde_json::from_str(fixtures::SKF_UPDATES_JSON).unwrap();
+        assert_eq!(2, updates.len());
+    }
+}
@@ -0,0 +1,154 @@
+//! Timestamps as printed by commands: the raw epoch for scripts, an RFC3339
+//! time in the local time zone (or UTC with `--utc`), and how long ago it was.
+
+use crate::Result;
+use anyhow::anyhow;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::{Serialize, Serializer};
+use std::{
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static UTC: OnceLock<bool> = OnceLock::new();
+
+/// Render times in UTC rather than the local time zone, set once from `--utc`.
+pub fn set_utc(utc: bool) -> Result {
+    UTC.set(utc)
+        .map_err(|_| anyhow!("timestamp time zone is already set"))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Timestamp {
+    /// The epoch as received, in the unit of its source
+    pub epoch: i64,
+    /// RFC3339
+    pub time: String,
+    /// e.g. `3h ago`
+    pub ago: String,
+}
+
+impl Timestamp {
+    pub fn from_unix_secs(secs: i64) -> Result<Self> {
+        let time = DateTime::from_timestamp(secs, 0)
+            .ok_or_else(|| anyhow!("unix time {secs} is out of range"))?;
+        Ok(Self::new(secs, time, Utc::now(), is_utc()))
+    }
+
+    pub fn from_unix_millis(millis: i64) -> Result<Self> {
+        let time = DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| anyhow!("unix time {millis}ms is out of range"))?;
+        Ok(Self::new(millis, time, Utc::now(), is_utc()))
+    }
+
+    fn new(epoch: i64, time: DateTime<Utc>, now: DateTime<Utc>, utc: bool) -> Self {
+        let rfc3339 = if utc {
+            time.to_rfc3339_opts(SecondsFormat::Secs, true)
+        } else {
+            time.with_timezone(&Local)
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+        Self {
+            epoch,
+            time: rfc3339,
+            ago: ago((now - time).num_seconds()),
+        }
+    }
+}
+
+fn is_utc() -> bool {
+    UTC.get().copied().unwrap_or_default()
+}
+
+/// The largest whole unit of `secs`, in the past when positive.
+fn ago(secs: i64) -> String {
+    let magnitude = secs.unsigned_abs();
+    let (count, unit) = match magnitude {
+        0..=59 => (magnitude, "s"),
+        60..=3_599 => (magnitude / 60, "m"),
+        3_600..=86_399 => (magnitude / 3_600, "h"),
+        _ => (magnitude / 86_400, "d"),
+    };
+    if secs < 0 {
+        format!("in {count}{unit}")
+    } else {
+        format!("{count}{unit} ago")
+    }
+}
+
+/// Unix milliseconds now.
+pub fn now_millis() -> Result<u64> {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    Ok(u64::try_from(millis)?)
+}
+
+/// Unix milliseconds of a `--since` argument, either an RFC3339 time or how
+/// long ago, e.g. `2024-03-01T12:00:00Z` or `15m`.
+pub fn parse_since(since: &str) -> Result<u64> {
+    since_at(since, SystemTime::now())
+}
+
+fn since_at(since: &str, now: SystemTime) -> Result<u64> {
+    let time = match humantime::parse_duration(since) {
+        Ok(ago) => now
+            .checked_sub(ago)
+            .ok_or_else(|| anyhow!("{since} ago is before the unix epoch"))?,
+        Err(_) => humantime::parse_rfc3339_weak(since)
+            .map_err(|_| anyhow!("expected an RFC3339 time or a duration, got {since}"))?,
+    };
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| anyhow!("{since} is before the unix epoch"))?
+        .as_millis();
+    Ok(u64::try_from(millis)?)
+}
+
+/// `serialize_with` for unix millisecond fields.
+pub fn serialize_millis<S: Serializer>(millis: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    let millis = i64::try_from(*millis).map_err(serde::ser::Error::custom)?;
+    Timestamp::from_unix_millis(millis)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ago, since_at, Timestamp};
+    use chrono::DateTime;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn timestamps_are_rendered() {
+        let time = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let now = DateTime::from_timestamp(1_700_010_800, 0).unwrap();
+        assert_eq!(
+            Timestamp {
+                epoch: 1_700_000_000,
+                time: "2023-11-14T22:13:20Z".to_string(),
+                ago: "3h ago".to_string(),
+            },
+            Timestamp::new(1_700_000_000, time, now, true)
+        );
+
+        assert_eq!("0s ago", ago(0));
+        assert_eq!("59s ago", ago(59));
+        assert_eq!("1m ago", ago(119));
+        assert_eq!("2d ago", ago(2 * 86_400 + 5));
+        assert_eq!("in 5m", ago(-300));
+    }
+
+    #[test]
+    fn since_is_a_time_or_ago() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_010_800);
+        assert_eq!(1_700_000_000_000, since_at("3h", now).unwrap());
+        assert_eq!(
+            1_700_000_000_000,
+            since_at("2023-11-14T22:13:20Z", now).unwrap()
+        );
+        assert_eq!(
+            1_700_000_000_000,
+            since_at("2023-11-14 22:13:20", now).unwrap()
+        );
+        assert!(since_at("yesterday", now).is_err());
+    }
+}
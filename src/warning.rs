@@ -0,0 +1,26 @@
+//! Warnings go to stderr so stdout carries only a command's data and can be
+//! piped, e.g. into `jq`. With `--warnings-as-errors` the first warning
+//! fails the command instead.
+
+use crate::Result;
+use anyhow::anyhow;
+use std::{fmt::Display, sync::OnceLock};
+
+static AS_ERRORS: OnceLock<bool> = OnceLock::new();
+
+/// Fail on warnings, set once from `--warnings-as-errors`.
+pub fn set_as_errors(as_errors: bool) -> Result {
+    AS_ERRORS
+        .set(as_errors)
+        .map_err(|_| anyhow!("warnings as errors is already set"))
+}
+
+/// Print `warning` to stderr, or return it as an error with
+/// `--warnings-as-errors`.
+pub fn warn(warning: impl Display) -> Result {
+    if AS_ERRORS.get().copied().unwrap_or_default() {
+        return Err(anyhow!("{warning} (--warnings-as-errors)"));
+    }
+    eprintln!("WARNING: {warning}");
+    Ok(())
+}
@@ -7,7 +7,7 @@ use helium_config_service_cli::{
     HeliumNetId, OrgResponse, Result,
 };
 use helium_crypto::PublicKey;
-use std::{path::PathBuf, str::FromStr};
+use std::{num::NonZeroUsize, path::PathBuf, str::FromStr};
 use tracing::info;
 
 pub const CONFIG_HOST: &str = "http://127.0.0.1:50051";
@@ -29,6 +29,8 @@ pub const CONFIG_PUBKEY: &str = "config-server-signing-pubkey";
 pub fn generate_keypair(path: PathBuf) -> Result<PublicKey> {
     let out = cmds::env::generate_keypair(cmds::GenerateKeypair {
         network: cmds::env::NetworkArg::Mainnet,
+        key_type: cmds::env::KeyTypeArg::Ed25519,
+        qr: false,
         out_file: path.clone(),
         commit: true,
     })?;
@@ -69,7 +71,13 @@ pub async fn create_helium_org(
 
 pub async fn ensure_no_routes(oui: u64, keypair_path: PathBuf) -> Result {
     let out = cmds::route::list_routes(ListRoutes {
-        oui,
+        oui: Some(oui),
+        sort: None,
+        columns: vec![],
+        output: RouteListFormat::Json,
+        with_counts: false,
+        no_cache: true,
+        concurrency: NonZeroUsize::new(8).unwrap(),
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
         config_pubkey: CONFIG_PUBKEY.to_string(),
@@ -91,11 +99,14 @@ pub async fn create_empty_route(
 ) -> Result<Route> {
     let out1 = cmds::route::new_route(NewRoute {
         net_id,
-        oui,
+        oui: Some(oui),
         max_copies: 5,
+        from_file: None,
+        wildcard_app_eui: false,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
         config_pubkey: CONFIG_PUBKEY.to_string(),
+        strict: false,
         commit: true,
     })
     .await?;
@@ -129,11 +140,15 @@ pub async fn ensure_no_devaddrs(route_id: &str, keypair_path: PathBuf) -> Result
 pub async fn ensure_num_euis(eui_count: usize, route_id: &str, keypair_path: PathBuf) -> Result {
     let out = cmds::route::euis::list_euis(ListEuis {
         route_id: route_id.to_string(),
+        out_format: OutFormat::Json,
+        out_file: None,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
         config_pubkey: CONFIG_PUBKEY.to_string(),
         app_eui: None,
         dev_eui: None,
+        wildcards_only: false,
+        count: false,
     })
     .await?;
     info!("{out}");
@@ -153,6 +168,9 @@ pub async fn ensure_num_devaddrs(
 ) -> Result {
     let out = cmds::route::devaddrs::list_devaddrs(ListDevaddrs {
         route_id: route_id.to_string(),
+        out_format: OutFormat::Json,
+        out_file: None,
+        count: false,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
         config_pubkey: CONFIG_PUBKEY.to_string(),
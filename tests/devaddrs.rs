@@ -36,11 +36,14 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
     // devaddr outside org constraint, should not add
     let out1 = cmds::route::devaddrs::add_devaddr(AddDevaddr {
         start_addr: hex_field::devaddr(1),
-        end_addr: hex_field::devaddr(2),
+        end_addr: Some(hex_field::devaddr(2)),
+        count: None,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -51,24 +54,47 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
     let devaddr_range = constraint.start_addr.to_range(3);
     let out2 = cmds::route::devaddrs::add_devaddr(AddDevaddr {
         start_addr: devaddr_range.start_addr,
-        end_addr: devaddr_range.end_addr,
+        end_addr: Some(devaddr_range.end_addr),
+        count: None,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
     println!("2: {out2}");
     common::ensure_num_devaddrs(1, &route.id, keypair_path.clone()).await?;
 
+    // Swap it for a wider range in one request
+    let replacement = constraint.start_addr.to_range(5);
+    let replaced = cmds::route::devaddrs::replace_devaddr(ReplaceDevaddr {
+        route_id: route.id.clone(),
+        remove_start: devaddr_range.start_addr,
+        remove_end: devaddr_range.end_addr,
+        add_start: replacement.start_addr,
+        add_end: replacement.end_addr,
+        config_host: config_host.clone(),
+        config_pubkey: config_pubkey.clone(),
+        keypair: keypair_path.clone(),
+        no_preflight: false,
+        commit: true,
+    })
+    .await?;
+    println!("replaced: {replaced}");
+    common::ensure_num_devaddrs(1, &route.id, keypair_path.clone()).await?;
+
     let out3 = cmds::route::devaddrs::remove_devaddr(RemoveDevaddr {
-        start_addr: devaddr_range.start_addr,
-        end_addr: devaddr_range.end_addr,
+        start_addr: replacement.start_addr,
+        end_addr: replacement.end_addr,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -102,6 +128,7 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
     let out5 = cmds::route::devaddrs::clear_devaddrs(ClearDevaddrs {
         route_id: route.id.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         commit: true,
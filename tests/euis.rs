@@ -1,7 +1,7 @@
 use helium_config_service_cli::{
     client,
     cmds::{self, *},
-    hex_field, Eui, Result,
+    hex_field, Eui, Msg, Result,
 };
 use temp_dir::TempDir;
 use tracing::info;
@@ -32,11 +32,14 @@ async fn create_route_and_add_remove_euis() -> Result {
     // Add an EUI
     let out1 = cmds::route::euis::add_eui(AddEui {
         dev_eui: hex_field::eui(1),
-        app_eui: hex_field::eui(2),
+        app_eui: Some(hex_field::eui(2)),
+        wildcard_app_eui: false,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -51,12 +54,29 @@ async fn create_route_and_add_remove_euis() -> Result {
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
     println!("2: {out2}");
     common::ensure_no_euis(&route.id, keypair_path.clone()).await?;
 
+    // Removing it again is a no-op with --idempotent
+    let out3 = cmds::route::euis::remove_eui(RemoveEui {
+        dev_eui: hex_field::eui(1),
+        app_eui: hex_field::eui(2),
+        route_id: route.id.clone(),
+        config_host: config_host.clone(),
+        config_pubkey: config_pubkey.clone(),
+        keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: true,
+        commit: true,
+    })
+    .await?;
+    assert!(matches!(out3, Msg::Success(_)), "{out3}");
+
     // Add many Euis to delete
     let mut eui_client = client::EuiClient::new(common::CONFIG_HOST, common::CONFIG_PUBKEY).await?;
     let mut euis = vec![];
@@ -33,6 +33,10 @@ async fn create_route_and_update_server() -> Result {
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         stats: false,
+        with_members: false,
+        counts_only: false,
+        oui: None,
+        no_cache: true,
     })
     .await?;
     info!("{out1}");
@@ -40,10 +44,19 @@ async fn create_route_and_update_server() -> Result {
 
     // Set packet-router protocol
     let out2 = cmds::route::update_packet_router(UpdatePacketRouter {
-        route_id: route.id.clone(),
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
+        allow_protocol_change: true,
         commit: true,
     })
     .await?;
@@ -53,14 +66,24 @@ async fn create_route_and_update_server() -> Result {
 
     // Set Http Protocol
     let out3 = cmds::route::update_http(UpdateHttp {
-        route_id: route.id.clone(),
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
         dedupe_timeout: 234,
         path: "path".to_string(),
         receiver_nsid: None,
         auth_header: Some("test-header".to_string()),
+        auth_header_file: None,
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
+        allow_protocol_change: true,
         commit: true,
     })
     .await?;
@@ -85,12 +108,22 @@ async fn create_route_and_update_server() -> Result {
 
     // Set GWMP protocol
     let out4 = cmds::route::add_gwmp_region(AddGwmpRegion {
-        route_id: route.id.clone(),
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
         region: helium_config_service_cli::region::Region::As923_1a,
         region_port: 9001,
+        replace: false,
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
+        allow_protocol_change: true,
         commit: true,
     })
     .await?;
@@ -104,12 +137,22 @@ async fn create_route_and_update_server() -> Result {
     assert_eq!(1, gwmp_protocol.mapping.len());
 
     let out5 = cmds::route::add_gwmp_region(AddGwmpRegion {
-        route_id: route.id.clone(),
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
         region: helium_config_service_cli::region::Region::Eu433,
         region_port: 9002,
+        replace: false,
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
+        allow_protocol_change: true,
         commit: true,
     })
     .await?;
@@ -123,9 +166,17 @@ async fn create_route_and_update_server() -> Result {
     assert_eq!(2, gwmp_protocol.mapping.len());
 
     let out6 = cmds::route::remove_gwmp_region(RemoveGwmpRegion {
-        route_id: route.id.clone(),
-        region: helium_config_service_cli::region::Region::As923_1a,
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
+        regions: vec![helium_config_service_cli::region::Region::As923_1a],
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         commit: true,
@@ -140,6 +191,26 @@ async fn create_route_and_update_server() -> Result {
         .inner_gwmp()?;
     assert_eq!(1, gwmp_protocol.mapping.len());
 
+    let out7 = cmds::route::clear_protocol(ClearProtocol {
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
+        keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: true,
+        backup_dir: None,
+        backup_retention: 20,
+        config_host: config_host.clone(),
+        config_pubkey: config_pubkey.clone(),
+        commit: true,
+    })
+    .await?;
+    info!("{out7}");
+    let cleared_route = common::get_route(&route.id, keypair_path.clone()).await?;
+    assert!(cleared_route.server.protocol.is_none());
+
     Ok(())
 }
 
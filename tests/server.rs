@@ -32,6 +32,10 @@ async fn create_route_and_update_server() -> Result {
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         stats: false,
+        with_members: false,
+        counts_only: false,
+        oui: None,
+        no_cache: true,
     })
     .await?;
     info!("{out1}");
@@ -40,10 +44,18 @@ async fn create_route_and_update_server() -> Result {
 
     // Update the server and port
     let out2 = cmds::route::update_server(UpdateServer {
-        route_id: route.id.clone(),
+        target: RouteTarget {
+            route_id: Some(route.id.clone()),
+            route_file: None,
+            strict: false,
+        },
         host: "www.example.com".to_string(),
         port: 1337,
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         commit: true,
@@ -34,6 +34,10 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     // List session key filters, there are none
     let out = cmds::route::skfs::list_filters(ListFilters {
         route_id: route.id.clone(),
+        out_format: OutFormat::Json,
+        out_file: None,
+        subnet: None,
+        devaddr_prefix: None,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
@@ -48,12 +52,15 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     // Add 2 session key filters
     let out = cmds::route::skfs::add_filter(AddFilter {
         route_id: route.id.clone(),
-        devaddr: hex_field::devaddr(1),
-        session_key: "key-one".to_string(),
+        devaddr: Some(hex_field::devaddr(1)),
+        session_key: Some("key-one".to_string()),
         max_copies: Some(3),
+        stdin: false,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -61,12 +68,15 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
 
     let out = cmds::route::skfs::add_filter(AddFilter {
         route_id: route.id.clone(),
-        devaddr: hex_field::devaddr(2),
-        session_key: "key-two".to_string(),
+        devaddr: Some(hex_field::devaddr(2)),
+        session_key: Some("key-two".to_string()),
         max_copies: Some(3),
+        stdin: false,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -75,6 +85,10 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     // List session key filters again, expecting 2
     let out = cmds::route::skfs::list_filters(ListFilters {
         route_id: route.id.clone(),
+        out_format: OutFormat::Json,
+        out_file: None,
+        subnet: None,
+        devaddr_prefix: None,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
@@ -105,6 +119,32 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         .await?;
     assert_eq!(1, filters.len());
 
+    // Change max copies without removing the filter
+    let out = cmds::route::skfs::set_max_copies(SetFilterMaxCopies {
+        route_id: route.id.clone(),
+        devaddr: hex_field::devaddr(1),
+        session_key: "key-one".to_string(),
+        max_copies: 5,
+        config_host: config_host.clone(),
+        config_pubkey: config_pubkey.clone(),
+        keypair: keypair_path.clone(),
+        no_preflight: false,
+        commit: true,
+    })
+    .await?;
+    info!("set max copies: {out}");
+    let filters = skf_client
+        .get_filters(
+            &route.id,
+            hex_field::devaddr(1),
+            &keypair_path.to_keypair()?,
+        )
+        .await?;
+    assert_eq!(
+        vec![Some(5)],
+        filters.iter().map(|f| f.max_copies).collect::<Vec<_>>()
+    );
+
     // Remove both session key filters
     let out = cmds::route::skfs::remove_filter(RemoveFilter {
         route_id: route.id.clone(),
@@ -113,6 +153,8 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -125,6 +167,8 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        idempotent: false,
         commit: true,
     })
     .await?;
@@ -133,6 +177,10 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     // List session key filters, expecting none
     let out = cmds::route::skfs::list_filters(ListFilters {
         route_id: route.id.clone(),
+        out_format: OutFormat::Json,
+        out_file: None,
+        subnet: None,
+        devaddr_prefix: None,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
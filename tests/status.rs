@@ -32,6 +32,10 @@ async fn create_route_and_update_server() -> Result {
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         stats: false,
+        with_members: false,
+        counts_only: false,
+        oui: None,
+        no_cache: true,
     })
     .await?;
     info!("{out}");
@@ -40,7 +44,13 @@ async fn create_route_and_update_server() -> Result {
     // Disable the Route
     let out = cmds::route::deactivate_route(DeactivateRoute {
         route_id: route.id.clone(),
+        reactivate_after: None,
+        stay: false,
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         commit: true,
@@ -54,6 +64,10 @@ async fn create_route_and_update_server() -> Result {
     let out = cmds::route::activate_route(ActivateRoute {
         route_id: route.id.clone(),
         keypair: keypair_path.clone(),
+        no_preflight: false,
+        force: false,
+        backup_dir: None,
+        backup_retention: 20,
         config_host: config_host.clone(),
         config_pubkey: config_pubkey.clone(),
         commit: true,